@@ -9,7 +9,10 @@ use super::*;
 impl<'ctxt> CodeGen<'ctxt> {
     pub fn gen_array(&mut self, elem_exprs: &[SemanticExpression], elem_type: &SemanticType) -> Result<GenValue<'ctxt>, CodeGenError> {
         let elems = elem_exprs.iter()
-            .map(|expr| self.gen_eval(expr))
+            .map(|expr| {
+                let elem = self.gen_eval(expr)?;
+                self.coerce(elem, elem_type)
+            })
             .collect::<Result<Vec<GenValue<'ctxt>>, CodeGenError>>()?;
 
         // Get the LLVM type for the array elements
@@ -17,10 +20,14 @@ impl<'ctxt> CodeGen<'ctxt> {
 
         let type_info = match elem_type.kind() {
             SemanticTypeKind::Integer => self.runtime.int_type_info.as_pointer_value(),
+            SemanticTypeKind::Long => self.runtime.long_type_info.as_pointer_value(),
+            SemanticTypeKind::Float => self.runtime.float_type_info.as_pointer_value(),
             SemanticTypeKind::Bool => self.runtime.bool_type_info.as_pointer_value(),
             SemanticTypeKind::String => self.runtime.string_type_info.as_pointer_value(),
             SemanticTypeKind::NamedStruct(struct_id, _) => self.struct_info[&struct_id].type_info.as_pointer_value(),
             SemanticTypeKind::Array(_) => self.runtime.array_type_info.as_pointer_value(),
+            SemanticTypeKind::Enum(enum_id, _) => self.enum_info[&enum_id].type_info.as_pointer_value(),
+            SemanticTypeKind::Ref(_, _) | SemanticTypeKind::Optional(_) => self.get_type_info(elem_type).as_pointer_value(),
             _ => self.ptr_type().const_null(),
         };
         
@@ -117,6 +124,7 @@ impl<'ctxt> CodeGen<'ctxt> {
             panic!("Expected array value");
         };
 
+        let elem = self.coerce(elem, &elem_type)?;
         self.add_ref(&elem)?;
         let elem_ptr = self.builder.build_alloca(
             self.llvm_basic_type(&elem_type),
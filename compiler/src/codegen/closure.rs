@@ -1,6 +1,6 @@
 use inkwell::{types::{BasicMetadataTypeEnum, BasicType, StructType}, values::{AnyValue, BasicMetadataValueEnum, BasicValue, FunctionValue, GlobalValue, ValueKind}};
 
-use crate::{codegen::{CodeGen, CodeGenError, data::GenValue}, semantics::{Ownership, SemanticClosure, SemanticClosureBody, SemanticExpression, SemanticQuery, SemanticTypeKind}};
+use crate::{codegen::{CodeGen, CodeGenError, data::GenValue, function::instance_key}, semantics::{Ownership, SemanticClosure, SemanticClosureBody, SemanticExpression, SemanticQuery, SemanticTypeKind}};
 
 pub(super) struct GenClosureInfo<'ctxt> {
 	pub(super) llvm_fn: FunctionValue<'ctxt>,
@@ -10,52 +10,69 @@ pub(super) struct GenClosureInfo<'ctxt> {
 
 impl<'ctxt> CodeGen<'ctxt> {
     pub fn declare_closure(&mut self, closure: &SemanticClosure) -> Result<(), CodeGenError> {
-		// Closure context (captured variables)
-		let captured_llvm_types = closure.captured_variables.iter()
-			.map(|(var_id, _)| &self.program.variables[var_id].sem_type)
-			.collect::<Vec<_>>();
-		let (context_type, context_type_info) = if !captured_llvm_types.is_empty() {
-			let (_type, _type_info) = self.gen_struct_type_info(
-				&format!("__ql__context_{}", closure.id),
-				captured_llvm_types.as_slice()
-			)?;
-			(Some(_type), Some(_type_info))
-		} else {
-			(None, None)
-		};
-
-		let mut llvm_param_types: Vec<BasicMetadataTypeEnum> = vec![self.ptr_type().into(); 2];
-		llvm_param_types.extend(
-			closure.param_ids.iter()
-			.map(|p| BasicMetadataTypeEnum::from(
-				self.llvm_basic_type(&self.program.variables[p].sem_type)
-			))
-		);
-		let llvm_fn_type = match closure.return_type.kind() {
-			SemanticTypeKind::Void => {
-				self.void_type().fn_type(&llvm_param_types, false)
-			}
-			_ => {
-				let llvm_return_type = self.llvm_basic_type(&closure.return_type);
-				llvm_return_type.fn_type(&llvm_param_types, false)
-			},
-		};
-
-		let fn_name = format!("__ql__closure_{}", closure.id);
-		let llvm_fn = self.module.add_function(&fn_name, llvm_fn_type, None);
-
-		self.closure_info.insert(closure.id, GenClosureInfo {
-			llvm_fn,
-			context_type,
-			context_type_info,
-		});
+		for type_args in self.function_instances(closure.generic_function_id) {
+			self.type_args = type_args;
+			let key = instance_key(&self.type_args);
+
+			// Closure context (captured variables)
+			let captured_llvm_types = closure.captured_variables.iter()
+				.map(|(var_id, _)| &self.program.variables[var_id].sem_type)
+				.collect::<Vec<_>>();
+			let (context_type, context_type_info) = if !captured_llvm_types.is_empty() {
+				let (_type, _type_info) = self.gen_struct_type_info(
+					&format!("__ql__context_{}{}", closure.id, key),
+					captured_llvm_types.as_slice()
+				)?;
+				(Some(_type), Some(_type_info))
+			} else {
+				(None, None)
+			};
+
+			let mut llvm_param_types: Vec<BasicMetadataTypeEnum> = vec![self.ptr_type().into(); 2];
+			llvm_param_types.extend(
+				closure.param_ids.iter()
+				.map(|p| BasicMetadataTypeEnum::from(
+					self.llvm_basic_type(&self.program.variables[p].sem_type)
+				))
+			);
+			let return_type = self.resolve_type(&closure.return_type);
+			let llvm_fn_type = match return_type.kind() {
+				SemanticTypeKind::Void => {
+					self.void_type().fn_type(&llvm_param_types, false)
+				}
+				_ => {
+					let llvm_return_type = self.llvm_basic_type(&return_type);
+					llvm_return_type.fn_type(&llvm_param_types, false)
+				},
+			};
+
+			let fn_name = format!("__ql__closure_{}{}", closure.id, key);
+			let llvm_fn = self.module.add_function(&fn_name, llvm_fn_type, None);
+
+			self.closure_info.insert((closure.id, key), GenClosureInfo {
+				llvm_fn,
+				context_type,
+				context_type_info,
+			});
+		}
 
+		self.type_args = vec![];
 		Ok(())
 	}
 
 	pub fn define_closure(&mut self, closure: &SemanticClosure) -> Result<(), CodeGenError> {
-		let closure_info = &self.closure_info[&closure.id];
+		for type_args in self.function_instances(closure.generic_function_id) {
+			self.type_args = type_args;
+			self.define_closure_instance(closure)?;
+		}
+		self.type_args = vec![];
+		Ok(())
+	}
+
+	fn define_closure_instance(&mut self, closure: &SemanticClosure) -> Result<(), CodeGenError> {
+		let closure_info = &self.closure_info[&(closure.id, instance_key(&self.type_args))];
 		self.cur_fn = Some(closure_info.llvm_fn);
+		self.cur_return_type = Some(self.resolve_type(&closure.return_type));
 		let entry_block = self.context.append_basic_block(closure_info.llvm_fn, "entry");
 		self.builder.position_at_end(entry_block);
 		
@@ -103,12 +120,13 @@ impl<'ctxt> CodeGen<'ctxt> {
             },
         }
 		self.cur_fn = None;
+		self.cur_return_type = None;
 		Ok(())
 	}
 
 	pub fn gen_callable(&mut self, closure_id: u32) -> Result<GenValue<'ctxt>, CodeGenError> {
 		let closure = &self.program.closures[&closure_id];
-		let closure_info = &self.closure_info[&closure_id];
+		let closure_info = &self.closure_info[&(closure_id, instance_key(&self.type_args))];
 
 		// Create the callable
 		let callable_type_val = match &closure.body {
@@ -170,7 +188,7 @@ impl<'ctxt> CodeGen<'ctxt> {
 	}
 
 	pub fn gen_indirect_call(&mut self, function_expr: &SemanticExpression, args: &[SemanticExpression]) -> Result<GenValue<'ctxt>, CodeGenError> {
-		let SemanticTypeKind::Callable(param_types, return_type) = &function_expr.sem_type.kind() else {
+		let SemanticTypeKind::Callable(param_types, return_type) = &self.resolve_type(&function_expr.sem_type).kind() else {
 			panic!("Expected callable type for indirect call");
 		};
 
@@ -198,7 +216,8 @@ impl<'ctxt> CodeGen<'ctxt> {
 		let mut llvm_param_types: Vec<BasicMetadataTypeEnum> = vec![self.ptr_type().into(); 2];
 		let mut arg_values: Vec<BasicMetadataValueEnum> = vec![context_ptr.into(), prepared_stmt.into()];
 		for (arg, param_type) in args.iter().zip(param_types) {
-			let arg_val = self.gen_eval(arg)?.as_llvm_basic_value();
+			let arg_val = self.gen_eval(arg)?;
+			let arg_val = self.coerce(arg_val, param_type)?.as_llvm_basic_value();
 			arg_values.push(arg_val.into());
 			llvm_param_types.push(self.llvm_basic_type(param_type).into());
 		}
@@ -1,10 +1,11 @@
-use inkwell::{basic_block::BasicBlock, values::{BasicValue, IntValue}};
+use inkwell::{basic_block::BasicBlock, intrinsics::Intrinsic, values::{AnyValue, BasicValue, IntValue}, IntPredicate};
 
-use super::{CodeGen, CodeGenError};
-use crate::semantics::{SemanticBlock, SemanticConditionalBranch, SemanticExpression, SemanticTypeKind};
+use super::{CodeGen, CodeGenError, GenValue};
+use crate::semantics::{SemanticBlock, SemanticConditionalBranch, SemanticExpression, SemanticRange, SemanticTypeKind};
 
 pub(super) struct GenLoopInfo<'a> {
-    cond_block: BasicBlock<'a>,
+    // Where continue jumps to, before the next iteration's condition
+    continue_block: BasicBlock<'a>,
     after_block: BasicBlock<'a>
 }
 
@@ -20,7 +21,10 @@ impl<'ctxt> CodeGen<'ctxt> {
         struct BranchGenInfo<'a> {
             cond_value: IntValue<'a>,
             cond_block: BasicBlock<'a>,
+            // Where the condition and body end, as either may branch, such as for `and` and `or`
+            cond_end_block: BasicBlock<'a>,
             body_block: BasicBlock<'a>,
+            body_end_block: BasicBlock<'a>,
             body_terminates: bool,
         }
 
@@ -30,6 +34,7 @@ impl<'ctxt> CodeGen<'ctxt> {
             let cond_block = self.context.append_basic_block(cur_fn, &format!("branch{}_cond", i+1));
             self.builder.position_at_end(cond_block);
             let cond_value = self.gen_eval(&branch.condition)?.as_llvm_basic_value().into_int_value();
+            let cond_end_block = self.builder.get_insert_block().unwrap();
 
             let body_block = self.context.append_basic_block(cur_fn, &format!("branch{}_body", i+1));
             self.builder.position_at_end(body_block);
@@ -38,7 +43,9 @@ impl<'ctxt> CodeGen<'ctxt> {
             blocks.push(BranchGenInfo {
                 cond_value,
                 cond_block,
+                cond_end_block,
                 body_block,
+                body_end_block: self.builder.get_insert_block().unwrap(),
                 body_terminates: branch.body.terminates,
             });
         }
@@ -51,16 +58,18 @@ impl<'ctxt> CodeGen<'ctxt> {
             blocks.push(BranchGenInfo {
                 cond_value: self.context.bool_type().const_int(1, false),
                 cond_block: else_jump_block,
+                cond_end_block: else_jump_block,
                 body_block: else_body_block,
+                body_end_block: self.builder.get_insert_block().unwrap(),
                 body_terminates: else_block.terminates,
             });
         }
 
         // Second pass: link blocks together
         for window in blocks.windows(2) {
-            let BranchGenInfo { cond_value, cond_block, body_block, .. } = window[0];
+            let BranchGenInfo { cond_value, cond_end_block, body_block, .. } = window[0];
             let BranchGenInfo { cond_block: next_cond_block, .. } = window[1];
-            self.builder.position_at_end(cond_block);
+            self.builder.position_at_end(cond_end_block);
             self.builder.build_conditional_branch(cond_value, body_block, next_cond_block)?;
         }
 
@@ -78,16 +87,16 @@ impl<'ctxt> CodeGen<'ctxt> {
         
         let BranchGenInfo {
             cond_value: last_cond_value,
-            cond_block: last_cond_block,
+            cond_end_block: last_cond_block,
             body_block: last_body_block, ..
         } = blocks.last().unwrap();
         
         // If not all branches terminate, create a merge block
         if !all_branches_terminate {
             let merge_block = self.context.append_basic_block(cur_fn, "merge_branches");
-            for BranchGenInfo { body_block, body_terminates, .. } in &blocks {
+            for BranchGenInfo { body_end_block, body_terminates, .. } in &blocks {
                 if !*body_terminates {
-                    self.builder.position_at_end(*body_block);
+                    self.builder.position_at_end(*body_end_block);
                     self.builder.build_unconditional_branch(merge_block)?;
                 }
             }
@@ -114,7 +123,7 @@ impl<'ctxt> CodeGen<'ctxt> {
         let cond_block = self.context.append_basic_block(cur_fn, "loop_cond");
         let entry_block = self.context.append_basic_block(cur_fn, "loop_body_entry");
         let after_block = self.context.append_basic_block(cur_fn, "after_loop");
-        self.loop_info.insert(id, GenLoopInfo { cond_block, after_block });
+        self.loop_info.insert(id, GenLoopInfo { continue_block: cond_block, after_block });
 
         // Build loop conditional branch
         self.builder.build_unconditional_branch(cond_block)?;
@@ -135,6 +144,149 @@ impl<'ctxt> CodeGen<'ctxt> {
         Ok(())
     }
 
+    pub fn gen_array_loop(
+        &mut self,
+        array_variable_id: u32,
+        index_variable_id: Option<u32>,
+        element_variable_id: u32,
+        body: &SemanticBlock,
+        id: u32,
+    ) -> Result<(), CodeGenError> {
+        let cur_fn = self.cur_fn.unwrap();
+
+        // Loops without an index variable still count through the array
+        let index_ptr = match index_variable_id {
+            Some(index_variable_id) => {
+                self.define_var(index_variable_id, GenValue::Integer(self.int_type().const_zero()))?;
+                self.llvm_variables[&index_variable_id]
+            }
+            None => {
+                let index_ptr = self.builder.build_alloca(self.int_type(), "loop_index")?;
+                self.builder.build_store(index_ptr, self.int_type().const_zero())?;
+                index_ptr
+            }
+        };
+        let element_variable = &self.program.variables[&element_variable_id];
+        let element_ptr = self.builder.build_alloca(
+            self.llvm_basic_type(&element_variable.sem_type),
+            &element_variable.name
+        )?;
+        self.llvm_variables.insert(element_variable_id, element_ptr);
+
+        let cond_block = self.context.append_basic_block(cur_fn, "for_cond");
+        let entry_block = self.context.append_basic_block(cur_fn, "for_body_entry");
+        let increment_block = self.context.append_basic_block(cur_fn, "for_increment");
+        let after_block = self.context.append_basic_block(cur_fn, "after_for");
+        self.loop_info.insert(id, GenLoopInfo { continue_block: increment_block, after_block });
+
+        // The length is read on every iteration, as the body may append to or pop from the array
+        self.builder.build_unconditional_branch(cond_block)?;
+        self.builder.position_at_end(cond_block);
+        let index = self.builder.build_load(self.int_type(), index_ptr, "index")?.into_int_value();
+        let array = self.load_var(array_variable_id)?;
+        let GenValue::Integer(length) = self.gen_array_length(array)? else {
+            panic!("Expected integer length");
+        };
+        let in_bounds = self.builder.build_int_compare(IntPredicate::SLT, index, length, "in_bounds")?;
+        self.builder.build_conditional_branch(in_bounds, entry_block, after_block)?;
+
+        // The element takes a reference of its own, as the body may remove it from the array. It is
+        // dropped with the rest of the loop's scope at the end of each iteration
+        self.builder.position_at_end(entry_block);
+        let array = self.load_var(array_variable_id)?;
+        let element = self.gen_array_index(array, GenValue::Integer(index))?;
+        self.add_ref(&element)?;
+        self.builder.build_store(element_ptr, element.as_llvm_basic_value())?;
+        self.gen_block(body)?;
+        if !body.terminates {
+            self.builder.build_unconditional_branch(increment_block)?;
+        }
+
+        let last_body_block = cur_fn.get_last_basic_block().unwrap();
+        let _ = increment_block.move_after(last_body_block);
+        self.builder.position_at_end(increment_block);
+        let index = self.builder.build_load(self.int_type(), index_ptr, "index")?.into_int_value();
+        let next_index = self.builder.build_int_add(index, self.int_type().const_int(1, false), "next_index")?;
+        self.builder.build_store(index_ptr, next_index)?;
+        self.builder.build_unconditional_branch(cond_block)?;
+
+        // Both the loop condition and breaks leave through here, with the array still held
+        let _ = after_block.move_after(increment_block);
+        self.builder.position_at_end(after_block);
+        self.drop_var(array_variable_id)?;
+        Ok(())
+    }
+
+    pub fn gen_range_loop(
+        &mut self,
+        variable_id: u32,
+        range: &SemanticRange,
+        body: &SemanticBlock,
+        id: u32,
+    ) -> Result<(), CodeGenError> {
+        let cur_fn = self.cur_fn.unwrap();
+        let start_value = self.gen_eval(&range.start)?;
+        let end_value = self.gen_eval(&range.end)?.as_llvm_basic_value().into_int_value();
+        let int_type = end_value.get_type();
+        let step_value = match &range.step {
+            Some((location, step_expr)) => {
+                let step_value = self.gen_eval(step_expr)?.as_llvm_basic_value().into_int_value();
+                let non_positive = self.builder.build_int_compare(
+                    IntPredicate::SLE,
+                    step_value,
+                    int_type.const_zero(),
+                    "non_positive_step"
+                )?;
+                self.gen_error_check(non_positive, self.runtime.invalid_range_step, location)?;
+                step_value
+            }
+            None => int_type.const_int(1, false),
+        };
+        self.define_var(variable_id, start_value)?;
+        let variable_ptr = self.llvm_variables[&variable_id];
+
+        let cond_block = self.context.append_basic_block(cur_fn, "range_cond");
+        let entry_block = self.context.append_basic_block(cur_fn, "range_body_entry");
+        let increment_block = self.context.append_basic_block(cur_fn, "range_increment");
+        let after_block = self.context.append_basic_block(cur_fn, "after_range");
+        self.loop_info.insert(id, GenLoopInfo { continue_block: increment_block, after_block });
+
+        self.builder.build_unconditional_branch(cond_block)?;
+        self.builder.position_at_end(cond_block);
+        let current = self.builder.build_load(int_type, variable_ptr, "current")?.into_int_value();
+        let predicate = if range.inclusive { IntPredicate::SLE } else { IntPredicate::SLT };
+        let in_range = self.builder.build_int_compare(predicate, current, end_value, "in_range")?;
+        self.builder.build_conditional_branch(in_range, entry_block, after_block)?;
+
+        self.builder.position_at_end(entry_block);
+        self.gen_block(body)?;
+        if !body.terminates {
+            self.builder.build_unconditional_branch(increment_block)?;
+        }
+
+        // Stepping past the largest value ends the loop instead of wrapping around to the start
+        let last_body_block = cur_fn.get_last_basic_block().unwrap();
+        let _ = increment_block.move_after(last_body_block);
+        self.builder.position_at_end(increment_block);
+        let current = self.builder.build_load(int_type, variable_ptr, "current")?.into_int_value();
+        let add_with_overflow = Intrinsic::find("llvm.sadd.with.overflow").unwrap()
+            .get_declaration(&self.module, &[int_type.into()])
+            .unwrap();
+        let result = self.builder.build_call(
+            add_with_overflow,
+            &[current.into(), step_value.into()],
+            "stepped"
+        )?.as_any_value_enum().into_struct_value();
+        let next = self.builder.build_extract_value(result, 0, "next")?;
+        let overflowed = self.builder.build_extract_value(result, 1, "overflowed")?.into_int_value();
+        self.builder.build_store(variable_ptr, next)?;
+        self.builder.build_conditional_branch(overflowed, after_block, cond_block)?;
+
+        let _ = after_block.move_after(increment_block);
+        self.builder.position_at_end(after_block);
+        Ok(())
+    }
+
     pub fn gen_block(&mut self, block: &SemanticBlock) -> Result<(), CodeGenError> {
         for stmt in &block.statements {
             self.gen_stmt(stmt)?;
@@ -154,6 +306,10 @@ impl<'ctxt> CodeGen<'ctxt> {
 		let return_value: Option<&dyn BasicValue> = match value {
 			Some(val) => {
 				let return_val = self.gen_eval(val)?;
+				let return_val = match &self.cur_return_type {
+					Some(return_type) => self.coerce(return_val, return_type)?,
+					None => return_val,
+				};
 				if val.sem_type.kind() != SemanticTypeKind::Void {
 					self.add_ref(&return_val)?;
 					Some(&return_val.as_llvm_basic_value())
@@ -168,6 +324,7 @@ impl<'ctxt> CodeGen<'ctxt> {
 			self.drop_var(*var_id)?;
 		}
         self.vars_to_drop.clear();
+        self.rollback_exited_transactions()?;
 		self.builder.build_return(return_value)?;
 		Ok(())
 	}
@@ -178,17 +335,19 @@ impl<'ctxt> CodeGen<'ctxt> {
             self.drop_var(*var_id)?;
         }
         self.vars_to_drop.clear();
+        self.rollback_exited_transactions()?;
         self.builder.build_unconditional_branch(after_block)?;
         Ok(())
     }
 
     pub fn gen_continue(&mut self, loop_id: u32) -> Result<(), CodeGenError> {
-        let GenLoopInfo { cond_block, .. } = self.loop_info[&loop_id];
+        let GenLoopInfo { continue_block, .. } = self.loop_info[&loop_id];
         for var_id in &self.vars_to_drop {
             self.drop_var(*var_id)?;
         }
         self.vars_to_drop.clear();
-        self.builder.build_unconditional_branch(cond_block)?;
+        self.rollback_exited_transactions()?;
+        self.builder.build_unconditional_branch(continue_block)?;
         Ok(())
     }
 }
\ No newline at end of file
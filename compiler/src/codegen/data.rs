@@ -1,4 +1,4 @@
-use inkwell::{types::BasicTypeEnum, values::{BasicValueEnum, IntValue, PointerValue, StructValue}};
+use inkwell::{types::BasicTypeEnum, values::{BasicValueEnum, FloatValue, IntValue, PointerValue, StructValue}};
 
 use crate::semantics::{Ownership, SemanticType, SemanticTypeKind};
 
@@ -7,6 +7,7 @@ use super::{CodeGen, CodeGenError};
 #[derive(Clone, PartialEq)]
 pub enum GenValue<'a> {
     Integer(IntValue<'a>),
+    Float(FloatValue<'a>),
     Bool(IntValue<'a>),
     String {
         value: PointerValue<'a>,
@@ -22,17 +23,30 @@ pub enum GenValue<'a> {
         struct_id: u32,
         ownership: Ownership
     },
+    Enum {
+        value: StructValue<'a>,
+        enum_id: u32,
+        ownership: Ownership
+    },
     Callable {
         value: PointerValue<'a>,
         ownership: Ownership
     },
+    Optional {
+        value: StructValue<'a>,
+        inner_type: SemanticType,
+        ownership: Ownership
+    },
     Void
 }
 
 impl<'a> GenValue<'a> {
     pub fn new(sem_type: &SemanticType, llvm_value: BasicValueEnum<'a>, ownership: Ownership) -> Self {
         match sem_type.kind() {
-            SemanticTypeKind::Integer => GenValue::Integer(llvm_value.into_int_value()),
+            SemanticTypeKind::Integer | SemanticTypeKind::Long | SemanticTypeKind::Ref(_, _) => {
+                GenValue::Integer(llvm_value.into_int_value())
+            }
+            SemanticTypeKind::Float => GenValue::Float(llvm_value.into_float_value()),
             SemanticTypeKind::Bool => GenValue::Bool(llvm_value.into_int_value()),
             SemanticTypeKind::String => GenValue::String {
                 value: llvm_value.into_pointer_value(),
@@ -48,10 +62,20 @@ impl<'a> GenValue<'a> {
                 struct_id,
                 ownership: ownership
             },
+            SemanticTypeKind::Enum(enum_id, _) => GenValue::Enum {
+                value: llvm_value.into_struct_value(),
+                enum_id,
+                ownership: ownership
+            },
             SemanticTypeKind::Callable(_,_) => GenValue::Callable {
                 value: llvm_value.into_pointer_value(),
                 ownership: ownership
             },
+            SemanticTypeKind::Optional(inner_type) => GenValue::Optional {
+                value: llvm_value.into_struct_value(),
+                inner_type,
+                ownership: ownership
+            },
             SemanticTypeKind::Void => GenValue::Void,
             _ => panic!("Incomplete type found in semantic IR"),
         }
@@ -62,7 +86,9 @@ impl<'a> GenValue<'a> {
             GenValue::String { ownership, .. }
             | GenValue::Array { ownership, .. }
             | GenValue::Struct { ownership, .. }
-            | GenValue::Callable { ownership, .. } => *ownership,
+            | GenValue::Enum { ownership, .. }
+            | GenValue::Callable { ownership, .. }
+            | GenValue::Optional { ownership, .. } => *ownership,
             _ => Ownership::Trivial,
         }
     }
@@ -70,11 +96,14 @@ impl<'a> GenValue<'a> {
     pub fn as_llvm_basic_value(&self) -> BasicValueEnum<'a> {
         match self {
             GenValue::Integer(int_val) => BasicValueEnum::IntValue(*int_val),
+            GenValue::Float(float_val) => BasicValueEnum::FloatValue(*float_val),
             GenValue::Bool(int_val) => BasicValueEnum::IntValue(*int_val),
             GenValue::String { value: str_val, .. } => BasicValueEnum::PointerValue(*str_val),
             GenValue::Array { value: arr_val, .. } => BasicValueEnum::PointerValue(*arr_val),
             GenValue::Struct { value: struct_val, .. } => BasicValueEnum::StructValue(*struct_val),
+            GenValue::Enum { value: enum_val, .. } => BasicValueEnum::StructValue(*enum_val),
             GenValue::Callable { value: callable_val, .. } => BasicValueEnum::PointerValue(*callable_val),
+            GenValue::Optional { value: optional_val, .. } => BasicValueEnum::StructValue(*optional_val),
             GenValue::Void => panic!("Unexpected void value"),
         }
     }
@@ -106,6 +135,15 @@ impl<'ctxt> CodeGen<'ctxt> {
                     )?;
                 }
             }
+            GenValue::Enum { value: enum_value, enum_id, ownership: Ownership::Borrowed } => {
+                if let Some(copy_fn) = self.enum_info[&enum_id].copy_fn {
+                    self.builder.build_call(
+                        copy_fn,
+                        &[(*enum_value).into()],
+                        "enum_copy"
+                    )?;
+                }
+            }
             GenValue::Callable { value: callable_ptr, ownership: Ownership::Borrowed } => {
                 self.builder.build_call(
                     self.runtime.callable_add_ref,
@@ -113,6 +151,11 @@ impl<'ctxt> CodeGen<'ctxt> {
                     "callable_add_ref"
                 )?;
             }
+            GenValue::Optional { value: optional_value, inner_type, ownership: Ownership::Borrowed } => {
+                if inner_type.can_be_owned() {
+                    self.build_if_present(*optional_value, inner_type, |value| self.add_ref(&value))?;
+                }
+            }
             _ => { }
         }
         Ok(())
@@ -143,6 +186,15 @@ impl<'ctxt> CodeGen<'ctxt> {
                     )?;
                 }
             }
+            GenValue::Enum { value: enum_value, enum_id, .. } => {
+                if let Some(drop_fn) = self.enum_info[&enum_id].drop_fn {
+                    self.builder.build_call(
+                        drop_fn,
+                        &[enum_value.into()],
+                        "enum_drop"
+                    )?;
+                }
+            }
             GenValue::Callable { value: callable_ptr, .. } => {
                 self.builder.build_call(
                     self.runtime.callable_remove_ref,
@@ -150,6 +202,11 @@ impl<'ctxt> CodeGen<'ctxt> {
                     "callable_remove_ref"
                 )?;
             }
+            GenValue::Optional { value: optional_value, inner_type, .. } => {
+                if inner_type.can_be_owned() {
+                    self.build_if_present(optional_value, &inner_type, |value| self.remove_ref(value))?;
+                }
+            }
             _ => { }
         }
         Ok(())
@@ -163,13 +220,18 @@ impl<'ctxt> CodeGen<'ctxt> {
     }
 
     pub fn llvm_basic_type(&self, sem_type: &SemanticType) -> BasicTypeEnum<'ctxt> {
-        match sem_type.kind() {
+        match self.resolve_type(sem_type).kind() {
             SemanticTypeKind::Integer => self.int_type().into(),
+            SemanticTypeKind::Long => self.long_type().into(),
+            SemanticTypeKind::Ref(table_id, _) => self.llvm_basic_type(&self.ref_key_type(table_id)),
+            SemanticTypeKind::Float => self.float_type().into(),
             SemanticTypeKind::Bool => self.bool_type().into(),
             SemanticTypeKind::String => self.ptr_type().into(),
             SemanticTypeKind::Array(_) => self.ptr_type().into(),
             SemanticTypeKind::NamedStruct(id, _) => self.struct_info[&id].struct_type.into(),
+            SemanticTypeKind::Enum(id, _) => self.enum_info[&id].enum_type.into(),
             SemanticTypeKind::Callable(_, _) => self.ptr_type().into(),
+            SemanticTypeKind::Optional(inner_type) => self.optional_struct_type(&inner_type).into(),
             _ => panic!("Incomplete type found in semantic IR"),
         }
     }
@@ -1,9 +1,39 @@
-use inkwell::{AddressSpace, values::{AnyValue, BasicValue, FunctionValue, PointerValue}};
+use inkwell::{AddressSpace, builder::BuilderError, values::{AnyValue, BasicMetadataValueEnum, BasicValue, CallSiteValue, FunctionValue, GlobalValue, IntValue, PointerValue}};
 
-use crate::{codegen::{data::GenValue}, semantics::{Ownership, SemanticDatasource, SemanticQuery, SemanticType, SemanticTypeKind, WhereClause}};
+use crate::{codegen::{data::GenValue, runtime::{AggregateType, WhereNodeType}}, semantics::{AggregateFunction, Join, JoinCondition, Ownership, Returning, SelectColumn, SelectShape, SemanticBlock, SemanticDatasource, SemanticQuery, SemanticTable, SemanticType, SemanticTypeKind, WhereClause}, tokens::ComparisonType};
 
 use super::{CodeGen, CodeGenError};
 
+pub(super) struct GenTransactionInfo<'a> {
+    database_ptr: PointerValue<'a>,
+    is_savepoint: IntValue<'a>,
+}
+
+impl From<ComparisonType> for WhereNodeType {
+    fn from(op: ComparisonType) -> Self {
+        match op {
+            ComparisonType::Equal => WhereNodeType::Equal,
+            ComparisonType::NotEqual => WhereNodeType::NotEqual,
+            ComparisonType::GreaterThan => WhereNodeType::GreaterThan,
+            ComparisonType::LessThan => WhereNodeType::LessThan,
+            ComparisonType::GreaterThanOrEqual => WhereNodeType::GreaterThanOrEqual,
+            ComparisonType::LessThanOrEqual => WhereNodeType::LessThanOrEqual,
+        }
+    }
+}
+
+impl From<AggregateFunction> for AggregateType {
+    fn from(function: AggregateFunction) -> Self {
+        match function {
+            AggregateFunction::Count => AggregateType::Count,
+            AggregateFunction::Sum => AggregateType::Sum,
+            AggregateFunction::Average => AggregateType::Average,
+            AggregateFunction::Minimum => AggregateType::Minimum,
+            AggregateFunction::Maximum => AggregateType::Maximum,
+        }
+    }
+}
+
 impl<'ctxt> CodeGen<'ctxt> {
     fn place_onto_stack(
         &mut self,
@@ -15,6 +45,217 @@ impl<'ctxt> CodeGen<'ctxt> {
         Ok(alloca)
     }
 
+    fn gen_where_condition(
+        &self,
+        column_name_strs: &[GlobalValue<'ctxt>],
+        node_type: WhereNodeType,
+        column_index: u32,
+    ) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        let column_name_str = column_name_strs[column_index as usize];
+        let where_node = self.builder.build_call(
+            self.runtime.where_node_new_condition,
+            &[
+                self.int_type().const_int(node_type as u64, false).into(),
+                column_name_str.as_pointer_value().into(),
+            ],
+            "where_condition"
+        )?.as_any_value_enum().into_pointer_value();
+        Ok(where_node)
+    }
+
+    fn gen_where_logical(
+        &self,
+        node_type: WhereNodeType,
+        left: PointerValue<'ctxt>,
+        right: PointerValue<'ctxt>,
+    ) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        let where_node = self.builder.build_call(
+            self.runtime.where_node_new_logical,
+            &[
+                self.int_type().const_int(node_type as u64, false).into(),
+                left.into(),
+                right.into(),
+            ],
+            "where_logical"
+        )?.as_any_value_enum().into_pointer_value();
+        Ok(where_node)
+    }
+
+    // Builds the runtime representation of a where clause, to be rendered into SQL by a query plan
+    fn gen_where_node(
+        &self,
+        column_name_strs: &[GlobalValue<'ctxt>],
+        where_clause: &WhereClause
+    ) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        match where_clause {
+            WhereClause::Comparison { column_index, op, .. } => {
+                self.gen_where_condition(column_name_strs, (*op).into(), *column_index)
+            }
+            WhereClause::Like { column_index, .. } => {
+                self.gen_where_condition(column_name_strs, WhereNodeType::Like, *column_index)
+            }
+            WhereClause::In { column_index, .. } => {
+                self.gen_where_condition(column_name_strs, WhereNodeType::In, *column_index)
+            }
+            WhereClause::IsNull { column_index, negated } => {
+                let node_type = if *negated { WhereNodeType::IsNotNull } else { WhereNodeType::IsNull };
+                self.gen_where_condition(column_name_strs, node_type, *column_index)
+            }
+            WhereClause::And(left, right) => {
+                let left_node = self.gen_where_node(column_name_strs, left)?;
+                let right_node = self.gen_where_node(column_name_strs, right)?;
+                self.gen_where_logical(WhereNodeType::And, left_node, right_node)
+            }
+            WhereClause::Or(left, right) => {
+                let left_node = self.gen_where_node(column_name_strs, left)?;
+                let right_node = self.gen_where_node(column_name_strs, right)?;
+                self.gen_where_logical(WhereNodeType::Or, left_node, right_node)
+            }
+            WhereClause::Not(inner) => {
+                let inner_node = self.gen_where_node(column_name_strs, inner)?;
+                self.gen_where_logical(WhereNodeType::Not, inner_node, self.ptr_type().const_null())
+            }
+        }
+    }
+
+    // Field names of a struct as C strings, naming the columns of a select's result rows
+    fn gen_field_name_strs(&self, struct_id: u32) -> Result<Vec<GlobalValue<'ctxt>>, CodeGenError> {
+        let sem_struct = &self.program.structs[&struct_id];
+        let field_name_strs = sem_struct.field_order.iter()
+            .map(|field_name| self.builder.build_global_string_ptr(field_name, "field_name"))
+            .collect::<Result<Vec<GlobalValue>, BuilderError>>()?;
+        Ok(field_name_strs)
+    }
+
+    // Column names of every table a select reads from, in the order the semantic IR indexes them.
+    // Joins qualify each column with its table name, since tables may share column names
+    fn gen_query_column_strs(&self, table_id: u32, joins: &[Join]) -> Result<Vec<GlobalValue<'ctxt>>, CodeGenError> {
+        if joins.is_empty() {
+            return Ok(self.table_info[&table_id].column_name_strs.clone());
+        }
+
+        let mut column_strs = vec![];
+        let table_ids = std::iter::once(table_id).chain(joins.iter().map(|join| join.table_id));
+        for table_id in table_ids {
+            let table = &self.program.tables[&table_id];
+            let table_struct = &self.program.structs[&table.struct_id];
+            for column_name in &table_struct.field_order {
+                let column_str = self.builder.build_global_string_ptr(
+                    &format!("{}.{}", table.name, column_name),
+                    "column_name"
+                )?;
+                column_strs.push(column_str);
+            }
+        }
+        Ok(column_strs)
+    }
+
+    fn gen_join_condition(
+        &self,
+        column_name_strs: &[GlobalValue<'ctxt>],
+        conditions: &[JoinCondition],
+    ) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        let mut condition_nodes = vec![];
+        for condition in conditions {
+            let node_type: WhereNodeType = condition.op.into();
+            let condition_node = self.builder.build_call(
+                self.runtime.where_node_new_column_comparison,
+                &[
+                    self.int_type().const_int(node_type as u64, false).into(),
+                    column_name_strs[condition.left_column as usize].as_pointer_value().into(),
+                    column_name_strs[condition.right_column as usize].as_pointer_value().into(),
+                ],
+                "join_condition"
+            )?.as_any_value_enum().into_pointer_value();
+            condition_nodes.push(condition_node);
+        }
+
+        let mut condition_nodes = condition_nodes.into_iter();
+        let first_node = condition_nodes.next().expect("Join without conditions");
+        condition_nodes.try_fold(first_node, |left, right| {
+            self.gen_where_logical(WhereNodeType::And, left, right)
+        })
+    }
+
+    fn gen_returning_clause(&self, table_id: u32, returning: &Returning) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        let table_info = &self.table_info[&table_id];
+        let SemanticTypeKind::NamedStruct(result_struct_id, _) = returning.result_type.kind() else {
+            panic!("Expected NamedStruct result type");
+        };
+        let struct_info = &self.struct_info[&result_struct_id];
+        let returning_ptr = self.builder.build_call(
+            self.runtime.returning_clause_new,
+            &[struct_info.type_info.as_pointer_value().into()],
+            "returning_clause"
+        )?.as_any_value_enum().into_pointer_value();
+
+        for &column_index in &returning.columns {
+            let column_name_str = table_info.column_name_strs[column_index as usize];
+            self.builder.build_call(
+                self.runtime.returning_clause_add_column,
+                &[returning_ptr.into(), column_name_str.as_pointer_value().into()],
+                "returning_clause_add_column"
+            )?;
+        }
+        Ok(returning_ptr)
+    }
+
+    // Updates and deletes give the number of affected rows, or the rows of their returning clause
+    fn exec_mutation(
+        &self,
+        statement: PointerValue<'ctxt>,
+        returning: &Option<Returning>,
+        exec_fn: FunctionValue<'ctxt>,
+        exec_returning_fn: FunctionValue<'ctxt>,
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        match returning {
+            Some(returning) => {
+                let result = self.builder.build_call(
+                    exec_returning_fn,
+                    &[statement.into()],
+                    "exec_returning"
+                )?.as_any_value_enum().into_pointer_value();
+                Ok(GenValue::Array {
+                    value: result,
+                    elem_type: returning.result_type.clone(),
+                    ownership: Ownership::Owned,
+                })
+            }
+            None => {
+                let affected_rows = self.builder.build_call(
+                    exec_fn,
+                    &[statement.into()],
+                    "exec"
+                )?.as_any_value_enum().into_int_value();
+                Ok(GenValue::Integer(affected_rows))
+            }
+        }
+    }
+
+    fn bind_where_values(
+        &mut self,
+        statement: PointerValue<'ctxt>,
+        where_clause: &WhereClause,
+        bind_fn: FunctionValue<'ctxt>,
+    ) -> Result<(), CodeGenError> {
+        for (i, value) in where_clause.bound_values().into_iter().enumerate() {
+            let gen_value = self.gen_eval(value)?;
+            let value_ptr = self.place_onto_stack(&gen_value)?;
+            self.builder.build_call(
+                bind_fn,
+                &[
+                    statement.into(),
+                    self.int_type().const_int(i as u64, false).into(),
+                    self.get_type_info(&value.sem_type).as_pointer_value().into(),
+                    value_ptr.into(),
+                ],
+                &format!("bind_where_{}", i)
+            )?;
+            self.remove_if_owned(gen_value)?;
+        }
+        Ok(())
+    }
+
     pub(super) fn gen_database_ptr(&mut self, datasource: &SemanticDatasource) {
         let db_ptr_global = self.module.add_global(
             self.ptr_type(),
@@ -62,10 +303,96 @@ impl<'ctxt> CodeGen<'ctxt> {
             ],
             "init_dbs_call"
         )?;
-            
+
+        self.run_migrations()?;
+
+        // Tables are set up in declaration order, leaving out those standing for a table as an
+        // earlier migration left it
+        let mut tables = self.program.tables.values()
+            .filter(|table| table.migration_version.is_none())
+            .collect::<Vec<_>>();
+        tables.sort_by_key(|table| table.id);
+
+        // Create any missing tables of datasources declared with `create`
+        for table in &tables {
+            if self.program.datasources[&table.datasource_id].creates_tables {
+                let table_info = &self.table_info[&table.id];
+                let constraint_arr = self.gen_string_array(&table_info.column_constraint_strs, "create_table_constraint_arr")?;
+                self.gen_table_schema_call(table, self.runtime.create_table, &[constraint_arr.into()], "create_table")?;
+            }
+        }
+
+        // Check every table against the live database, refusing to run on any mismatch
+        if self.verify_schema {
+            let mut schema_matches = self.bool_type().const_int(1, false);
+            for table in &tables {
+                let table_matches = self.gen_table_schema_call(table, self.runtime.verify_table, &[], "verify_table")?
+                    .as_any_value_enum().into_int_value();
+                schema_matches = self.builder.build_and(schema_matches, table_matches, "schema_matches")?;
+            }
+            self.builder.build_call(
+                self.runtime.check_schema,
+                &[
+                    schema_matches.into(),
+                    self.context.i32_type().const_int(num_dbs as u64, false).into(),
+                    db_ptr_arr.into(),
+                ],
+                "check_schema"
+            )?;
+        }
+
         Ok(db_ptr_arr)
     }
 
+    // Builds a stack array of pointers to global strings, for runtime functions taking a char**
+    fn gen_string_array(&self, strs: &[GlobalValue<'ctxt>], name: &str) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        let arr_type = self.ptr_type().array_type(strs.len() as u32);
+        let arr = self.builder.build_alloca(arr_type, name)?;
+        for (i, str_global) in strs.iter().enumerate() {
+            let elem_ptr = unsafe { self.builder.build_gep(
+                arr_type,
+                arr,
+                &[
+                    self.context.i32_type().const_zero(),
+                    self.context.i32_type().const_int(i as u64, false)
+                ],
+                &format!("{}_ptr_{}", name, i)
+            )? };
+            self.builder.build_store(elem_ptr, str_global.as_pointer_value())?;
+        }
+        Ok(arr)
+    }
+
+    // Calls a runtime function taking a table's database, name, column names and row type,
+    // followed by any extra arguments
+    fn gen_table_schema_call(
+        &self,
+        table: &SemanticTable,
+        function: FunctionValue<'ctxt>,
+        extra_args: &[BasicMetadataValueEnum<'ctxt>],
+        name: &str,
+    ) -> Result<CallSiteValue<'ctxt>, CodeGenError> {
+        let table_info = &self.table_info[&table.id];
+        let struct_info = &self.struct_info[&table.struct_id];
+
+        let col_name_arr = self.gen_string_array(&table_info.column_name_strs, &format!("{}_col_name_arr", name))?;
+
+        let database_ptr = self.builder.build_load(
+            self.ptr_type(),
+            self.datasource_ptrs[&table.datasource_id],
+            "load_database_ptr"
+        )?.into_pointer_value();
+        let mut args: Vec<BasicMetadataValueEnum> = vec![
+            database_ptr.into(),
+            table_info.name_str.as_pointer_value().into(),
+            col_name_arr.into(),
+            struct_info.type_info.as_pointer_value().into(),
+        ];
+        args.extend_from_slice(extra_args);
+        let call_site = self.builder.build_call(function, &args, name)?;
+        Ok(call_site)
+    }
+
     pub(super) fn close_databases(&self, db_ptr_arr: PointerValue<'ctxt>) -> Result<(), CodeGenError> {
         let num_dbs = self.datasource_ptrs.len() as u32;
         self.builder.build_call(
@@ -79,6 +406,50 @@ impl<'ctxt> CodeGen<'ctxt> {
         Ok(())
     }
 
+    pub(super) fn gen_transaction(
+        &mut self,
+        datasource_id: u32,
+        body: &SemanticBlock,
+        id: u32,
+    ) -> Result<(), CodeGenError> {
+        let database_global = self.datasource_ptrs[&datasource_id];
+        let database_ptr = self.builder.build_load(
+            self.ptr_type(),
+            database_global,
+            "load_database_ptr"
+        )?.into_pointer_value();
+        let is_savepoint = self.builder.build_call(
+            self.runtime.transaction_begin,
+            &[database_ptr.into()],
+            "transaction_begin"
+        )?.as_any_value_enum().into_int_value();
+        self.transaction_info.insert(id, GenTransactionInfo { database_ptr, is_savepoint });
+
+        self.gen_block(body)?;
+        if !body.terminates {
+            self.builder.build_call(
+                self.runtime.transaction_commit,
+                &[database_ptr.into(), is_savepoint.into()],
+                "transaction_commit"
+            )?;
+        }
+        Ok(())
+    }
+
+    // Rolls back the transactions left by an early return, break or continue
+    pub(super) fn rollback_exited_transactions(&mut self) -> Result<(), CodeGenError> {
+        for transaction_id in &self.transactions_to_rollback {
+            let GenTransactionInfo { database_ptr, is_savepoint } = self.transaction_info[transaction_id];
+            self.builder.build_call(
+                self.runtime.transaction_rollback,
+                &[database_ptr.into(), is_savepoint.into()],
+                "transaction_rollback"
+            )?;
+        }
+        self.transactions_to_rollback.clear();
+        Ok(())
+    }
+
     pub(super) fn gen_immediate_query(&mut self, query: &SemanticQuery) -> Result<GenValue<'ctxt>, CodeGenError> {
         let prepared_stmt = self.prepare_query(query)?;
         let result = self.execute_query(prepared_stmt, query)?;
@@ -87,11 +458,37 @@ impl<'ctxt> CodeGen<'ctxt> {
     }
 
     pub(super) fn prepare_query(&mut self, query: &SemanticQuery) -> Result<PointerValue<'ctxt>, CodeGenError> {
+        // Source location reported by the runtime if the query fails
+        let location = match query {
+            SemanticQuery::Select { location, .. }
+            | SemanticQuery::Insert { location, .. }
+            | SemanticQuery::Update { location, .. }
+            | SemanticQuery::Delete { location, .. } => location,
+        };
+        let location_str = self.builder.build_global_string_ptr(&location.to_string(), "query_location")?;
+
         match query {
-            SemanticQuery::Select { table_id, where_clause } => {
+            SemanticQuery::Select {
+                table_id,
+                joins,
+                is_distinct,
+                columns,
+                result_type,
+                where_clause,
+                group_by,
+                having,
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
                 let table = &self.program.tables[table_id];
                 let table_info = &self.table_info[table_id];
-                let struct_info = &self.struct_info[&table.struct_id];
+                let SemanticTypeKind::NamedStruct(result_struct_id, _) = result_type.kind() else {
+                    panic!("Expected NamedStruct result type");
+                };
+                let column_name_strs = self.gen_query_column_strs(*table_id, joins)?;
+                let struct_info = &self.struct_info[&result_struct_id];
                 let select_plan_ptr = self.builder.build_call(
                     self.runtime.select_plan_new,
                     &[
@@ -101,15 +498,117 @@ impl<'ctxt> CodeGen<'ctxt> {
                     "select_plan"
                 )?.as_any_value_enum().into_pointer_value();
 
-                if let Some(WhereClause { column_index, .. }) = where_clause {
-                    let column_name_str = table_info.column_name_strs[*column_index as usize];
+                for join in joins {
+                    let join_table_info = &self.table_info[&join.table_id];
+                    let condition_node = self.gen_join_condition(&column_name_strs, &join.conditions)?;
+                    self.builder.build_call(
+                        self.runtime.select_plan_add_join,
+                        &[
+                            select_plan_ptr.into(),
+                            self.bool_type().const_int(join.is_left as u64, false).into(),
+                            join_table_info.name_str.as_pointer_value().into(),
+                            condition_node.into(),
+                        ],
+                        "select_plan_add_join"
+                    )?;
+                }
+
+                if *is_distinct {
+                    self.builder.build_call(
+                        self.runtime.select_plan_set_distinct,
+                        &[select_plan_ptr.into()],
+                        "select_plan_set_distinct"
+                    )?;
+                }
+                let field_name_strs = if columns.is_empty() && having.is_none() {
+                    vec![]
+                } else {
+                    self.gen_field_name_strs(result_struct_id)?
+                };
+                for (column, alias_str) in columns.iter().zip(&field_name_strs) {
+                    match column {
+                        SelectColumn::Column(column_index) => {
+                            let column_name_str = column_name_strs[*column_index as usize];
+                            self.builder.build_call(
+                                self.runtime.select_plan_add_column,
+                                &[
+                                    select_plan_ptr.into(),
+                                    column_name_str.as_pointer_value().into(),
+                                    alias_str.as_pointer_value().into(),
+                                ],
+                                "select_plan_add_column"
+                            )?;
+                        }
+                        SelectColumn::Aggregate(function, column_index) => {
+                            let column_name_ptr = match column_index {
+                                Some(index) => column_name_strs[*index as usize].as_pointer_value(),
+                                None => self.ptr_type().const_null(),
+                            };
+                            let aggregate_type = AggregateType::from(*function);
+                            self.builder.build_call(
+                                self.runtime.select_plan_add_aggregate,
+                                &[
+                                    select_plan_ptr.into(),
+                                    self.int_type().const_int(aggregate_type as u64, false).into(),
+                                    column_name_ptr.into(),
+                                    alias_str.as_pointer_value().into(),
+                                ],
+                                "select_plan_add_aggregate"
+                            )?;
+                        }
+                    }
+                }
+
+                if let Some(where_clause) = where_clause {
+                    let where_node = self.gen_where_node(&column_name_strs, where_clause)?;
                     self.builder.build_call(
                         self.runtime.select_plan_set_where,
+                        &[select_plan_ptr.into(), where_node.into()],
+                        "select_plan_set_where"
+                    )?;
+                }
+
+                for &column_index in group_by {
+                    let column_name_str = column_name_strs[column_index as usize];
+                    self.builder.build_call(
+                        self.runtime.select_plan_add_group_by,
+                        &[select_plan_ptr.into(), column_name_str.as_pointer_value().into()],
+                        "select_plan_add_group_by"
+                    )?;
+                }
+                if let Some(having) = having {
+                    let having_node = self.gen_where_node(&field_name_strs, having)?;
+                    self.builder.build_call(
+                        self.runtime.select_plan_set_having,
+                        &[select_plan_ptr.into(), having_node.into()],
+                        "select_plan_set_having"
+                    )?;
+                }
+
+                for ordering in order_by {
+                    let column_name_str = column_name_strs[ordering.column_index as usize];
+                    self.builder.build_call(
+                        self.runtime.select_plan_add_ordering,
                         &[
                             select_plan_ptr.into(),
                             column_name_str.as_pointer_value().into(),
+                            self.bool_type().const_int(ordering.descending as u64, false).into(),
                         ],
-                        "select_plan_set_where"
+                        "select_plan_add_ordering"
+                    )?;
+                }
+                if limit.is_some() {
+                    self.builder.build_call(
+                        self.runtime.select_plan_set_limit,
+                        &[select_plan_ptr.into()],
+                        "select_plan_set_limit"
+                    )?;
+                }
+                if offset.is_some() {
+                    self.builder.build_call(
+                        self.runtime.select_plan_set_offset,
+                        &[select_plan_ptr.into()],
+                        "select_plan_set_offset"
                     )?;
                 }
 
@@ -121,25 +620,47 @@ impl<'ctxt> CodeGen<'ctxt> {
                 )?.into_pointer_value();
                 let prepared_select = self.builder.build_call(
                     self.runtime.select_plan_prepare,
-                    &[database_ptr.into(), select_plan_ptr.into()],
+                    &[database_ptr.into(), select_plan_ptr.into(), location_str.as_pointer_value().into()],
                     "prepared_select"
                 )?.as_any_value_enum().into_pointer_value();
 
                 Ok(prepared_select)
             },
-            SemanticQuery::Insert { table_id, .. } => {
+            SemanticQuery::Insert { table_id, columns, value, returning, .. } => {
                 let table = &self.program.tables[table_id];
                 let table_info = &self.table_info[table_id];
-                let struct_info = &self.struct_info[&table.struct_id];
+                // Rows may give only some of the table's columns, so bind them by the row's own struct
+                let row_type = match value.sem_type.kind() {
+                    SemanticTypeKind::Array(elem_type) => elem_type,
+                    _ => value.sem_type.clone(),
+                };
+                let SemanticTypeKind::NamedStruct(row_struct_id, _) = row_type.kind() else {
+                    panic!("Expected inserted rows to be named structs");
+                };
+                let struct_info = &self.struct_info[&row_struct_id];
+                let column_name_strs = columns.iter()
+                    .map(|&i| table_info.column_name_strs[i as usize])
+                    .collect::<Vec<GlobalValue>>();
+                let col_name_arr = self.gen_string_array(&column_name_strs, "insert_col_name_arr")?;
                 let insert_plan_ptr = self.builder.build_call(
                     self.runtime.insert_plan_new,
                     &[
                         table_info.name_str.as_pointer_value().into(),
                         struct_info.type_info.as_pointer_value().into(),
+                        col_name_arr.into(),
                     ],
                     "insert_plan"
                 )?.as_any_value_enum().into_pointer_value();
 
+                if let Some(returning) = returning {
+                    let returning_ptr = self.gen_returning_clause(*table_id, returning)?;
+                    self.builder.build_call(
+                        self.runtime.insert_plan_set_returning,
+                        &[insert_plan_ptr.into(), returning_ptr.into()],
+                        "insert_plan_set_returning"
+                    )?;
+                }
+
                 let database_global = self.datasource_ptrs[&table.datasource_id];
                 let database_ptr = self.builder.build_load(
                     self.ptr_type(),
@@ -148,13 +669,13 @@ impl<'ctxt> CodeGen<'ctxt> {
                 )?.into_pointer_value();
                 let prepared_insert = self.builder.build_call(
                     self.runtime.insert_plan_prepare,
-                    &[database_ptr.into(), insert_plan_ptr.into()],
+                    &[database_ptr.into(), insert_plan_ptr.into(), location_str.as_pointer_value().into()],
                     "prepared_insert"
                 )?.as_any_value_enum().into_pointer_value();
 
                 Ok(prepared_insert)
             }
-            SemanticQuery::Update { table_id, assignments, where_clause } => {
+            SemanticQuery::Update { table_id, assignments, where_clause, returning, .. } => {
                 let table = &self.program.tables[table_id];
                 let table_info = &self.table_info[table_id];
 
@@ -186,17 +707,22 @@ impl<'ctxt> CodeGen<'ctxt> {
                     "update_plan"
                 )?.as_any_value_enum().into_pointer_value();
 
-                if let Some(WhereClause { column_index, .. }) = where_clause {
-                    let column_name_str = table_info.column_name_strs[*column_index as usize];
+                if let Some(where_clause) = where_clause {
+                    let where_node = self.gen_where_node(&table_info.column_name_strs, where_clause)?;
                     self.builder.build_call(
                         self.runtime.update_plan_set_where,
-                        &[
-                            update_plan_ptr.into(),
-                            column_name_str.as_pointer_value().into(),
-                        ],
+                        &[update_plan_ptr.into(), where_node.into()],
                         "update_plan_set_where"
                     )?;
                 }
+                if let Some(returning) = returning {
+                    let returning_ptr = self.gen_returning_clause(*table_id, returning)?;
+                    self.builder.build_call(
+                        self.runtime.update_plan_set_returning,
+                        &[update_plan_ptr.into(), returning_ptr.into()],
+                        "update_plan_set_returning"
+                    )?;
+                }
 
                 let database_global = self.datasource_ptrs[&table.datasource_id];
                 let database_ptr = self.builder.build_load(
@@ -206,36 +732,37 @@ impl<'ctxt> CodeGen<'ctxt> {
                 )?.into_pointer_value();
                 let prepared_update = self.builder.build_call(
                     self.runtime.update_plan_prepare,
-                    &[database_ptr.into(), update_plan_ptr.into()],
+                    &[database_ptr.into(), update_plan_ptr.into(), location_str.as_pointer_value().into()],
                     "prepared_update"
                 )?.as_any_value_enum().into_pointer_value();
 
                 Ok(prepared_update)
             }
-            SemanticQuery::Delete { table_id, where_clause } => {
+            SemanticQuery::Delete { table_id, where_clause, returning, .. } => {
                 let table = &self.program.tables[table_id];
                 let table_info = &self.table_info[table_id];
-                let struct_info = &self.struct_info[&table.struct_id];
-                let select_plan_ptr = self.builder.build_call(
+                let delete_plan_ptr = self.builder.build_call(
                     self.runtime.delete_plan_new,
-                    &[
-                        table_info.name_str.as_pointer_value().into(),
-                        struct_info.type_info.as_pointer_value().into(),
-                    ],
+                    &[table_info.name_str.as_pointer_value().into()],
                     "delete_plan"
                 )?.as_any_value_enum().into_pointer_value();
 
-                if let Some(WhereClause { column_index, .. }) = where_clause {
-                    let column_name_str = table_info.column_name_strs[*column_index as usize];
+                if let Some(where_clause) = where_clause {
+                    let where_node = self.gen_where_node(&table_info.column_name_strs, where_clause)?;
                     self.builder.build_call(
                         self.runtime.delete_plan_set_where,
-                        &[
-                            select_plan_ptr.into(),
-                            column_name_str.as_pointer_value().into(),
-                        ],
+                        &[delete_plan_ptr.into(), where_node.into()],
                         "delete_plan_set_where"
                     )?;
                 }
+                if let Some(returning) = returning {
+                    let returning_ptr = self.gen_returning_clause(*table_id, returning)?;
+                    self.builder.build_call(
+                        self.runtime.delete_plan_set_returning,
+                        &[delete_plan_ptr.into(), returning_ptr.into()],
+                        "delete_plan_set_returning"
+                    )?;
+                }
 
                 let database_global = self.datasource_ptrs[&table.datasource_id];
                 let database_ptr = self.builder.build_load(
@@ -245,7 +772,7 @@ impl<'ctxt> CodeGen<'ctxt> {
                 )?.into_pointer_value();
                 let prepared_delete = self.builder.build_call(
                     self.runtime.delete_plan_prepare,
-                    &[database_ptr.into(), select_plan_ptr.into()],
+                    &[database_ptr.into(), delete_plan_ptr.into(), location_str.as_pointer_value().into()],
                     "prepared_delete"
                 )?.as_any_value_enum().into_pointer_value();
 
@@ -260,59 +787,117 @@ impl<'ctxt> CodeGen<'ctxt> {
         query: &SemanticQuery
     ) -> Result<GenValue<'ctxt>, CodeGenError> {
         match query {
-            SemanticQuery::Select { where_clause, table_id } => {
-                if let Some(WhereClause { value, .. }) = where_clause {
-                    let gen_value = self.gen_eval(value)?;
-                    let value_ptr = self.place_onto_stack(&gen_value)?;
+            SemanticQuery::Select { where_clause, having, result_type, shape, limit, offset, .. } => {
+                if let Some(where_clause) = where_clause {
+                    self.bind_where_values(statement, where_clause, self.runtime.prepared_select_bind_where)?;
+                }
+                if let Some(having) = having {
+                    self.bind_where_values(statement, having, self.runtime.prepared_select_bind_having)?;
+                }
+                if let Some(limit) = limit {
+                    let limit_value = self.gen_eval(limit)?.as_llvm_basic_value();
                     self.builder.build_call(
-                        self.runtime.prepared_select_bind_where,
-                        &[
-                            statement.into(),
-                            self.get_qltype(&value.sem_type).into(),
-                            value_ptr.into(),
-                        ],
-                        "select_bind_where"
+                        self.runtime.prepared_select_bind_limit,
+                        &[statement.into(), limit_value.into()],
+                        "bind_limit"
+                    )?;
+                }
+                if let Some(offset) = offset {
+                    let offset_value = self.gen_eval(offset)?.as_llvm_basic_value();
+                    self.builder.build_call(
+                        self.runtime.prepared_select_bind_offset,
+                        &[statement.into(), offset_value.into()],
+                        "bind_offset"
+                    )?;
+                }
+
+                if *shape == SelectShape::Row {
+                    let llvm_row_type = self.llvm_basic_type(result_type);
+                    let row_ptr = self.builder.build_alloca(llvm_row_type, "row_result")?;
+                    self.builder.build_call(
+                        self.runtime.prepared_select_execute_row,
+                        &[statement.into(), row_ptr.into()],
+                        "execute_select_row"
                     )?;
+                    let row = self.builder.build_load(llvm_row_type, row_ptr, "load_row_result")?;
+                    return Ok(GenValue::new(result_type, row, Ownership::Owned));
                 }
+
+                if *shape == SelectShape::Scalar {
+                    let SemanticTypeKind::NamedStruct(result_struct_id, _) = result_type.kind() else {
+                        panic!("Expected NamedStruct result type");
+                    };
+                    let result_struct = &self.program.structs[&result_struct_id];
+                    let value_type = &result_struct.fields[&result_struct.field_order[0]];
+                    let llvm_value_type = self.llvm_basic_type(value_type);
+                    let value_ptr = self.builder.build_alloca(llvm_value_type, "scalar_result")?;
+                    self.builder.build_call(
+                        self.runtime.prepared_select_execute_scalar,
+                        &[statement.into(), value_ptr.into()],
+                        "execute_select_scalar"
+                    )?;
+                    let value = self.builder.build_load(llvm_value_type, value_ptr, "load_scalar_result")?;
+                    let ownership = if value_type.can_be_owned() {
+                        Ownership::Owned
+                    } else {
+                        Ownership::Trivial
+                    };
+                    return Ok(GenValue::new(value_type, value, ownership));
+                }
+
                 let result = self.builder.build_call(
                     self.runtime.prepared_select_execute.into(),
                     &[statement.into()],
                     "execute_select"
                 )?.as_any_value_enum().into_pointer_value();
 
-                let table = &self.program.tables[table_id];
-                let elem_type = SemanticType::new(
-                    SemanticTypeKind::NamedStruct(table.struct_id, table.name.clone())
-                );
                 Ok(GenValue::Array {
                     value: result,
-                    elem_type,
+                    elem_type: result_type.clone(),
                     ownership: Ownership::Owned,
                 })
             },
-            SemanticQuery::Insert { value: insert_value, .. } => {
+            SemanticQuery::Insert { value: insert_value, returning, .. } => {
+                // Each inserted row gives its rowid, or the columns of the returning clause
+                let row_type = match returning {
+                    Some(returning) => returning.result_type.clone(),
+                    None => SemanticType::new(SemanticTypeKind::Long),
+                };
                 let gen_value = self.gen_eval(insert_value)?;
                 match gen_value {
                     GenValue::Array { value: llvm_value, .. } => {
-                        self.builder.build_call(
+                        let results = self.builder.build_call(
                             self.runtime.prepared_insert_exec_array.into(),
                             &[statement.into(), llvm_value.into()],
                             "insert_exec_array"
-                        )?;
+                        )?.as_any_value_enum().into_pointer_value();
+                        Ok(GenValue::Array {
+                            value: results,
+                            elem_type: row_type,
+                            ownership: Ownership::Owned,
+                        })
                     }
                     GenValue::Struct { .. } => {
                         let data_ptr = self.place_onto_stack(&gen_value)?;
+                        let llvm_row_type = self.llvm_basic_type(&row_type);
+                        let result_ptr = self.builder.build_alloca(llvm_row_type, "insert_result")?;
                         self.builder.build_call(
                             self.runtime.prepared_insert_exec_row.into(),
-                            &[statement.into(), data_ptr.into()],
+                            &[statement.into(), data_ptr.into(), result_ptr.into()],
                             "insert_exec_row"
                         )?;
+                        let result = self.builder.build_load(llvm_row_type, result_ptr, "load_insert_result")?;
+                        let ownership = if row_type.can_be_owned() {
+                            Ownership::Owned
+                        } else {
+                            Ownership::Trivial
+                        };
+                        Ok(GenValue::new(&row_type, result, ownership))
                     }
                     _ => panic!("Unexpected insert value type")
                 }
-                Ok(GenValue::Void)
             },
-            SemanticQuery::Update { assignments, where_clause, .. } => {
+            SemanticQuery::Update { assignments, where_clause, returning, .. } => {
                 for (i, assignment) in assignments.iter().enumerate() {
                     let gen_value = self.gen_eval(&assignment.value)?.as_llvm_basic_value();
                     let value_ptr = self.builder.build_alloca(
@@ -325,56 +910,35 @@ impl<'ctxt> CodeGen<'ctxt> {
                         &[
                             statement.into(),
                             self.context.i32_type().const_int(i as u64, false).into(),
-                            self.get_qltype(&assignment.value.sem_type).into(),
+                            self.get_type_info(&assignment.value.sem_type).as_pointer_value().into(),
                             value_ptr.into(),
                         ],
                         &format!("update_bind_assign_{}", i)
                     )?;
                 }
 
-                if let Some(WhereClause { value, .. }) = where_clause {
-                    let gen_value = self.gen_eval(value)?;
-                    let value_ptr = self.place_onto_stack(&gen_value)?;
-                    self.builder.build_call(
-                        self.runtime.prepared_update_bind_where,
-                        &[
-                            statement.into(),
-                            self.get_qltype(&value.sem_type).into(),
-                            value_ptr.into(),
-                        ],
-                        "update_bind_where"
-                    )?;
+                if let Some(where_clause) = where_clause {
+                    self.bind_where_values(statement, where_clause, self.runtime.prepared_update_bind_where)?;
                 }
 
-                self.builder.build_call(
-                    self.runtime.prepared_update_exec.into(),
-                    &[statement.into()],
-                    "execute_update"
-                )?;
-                Ok(GenValue::Void)
+                self.exec_mutation(
+                    statement,
+                    returning,
+                    self.runtime.prepared_update_exec,
+                    self.runtime.prepared_update_exec_returning
+                )
             },
-            SemanticQuery::Delete { where_clause, .. } => {
-                if let Some(WhereClause { value, .. }) = where_clause {
-                    let gen_value = self.gen_eval(value)?;
-                    let value_ptr = self.place_onto_stack(&gen_value)?;
-                    self.builder.build_call(
-                        self.runtime.prepared_delete_bind_where,
-                        &[
-                            statement.into(),
-                            self.get_qltype(&value.sem_type).into(),
-                            value_ptr.into(),
-                        ],
-                        "delete_bind_where"
-                    )?;
+            SemanticQuery::Delete { where_clause, returning, .. } => {
+                if let Some(where_clause) = where_clause {
+                    self.bind_where_values(statement, where_clause, self.runtime.prepared_delete_bind_where)?;
                 }
 
-                self.builder.build_call(
-                    self.runtime.prepared_delete_exec.into(),
-                    &[statement.into()],
-                    "execute_delete"
-                )?.as_any_value_enum().into_pointer_value();
-
-                Ok(GenValue::Void)
+                self.exec_mutation(
+                    statement,
+                    returning,
+                    self.runtime.prepared_delete_exec,
+                    self.runtime.prepared_delete_exec_returning
+                )
             }
         }
     }
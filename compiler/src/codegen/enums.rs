@@ -0,0 +1,361 @@
+use inkwell::AddressSpace;
+use inkwell::basic_block::BasicBlock;
+use inkwell::types::{BasicTypeEnum, StructType};
+use inkwell::values::{BasicValueEnum, FunctionValue, GlobalValue, IntValue, PointerValue, StructValue};
+
+use super::{CodeGen, CodeGenError, GenValue};
+use super::runtime::QLType;
+use super::structs::GenStructInfo;
+use crate::semantics::{Ownership, SemanticBlock, SemanticEnum, SemanticExpression, SemanticMatchArm, SemanticType, SemanticTypeKind};
+
+pub(super) struct GenEnumInfo<'a> {
+    pub(super) enum_type: StructType<'a>,
+    pub(super) type_info: GlobalValue<'a>,
+    pub(super) copy_fn: Option<FunctionValue<'a>>,
+    pub(super) drop_fn: Option<FunctionValue<'a>>,
+}
+
+impl<'ctxt> CodeGen<'ctxt> {
+    // Enums are laid out as the tag of their variant followed by one payload per variant, of which
+    // only the tagged one is set. Variants without fields hold an empty payload
+    fn enum_payload_type(&self, payload_struct_id: Option<u32>) -> StructType<'ctxt> {
+        match payload_struct_id {
+            Some(struct_id) => self.struct_info[&struct_id].struct_type,
+            None => self.context.struct_type(&[], false),
+        }
+    }
+
+    // Builds a function calling the given payload function on the payload of the tagged variant
+    fn create_variant_fn(
+        &self,
+        sem_enum: &SemanticEnum,
+        enum_type: StructType<'ctxt>,
+        fn_name: &str,
+        payload_fn: impl Fn(&GenStructInfo<'ctxt>) -> Option<FunctionValue<'ctxt>>
+    ) -> Result<FunctionValue<'ctxt>, CodeGenError> {
+        let fn_type = self.void_type().fn_type(&[enum_type.into()], false);
+        let fn_value = self.module.add_function(
+            &format!("__ql__{}_{}", sem_enum.name, fn_name),
+            fn_type,
+            None
+        );
+        let entry_block = self.context.append_basic_block(fn_value, "entry");
+        let done_block = self.context.append_basic_block(fn_value, "done");
+        self.builder.position_at_end(done_block);
+        self.builder.build_return(None)?;
+
+        self.builder.position_at_end(entry_block);
+        let enum_arg = fn_value.get_nth_param(0).unwrap().into_struct_value();
+        let tag = self.builder.build_extract_value(enum_arg, 0, "tag")?.into_int_value();
+
+        let mut cases: Vec<(IntValue<'ctxt>, BasicBlock<'ctxt>)> = vec![];
+        for (i, variant) in sem_enum.variants.iter().enumerate() {
+            let Some(struct_id) = variant.payload_struct_id else {
+                continue;
+            };
+            let Some(payload_fn) = payload_fn(&self.struct_info[&struct_id]) else {
+                continue;
+            };
+
+            let variant_block = self.context.append_basic_block(fn_value, &variant.name);
+            self.builder.position_at_end(variant_block);
+            let payload = self.builder.build_extract_value(
+                enum_arg,
+                i as u32 + 1,
+                &format!("{}.{}", sem_enum.name, variant.name)
+            )?;
+            self.builder.build_call(payload_fn, &[payload.into()], fn_name)?;
+            self.builder.build_unconditional_branch(done_block)?;
+            cases.push((self.int_type().const_int(i as u64, false), variant_block));
+        }
+
+        self.builder.position_at_end(entry_block);
+        self.builder.build_switch(tag, done_block, &cases)?;
+        Ok(fn_value)
+    }
+
+    fn gen_enum_type_info(&self, sem_enum: &SemanticEnum, enum_type: StructType<'ctxt>) -> GlobalValue<'ctxt> {
+        let num_variants = sem_enum.variants.len() as u32;
+        let fields_arr = self.runtime.struct_field_type.const_array(&sem_enum.variants.iter()
+            .enumerate()
+            .map(|(i, variant)| {
+                let offset = self.target_data.offset_of_element(&enum_type, i as u32 + 1).unwrap();
+                let payload_type_info = match variant.payload_struct_id {
+                    Some(struct_id) => self.struct_info[&struct_id].type_info.as_pointer_value(),
+                    None => self.ptr_type().const_null(),
+                };
+                self.runtime.struct_field_type.const_named_struct(&[
+                    self.int_type().const_int(offset, false).into(),
+                    payload_type_info.into(),
+                ])
+            })
+            .collect::<Vec<StructValue>>());
+        let fields_global = self.module.add_global(
+            self.runtime.struct_field_type.array_type(num_variants),
+            Some(AddressSpace::default()),
+            &format!("__ql__{}_fields", sem_enum.name)
+        );
+        fields_global.set_initializer(&fields_arr);
+        fields_global.set_constant(true);
+
+        let variant_name_ptrs = sem_enum.variants.iter()
+            .map(|variant| {
+                let name_value = self.context.const_string(variant.name.as_bytes(), true);
+                let name_global = self.module.add_global(
+                    name_value.get_type(),
+                    Some(AddressSpace::default()),
+                    &format!("__ql__{}.{}_name", sem_enum.name, variant.name)
+                );
+                name_global.set_initializer(&name_value);
+                name_global.set_constant(true);
+                name_global.as_pointer_value()
+            })
+            .collect::<Vec<PointerValue>>();
+        let variant_names_global = self.module.add_global(
+            self.ptr_type().array_type(num_variants),
+            Some(AddressSpace::default()),
+            &format!("__ql__{}_variant_names", sem_enum.name)
+        );
+        variant_names_global.set_initializer(&self.ptr_type().const_array(&variant_name_ptrs));
+        variant_names_global.set_constant(true);
+
+        let type_info_value = self.runtime.type_info_type.const_named_struct(&[
+            self.int_type().const_int(QLType::Enum as u64, false).into(),
+            enum_type.size_of().unwrap().into(),
+            self.int_type().const_int(num_variants as u64, false).into(),
+            fields_global.as_pointer_value().into(),
+            variant_names_global.as_pointer_value().into(),
+        ]);
+        let type_info_global = self.module.add_global(
+            self.runtime.type_info_type,
+            Some(AddressSpace::default()),
+            &format!("__ql__{}_type_info", sem_enum.name)
+        );
+        type_info_global.set_initializer(&type_info_value);
+        type_info_global.set_constant(true);
+        type_info_global
+    }
+
+    pub fn gen_enum(&mut self, sem_enum: &SemanticEnum) -> Result<(), CodeGenError> {
+        let mut llvm_field_types: Vec<BasicTypeEnum> = vec![self.int_type().into()];
+        llvm_field_types.extend(sem_enum.variants.iter()
+            .map(|variant| BasicTypeEnum::from(self.enum_payload_type(variant.payload_struct_id))));
+        let enum_type = self.context.opaque_struct_type(&sem_enum.name);
+        enum_type.set_body(&llvm_field_types, false);
+        let type_info = self.gen_enum_type_info(sem_enum, enum_type);
+
+        let has_heap_fields = sem_enum.variants.iter()
+            .filter_map(|variant| variant.payload_struct_id)
+            .any(|struct_id| self.struct_info[&struct_id].drop_fn.is_some());
+        let (copy_fn, drop_fn) = if has_heap_fields {
+            let copy_fn = self.create_variant_fn(sem_enum, enum_type, "copy", |info| info.copy_fn)?;
+            let drop_fn = self.create_variant_fn(sem_enum, enum_type, "drop", |info| info.drop_fn)?;
+            (Some(copy_fn), Some(drop_fn))
+        } else {
+            (None, None)
+        };
+
+        self.enum_info.insert(sem_enum.id, GenEnumInfo {
+            enum_type,
+            type_info,
+            copy_fn,
+            drop_fn,
+        });
+        Ok(())
+    }
+
+    pub fn gen_variant_value(
+        &mut self,
+        enum_id: u32,
+        variant_index: u32,
+        args: &[SemanticExpression]
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let sem_enum = &self.program.enums[&enum_id];
+        let variant = &sem_enum.variants[variant_index as usize];
+
+        let mut payload_value = self.enum_payload_type(variant.payload_struct_id).get_undef();
+        if let Some(struct_id) = variant.payload_struct_id {
+            let payload_struct = &self.program.structs[&struct_id];
+            for (i, (arg, field_name)) in args.iter().zip(&payload_struct.field_order).enumerate() {
+                let arg_value = self.gen_eval(arg)?;
+                let arg_value = self.coerce(arg_value, &payload_struct.fields[field_name])?;
+                self.add_ref(&arg_value)?;
+                payload_value = self.builder.build_insert_value(
+                    payload_value,
+                    arg_value.as_llvm_basic_value(),
+                    i as u32,
+                    &format!("{}.{}", payload_struct.name, field_name)
+                )?.into_struct_value();
+            }
+        }
+
+        let enum_type = self.enum_info[&enum_id].enum_type;
+        let enum_value = self.builder.build_insert_value(
+            enum_type.const_zero(),
+            self.int_type().const_int(variant_index as u64, false),
+            0,
+            "variant_tag"
+        )?;
+        let enum_value = self.builder.build_insert_value(
+            enum_value,
+            payload_value,
+            variant_index + 1,
+            &format!("{}.{}", sem_enum.name, variant.name)
+        )?.into_struct_value();
+
+        Ok(GenValue::Enum {
+            value: enum_value,
+            enum_id,
+            ownership: Ownership::Owned,
+        })
+    }
+
+    // Jumps to the block of the arm handling the value's variant. Every variant is handled by
+    // some arm, so no value reaches the default block
+    fn build_match_switch<T>(
+        &self,
+        enum_value: StructValue<'ctxt>,
+        arms: &[SemanticMatchArm<T>]
+    ) -> Result<Vec<BasicBlock<'ctxt>>, CodeGenError> {
+        let cur_fn = self.cur_fn.unwrap();
+        let tag = self.builder.build_extract_value(enum_value, 0, "tag")?.into_int_value();
+        let switch_block = self.builder.get_insert_block().unwrap();
+
+        let unreachable_block = self.context.append_basic_block(cur_fn, "match_unreachable");
+        self.builder.position_at_end(unreachable_block);
+        self.builder.build_unreachable()?;
+
+        let mut arm_blocks: Vec<BasicBlock<'ctxt>> = vec![];
+        let mut cases: Vec<(IntValue<'ctxt>, BasicBlock<'ctxt>)> = vec![];
+        for (i, arm) in arms.iter().enumerate() {
+            let arm_block = self.context.append_basic_block(cur_fn, &format!("match_arm{}", i + 1));
+            for &variant_index in &arm.variant_indices {
+                cases.push((self.int_type().const_int(variant_index as u64, false), arm_block));
+            }
+            arm_blocks.push(arm_block);
+        }
+
+        self.builder.position_at_end(switch_block);
+        self.builder.build_switch(tag, unreachable_block, &cases)?;
+        Ok(arm_blocks)
+    }
+
+    // Bindings borrow the payload's fields from the matched value, so they are never dropped
+    fn bind_match_arm<T>(
+        &mut self,
+        enum_value: StructValue<'ctxt>,
+        enum_id: u32,
+        arm: &SemanticMatchArm<T>
+    ) -> Result<(), CodeGenError> {
+        if arm.bindings.is_empty() {
+            return Ok(());
+        }
+
+        let variant_index = arm.variant_indices[0];
+        let variant = &self.program.enums[&enum_id].variants[variant_index as usize];
+        let payload_struct = &self.program.structs[&variant.payload_struct_id.unwrap()];
+        let payload_value = self.builder.build_extract_value(
+            enum_value,
+            variant_index + 1,
+            &payload_struct.name
+        )?.into_struct_value();
+
+        for &(field_index, variable_id) in &arm.bindings {
+            let variable = &self.program.variables[&variable_id];
+            let field_value = self.builder.build_extract_value(
+                payload_value,
+                field_index,
+                &variable.name
+            )?;
+            let variable_ptr = self.builder.build_alloca(
+                self.llvm_basic_type(&variable.sem_type),
+                &variable.name
+            )?;
+            self.builder.build_store(variable_ptr, field_value)?;
+            self.llvm_variables.insert(variable_id, variable_ptr);
+            self.borrowed_variables.insert(variable_id);
+        }
+        Ok(())
+    }
+
+    pub fn gen_match(
+        &mut self,
+        variable_id: u32,
+        arms: &[SemanticMatchArm<SemanticBlock>]
+    ) -> Result<(), CodeGenError> {
+        let GenValue::Enum { value: enum_value, enum_id, .. } = self.load_var(variable_id)? else {
+            panic!("Expected enum value");
+        };
+        let arm_blocks = self.build_match_switch(enum_value, arms)?;
+
+        let mut arm_end_blocks: Vec<BasicBlock<'ctxt>> = vec![];
+        for (arm, arm_block) in arms.iter().zip(&arm_blocks) {
+            self.builder.position_at_end(*arm_block);
+            self.bind_match_arm(enum_value, enum_id, arm)?;
+            self.gen_block(&arm.body)?;
+            if !arm.body.terminates {
+                arm_end_blocks.push(self.builder.get_insert_block().unwrap());
+            }
+        }
+
+        // Arms which do not leave the function or loop resume after the match, where the matched
+        // value is released. Arms which do leave have already released it
+        if !arm_end_blocks.is_empty() {
+            let merge_block = self.context.append_basic_block(self.cur_fn.unwrap(), "merge_arms");
+            for arm_end_block in arm_end_blocks {
+                self.builder.position_at_end(arm_end_block);
+                self.builder.build_unconditional_branch(merge_block)?;
+            }
+            self.builder.position_at_end(merge_block);
+            self.drop_var(variable_id)?;
+        }
+        Ok(())
+    }
+
+    pub fn gen_match_value(
+        &mut self,
+        value: &SemanticExpression,
+        arms: &[SemanticMatchArm<SemanticExpression>],
+        result_type: &SemanticType
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let matched_value = self.gen_eval(value)?;
+        let GenValue::Enum { value: enum_value, enum_id, .. } = matched_value else {
+            panic!("Expected enum value");
+        };
+        let arm_blocks = self.build_match_switch(enum_value, arms)?;
+        let cur_fn = self.cur_fn.unwrap();
+        let merge_block = self.context.append_basic_block(cur_fn, "merge_arms");
+
+        // Each arm takes a reference of its own to its result, which may borrow from the matched value
+        let mut results: Vec<(BasicValueEnum<'ctxt>, BasicBlock<'ctxt>)> = vec![];
+        for (arm, arm_block) in arms.iter().zip(&arm_blocks) {
+            self.builder.position_at_end(*arm_block);
+            self.bind_match_arm(enum_value, enum_id, arm)?;
+            let result = self.gen_eval(&arm.body)?;
+            if result_type.kind() != SemanticTypeKind::Void {
+                let result = self.coerce(result, result_type)?;
+                self.add_ref(&result)?;
+                results.push((result.as_llvm_basic_value(), self.builder.get_insert_block().unwrap()));
+            }
+            self.builder.build_unconditional_branch(merge_block)?;
+        }
+
+        self.builder.position_at_end(merge_block);
+        if result_type.kind() == SemanticTypeKind::Void {
+            self.remove_if_owned(matched_value)?;
+            return Ok(GenValue::Void);
+        }
+
+        let result = self.builder.build_phi(self.llvm_basic_type(result_type), "match_result")?;
+        for (value, block) in &results {
+            result.add_incoming(&[(value, *block)]);
+        }
+        self.remove_if_owned(matched_value)?;
+        let ownership = if result_type.can_be_owned() {
+            Ownership::Owned
+        } else {
+            Ownership::Trivial
+        };
+        Ok(GenValue::new(result_type, result.as_basic_value(), ownership))
+    }
+}
@@ -1,60 +1,159 @@
+use inkwell::IntPredicate;
 use inkwell::types::{BasicMetadataTypeEnum, BasicType};
 use inkwell::values::{AnyValue, BasicMetadataValueEnum, ValueKind};
 
 use super::{CodeGen, CodeGenError};
 use crate::codegen::data::GenValue;
-use crate::semantics::{BuiltinFunction, BuiltinMethod, Ownership, SemanticExpression, SemanticFunction, SemanticTypeKind};
+use crate::semantics::{BuiltinFunction, BuiltinMethod, Ownership, SemanticExpression, SemanticFunction, SemanticType, SemanticTypeKind};
+
+// Distinguishes the instances of a generic function by their type arguments, and is empty for
+// functions without type parameters
+pub(super) fn instance_key(type_args: &[SemanticType]) -> String {
+	if type_args.is_empty() {
+		return String::new();
+	}
+	let type_args = type_args.iter()
+		.map(|type_arg| type_arg.to_string())
+		.collect::<Vec<String>>();
+	format!("<{}>", type_args.join(", "))
+}
 
 impl<'ctxt> CodeGen<'ctxt> {
-	pub(super) fn declare_function(&mut self, function: &SemanticFunction) -> Result<(), CodeGenError> {
-		let llvm_param_types = function.param_ids.iter()
-			.map(|p| self.llvm_basic_type(&self.program.variables[p].sem_type).into())
-			.collect::<Vec<BasicMetadataTypeEnum>>(); 
-		let llvm_type = match function.return_type.kind() {
-			SemanticTypeKind::Void => {
-				self.void_type().fn_type(&llvm_param_types, false)
-			}
-			_ => {
-				let llvm_return_type = self.llvm_basic_type(&function.return_type);
-				llvm_return_type.fn_type(&llvm_param_types, false)
+	// Generic functions, and the closures written in them, are generated once per instance with
+	// their type parameters standing for the instance's type arguments
+	pub(super) fn function_instances(&self, generic_function_id: Option<u32>) -> Vec<Vec<SemanticType>> {
+		match generic_function_id {
+			Some(function_id) => self.program.functions[&function_id].instances.clone(),
+			None => vec![vec![]],
+		}
+	}
+
+	fn generic_function_id(function: &SemanticFunction) -> Option<u32> {
+		(!function.type_params.is_empty()).then_some(function.id)
+	}
+
+	// Replaces the type parameters within a type by the type arguments of the instance being
+	// generated, and open instances of generic structs by the concrete ones they stand for
+	pub(super) fn resolve_type(&self, sem_type: &SemanticType) -> SemanticType {
+		if self.type_args.is_empty() {
+			return sem_type.clone();
+		}
+		let kind = match sem_type.kind() {
+			SemanticTypeKind::TypeParameter(index, _) => return self.type_args[index as usize].clone(),
+			SemanticTypeKind::Array(elem_type) => SemanticTypeKind::Array(self.resolve_type(&elem_type)),
+			SemanticTypeKind::Optional(inner_type) => SemanticTypeKind::Optional(self.resolve_type(&inner_type)),
+			SemanticTypeKind::Callable(param_types, ret_type) => SemanticTypeKind::Callable(
+				param_types.iter().map(|param_type| self.resolve_type(param_type)).collect(),
+				self.resolve_type(&ret_type),
+			),
+			SemanticTypeKind::NamedStruct(struct_id, name) => match self.program.struct_instances.get(&struct_id) {
+				Some(open_instance) if open_instance.is_open => {
+					let type_args = open_instance.type_args.iter()
+						.map(|type_arg| self.resolve_type(type_arg))
+						.collect::<Vec<SemanticType>>();
+					let (&instance_id, _) = self.program.struct_instances.iter()
+						.find(|(_, instance)| {
+							!instance.is_open
+								&& instance.generic_name == open_instance.generic_name
+								&& instance.type_args == type_args
+						})
+						.expect("Structs used by a generic function are instantiated along with it");
+					SemanticTypeKind::NamedStruct(instance_id, self.program.structs[&instance_id].name.clone())
+				}
+				_ => SemanticTypeKind::NamedStruct(struct_id, name),
 			},
+			kind => kind,
 		};
+		SemanticType::new(kind)
+	}
+
+	pub(super) fn declare_function(&mut self, function: &SemanticFunction) -> Result<(), CodeGenError> {
+		for type_args in self.function_instances(Self::generic_function_id(function)) {
+			self.type_args = type_args;
+			let llvm_param_types = function.param_ids.iter()
+				.map(|p| self.llvm_basic_type(&self.program.variables[p].sem_type).into())
+				.collect::<Vec<BasicMetadataTypeEnum>>(); 
+			let return_type = self.resolve_type(&function.return_type);
+			let llvm_type = match return_type.kind() {
+				SemanticTypeKind::Void => {
+					self.void_type().fn_type(&llvm_param_types, false)
+				}
+				_ => {
+					let llvm_return_type = self.llvm_basic_type(&return_type);
+					llvm_return_type.fn_type(&llvm_param_types, false)
+				},
+			};
 
-		let llvm_name = if function.name == "main" { "__ql__user_main" } else { &function.name };
-		let llvm_fn = self.module.add_function(llvm_name, llvm_type, None);
-		self.llvm_functions.insert(function.id, llvm_fn);
+			let key = instance_key(&self.type_args);
+			let llvm_name = if function.name == "main" {
+				"__ql__user_main".to_string()
+			} else {
+				format!("{}{}", function.name, key)
+			};
+			let llvm_fn = self.module.add_function(&llvm_name, llvm_type, None);
+			self.llvm_functions.insert((function.id, key), llvm_fn);
+		}
+		self.type_args = vec![];
 		Ok(())
 	}
 
 	pub(super) fn define_function(&mut self, function: &SemanticFunction) -> Result<(), CodeGenError> {
-		let llvm_fn = self.llvm_functions[&function.id];
-
-		self.cur_fn = Some(llvm_fn);
-		let entry_block = self.context.append_basic_block(llvm_fn, "entry");
-		self.builder.position_at_end(entry_block);
-
-		for (i, param_id) in function.param_ids.iter().enumerate() {
-			let param_var = &self.program.variables[param_id];
-			let llvm_param_val = llvm_fn.get_nth_param(i as u32).unwrap();
-			let llvm_param_var = self.builder.build_alloca(
-				self.llvm_basic_type(&param_var.sem_type),
-				&param_var.name
-			)?;
-			self.builder.build_store(llvm_param_var, llvm_param_val)?;
-			self.llvm_variables.insert(*param_id, llvm_param_var);
+		for type_args in self.function_instances(Self::generic_function_id(function)) {
+			let llvm_fn = self.llvm_functions[&(function.id, instance_key(&type_args))];
+			self.type_args = type_args;
+
+			self.cur_fn = Some(llvm_fn);
+			self.cur_return_type = Some(self.resolve_type(&function.return_type));
+			let entry_block = self.context.append_basic_block(llvm_fn, "entry");
+			self.builder.position_at_end(entry_block);
+
+			for (i, param_id) in function.param_ids.iter().enumerate() {
+				let param_var = &self.program.variables[param_id];
+				let llvm_param_val = llvm_fn.get_nth_param(i as u32).unwrap();
+				let llvm_param_var = self.builder.build_alloca(
+					self.llvm_basic_type(&param_var.sem_type),
+					&param_var.name
+				)?;
+				self.builder.build_store(llvm_param_var, llvm_param_val)?;
+				self.llvm_variables.insert(*param_id, llvm_param_var);
+			}
+			self.gen_block(&function.body)?;
 		}
-		self.gen_block(&function.body)?;
-		
+
 		self.cur_fn = None;
+		self.cur_return_type = None;
+		self.type_args = vec![];
 		Ok(())
 	}
 
-    pub fn gen_direct_call(&mut self, function_id: u32, args: &[SemanticExpression]) -> Result<GenValue<'ctxt>, CodeGenError> {
+    pub fn gen_direct_call(
+		&mut self,
+		function_id: u32,
+		type_args: &[SemanticType],
+		args: &[SemanticExpression]
+	) -> Result<GenValue<'ctxt>, CodeGenError> {
 		let sem_function = &self.program.functions[&function_id];
-		let llvm_function = self.llvm_functions[&function_id];
+
+		// The callee's type arguments may refer to the type parameters of the caller, while its
+		// parameter and return types refer to its own
+		let callee_type_args = type_args.iter()
+			.map(|type_arg| self.resolve_type(type_arg))
+			.collect::<Vec<SemanticType>>();
+		let llvm_function = self.llvm_functions[&(function_id, instance_key(&callee_type_args))];
+		let caller_type_args = std::mem::replace(&mut self.type_args, callee_type_args);
+		let param_types = sem_function.param_ids.iter()
+			.map(|param_id| self.resolve_type(&self.program.variables[param_id].sem_type))
+			.collect::<Vec<SemanticType>>();
+		let return_type = self.resolve_type(&sem_function.return_type);
+		self.type_args = caller_type_args;
+
 		let arg_values = args
 			.iter()
-			.map(|arg| self.gen_eval(arg))
+			.zip(&param_types)
+			.map(|(arg, param_type)| {
+				let arg_value = self.gen_eval(arg)?;
+				self.coerce(arg_value, param_type)
+			})
 			.collect::<Result<Vec<GenValue<'ctxt>>, CodeGenError>>()?;
 		let llvm_arg_values = arg_values
 			.iter()
@@ -68,7 +167,7 @@ impl<'ctxt> CodeGen<'ctxt> {
 
 		match call_site.try_as_basic_value() {
 			ValueKind::Basic(value) => Ok(GenValue::new(
-				&sem_function.return_type,
+				&return_type,
 				value,
 				Ownership::Owned
 			)),
@@ -101,6 +200,24 @@ impl<'ctxt> CodeGen<'ctxt> {
 				)?;
 				Ok(GenValue::Void)
 			}
+			BuiltinFunction::PrintLong => {
+				let long_val = &arg_values[0];
+				self.builder.build_call(
+					self.runtime.print_long,
+					&[long_val.as_llvm_basic_value().into()],
+					"print_long"
+				)?;
+				Ok(GenValue::Void)
+			}
+			BuiltinFunction::PrintFloat => {
+				let float_val = &arg_values[0];
+				self.builder.build_call(
+					self.runtime.print_float,
+					&[float_val.as_llvm_basic_value().into()],
+					"print_float"
+				)?;
+				Ok(GenValue::Void)
+			}
 			BuiltinFunction::PrintBool => {
 				let bool_val = &arg_values[0];
 				self.builder.build_call(
@@ -152,6 +269,39 @@ impl<'ctxt> CodeGen<'ctxt> {
 			BuiltinMethod::ArrayPop => {
 				self.gen_array_pop(object)
 			}
+			BuiltinMethod::IntegerToLong => {
+				let int_val = object.as_llvm_basic_value().into_int_value();
+				let long_val = self.builder.build_int_s_extend(int_val, self.long_type(), "to_long")?;
+				Ok(GenValue::Integer(long_val))
+			}
+			BuiltinMethod::LongToInteger(location) => {
+				let long_val = object.as_llvm_basic_value().into_int_value();
+				let int_val = self.builder.build_int_truncate(long_val, self.int_type(), "to_int")?;
+				if self.checked_arithmetic {
+					// The value fits when extending it back gives the original
+					let extended = self.builder.build_int_s_extend(int_val, self.long_type(), "extended")?;
+					let overflowed = self.builder.build_int_compare(IntPredicate::NE, extended, long_val, "overflowed")?;
+					self.gen_overflow_check(overflowed, &location)?;
+				}
+				Ok(GenValue::Integer(int_val))
+			}
+			BuiltinMethod::IntegerToFloat => {
+				let int_val = object.as_llvm_basic_value().into_int_value();
+				let float_val = self.builder.build_signed_int_to_float(int_val, self.float_type(), "to_float")?;
+				Ok(GenValue::Float(float_val))
+			}
+			BuiltinMethod::FloatToInteger(location) => {
+				let float_val = object.as_llvm_basic_value().into_float_value();
+				Ok(GenValue::Integer(self.gen_float_to_int(float_val, self.int_type(), &location)?))
+			}
+			BuiltinMethod::FloatToLong(location) => {
+				let float_val = object.as_llvm_basic_value().into_float_value();
+				Ok(GenValue::Integer(self.gen_float_to_int(float_val, self.long_type(), &location)?))
+			}
+			BuiltinMethod::OptionalUnwrapOr => {
+				let default = arg_vals.remove(0);
+				self.gen_unwrap_or(object, default)
+			}
 		}
 	}
 }
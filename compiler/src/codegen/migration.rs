@@ -0,0 +1,135 @@
+use crate::semantics::{SchemaChange, SemanticColumn, SemanticMigration, SemanticType, SemanticTypeKind};
+use crate::tokens::SourceLocation;
+use super::{CodeGen, CodeGenError};
+
+fn column_sql_type(sem_type: &SemanticType) -> &'static str {
+    match sem_type.non_optional().kind() {
+        SemanticTypeKind::Integer | SemanticTypeKind::Long | SemanticTypeKind::Bool | SemanticTypeKind::Ref(_, _) => "INTEGER",
+        SemanticTypeKind::Float => "REAL",
+        SemanticTypeKind::String | SemanticTypeKind::Enum(_, _) => "TEXT",
+        _ => panic!("Expected primitive column type"),
+    }
+}
+
+impl SchemaChange {
+    fn table_name(&self) -> &str {
+        match self {
+            SchemaChange::CreateTable { table_name, .. }
+            | SchemaChange::DropTable { table_name }
+            | SchemaChange::RenameTable { table_name, .. }
+            | SchemaChange::AddColumn { table_name, .. }
+            | SchemaChange::DropColumn { table_name, .. }
+            | SchemaChange::RenameColumn { table_name, .. } => table_name,
+        }
+    }
+}
+
+impl<'ctxt> CodeGen<'ctxt> {
+    // Matches the columns of tables created from their declarations, where only optional columns
+    // may hold NULL
+    fn column_sql(&self, column: &SemanticColumn) -> String {
+        format!(
+            "{} {}{}{}",
+            column.name,
+            column_sql_type(&column.sem_type),
+            if column.sem_type.is_optional() { "" } else { " NOT NULL" },
+            self.column_constraints_sql(&column.sem_type, &column.constraints)
+        )
+    }
+
+    fn schema_change_sql(&self, change: &SchemaChange) -> String {
+        match change {
+            SchemaChange::CreateTable { table_name, columns } => {
+                let column_strs = columns.iter()
+                    .map(|column| self.column_sql(column))
+                    .collect::<Vec<String>>();
+                format!("CREATE TABLE {} ({});", table_name, column_strs.join(", "))
+            },
+            SchemaChange::DropTable { table_name } => {
+                format!("DROP TABLE {};", table_name)
+            },
+            SchemaChange::RenameTable { table_name, new_name } => {
+                format!("ALTER TABLE {} RENAME TO {};", table_name, new_name)
+            },
+            SchemaChange::AddColumn { table_name, column } => {
+                format!("ALTER TABLE {} ADD COLUMN {};", table_name, self.column_sql(column))
+            },
+            SchemaChange::DropColumn { table_name, column_name } => {
+                format!("ALTER TABLE {} DROP COLUMN {};", table_name, column_name)
+            },
+            SchemaChange::RenameColumn { table_name, column_name, new_name } => {
+                format!("ALTER TABLE {} RENAME COLUMN {} TO {};", table_name, column_name, new_name)
+            },
+        }
+    }
+
+    pub(super) fn declare_migration(&mut self, migration: &SemanticMigration) {
+        let datasource = &self.program.datasources[&migration.datasource_id];
+        let llvm_fn = self.module.add_function(
+            &format!("__ql__migration_{}_{}", datasource.name, migration.version),
+            self.void_type().fn_type(&[], false),
+            None
+        );
+        self.migration_fns.push(llvm_fn);
+    }
+
+    pub(super) fn define_migration(&mut self, index: usize, migration: &SemanticMigration) -> Result<(), CodeGenError> {
+        let llvm_fn = self.migration_fns[index];
+
+        self.cur_fn = Some(llvm_fn);
+        let entry_block = self.context.append_basic_block(llvm_fn, "entry");
+        self.builder.position_at_end(entry_block);
+        self.gen_block(&migration.body)?;
+
+        self.cur_fn = None;
+        Ok(())
+    }
+
+    // Applies pending migrations in order, each in its own transaction
+    pub(super) fn run_migrations(&self) -> Result<(), CodeGenError> {
+        for (migration, llvm_fn) in self.program.migrations.iter().zip(&self.migration_fns) {
+            let database_ptr = self.builder.build_load(
+                self.ptr_type(),
+                self.datasource_ptrs[&migration.datasource_id],
+                "load_database_ptr"
+            )?.into_pointer_value();
+            self.builder.build_call(
+                self.runtime.migration_run,
+                &[
+                    database_ptr.into(),
+                    self.int_type().const_int(migration.version as u64, false).into(),
+                    llvm_fn.as_global_value().as_pointer_value().into(),
+                ],
+                "run_migration"
+            )?;
+        }
+        Ok(())
+    }
+
+    pub(super) fn gen_schema_change(
+        &mut self,
+        datasource_id: u32,
+        location: &SourceLocation,
+        change: &SchemaChange,
+    ) -> Result<(), CodeGenError> {
+        let database_ptr = self.builder.build_load(
+            self.ptr_type(),
+            self.datasource_ptrs[&datasource_id],
+            "load_database_ptr"
+        )?.into_pointer_value();
+        let sql_str = self.builder.build_global_string_ptr(&self.schema_change_sql(change), "schema_change_sql")?;
+        let table_name_str = self.builder.build_global_string_ptr(change.table_name(), "schema_change_table")?;
+        let location_str = self.builder.build_global_string_ptr(&location.to_string(), "schema_change_location")?;
+        self.builder.build_call(
+            self.runtime.migration_change_schema,
+            &[
+                database_ptr.into(),
+                sql_str.as_pointer_value().into(),
+                table_name_str.as_pointer_value().into(),
+                location_str.as_pointer_value().into(),
+            ],
+            "change_schema"
+        )?;
+        Ok(())
+    }
+}
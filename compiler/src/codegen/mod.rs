@@ -1,13 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
 use inkwell::context::Context;
 use inkwell::module::Module;
 use inkwell::builder::Builder;
 use inkwell::targets::{FileType, Target, TargetData, TargetMachine};
-use inkwell::types::{IntType, PointerType, VoidType};
+use inkwell::types::{FloatType, IntType, PointerType, VoidType};
 use inkwell::values::{AnyValue, FunctionValue, GlobalValue, PointerValue};
 
-use crate::semantics::{SemanticExpression, SemanticExpressionKind, SemanticProgram, SemanticStatement, SemanticTypeKind};
+use crate::semantics::{SemanticExpression, SemanticExpressionKind, SemanticProgram, SemanticStatement, SemanticType, SemanticTypeKind};
 
 mod control_flow;
 mod operations;
@@ -20,32 +20,55 @@ mod variable;
 mod table;
 mod array;
 mod database;
+mod migration;
 mod runtime;
 mod structs;
+mod optional;
+mod enums;
 
 use data::GenValue;
 use table::GenTableInfo;
 use structs::GenStructInfo;
+use enums::GenEnumInfo;
 use control_flow::GenLoopInfo;
+use database::GenTransactionInfo;
 use closure::GenClosureInfo;
 use runtime::Runtime;
 pub use error::CodeGenError;
 
 pub struct CodeGen<'ctxt> {
     program: &'ctxt SemanticProgram,
+    // Whether declared tables are checked against the live databases at startup
+    verify_schema: bool,
+    // Whether integer overflow traps at runtime instead of wrapping around
+    checked_arithmetic: bool,
 
     datasource_ptrs: HashMap<u32, PointerValue<'ctxt>>,
-    llvm_functions: HashMap<u32, FunctionValue<'ctxt>>,
+    // Functions and closures are keyed by their id along with the key of their instance, which
+    // tells apart the instances of generic functions
+    llvm_functions: HashMap<(u32, String), FunctionValue<'ctxt>>,
     llvm_variables: HashMap<u32, PointerValue<'ctxt>>,
+    // Element variables of array loops and bindings of match arms, which borrow from the value
+    // they came from and are never dropped
+    borrowed_variables: HashSet<u32>,
+    // One function per migration, in the order of the program's migrations
+    migration_fns: Vec<FunctionValue<'ctxt>>,
     table_info: HashMap<u32, GenTableInfo<'ctxt>>,
     struct_info: HashMap<u32, GenStructInfo<'ctxt>>,
+    enum_info: HashMap<u32, GenEnumInfo<'ctxt>>,
     loop_info: HashMap<u32, GenLoopInfo<'ctxt>>,
-    closure_info: HashMap<u32, GenClosureInfo<'ctxt>>,
+    transaction_info: HashMap<u32, GenTransactionInfo<'ctxt>>,
+    closure_info: HashMap<(u32, String), GenClosureInfo<'ctxt>>,
     runtime: Runtime<'ctxt>,
     strings: HashMap<String, GlobalValue<'ctxt>>,
 
     cur_fn: Option<FunctionValue<'ctxt>>,
+    // Type arguments of the instance of a generic function being generated
+    type_args: Vec<SemanticType>,
+    // Returned values are wrapped when the current function returns an optional
+    cur_return_type: Option<SemanticType>,
     vars_to_drop: Vec<u32>,
+    transactions_to_rollback: Vec<u32>,
 
     context: &'ctxt Context,
     builder: Builder<'ctxt>,
@@ -59,6 +82,24 @@ impl<'ctxt> CodeGen<'ctxt> {
             self.gen_database_ptr(&datasource);
         }
 
+        // Structs and enums share ids and are generated in declaration order, so the types
+        // they hold already exist
+        let mut type_ids = self.program.structs.keys()
+            .chain(self.program.enums.keys())
+            .copied()
+            .collect::<Vec<u32>>();
+        type_ids.sort();
+        for type_id in type_ids {
+            // Open instances of generic structs only stand for concrete ones
+            if self.program.struct_instances.get(&type_id).is_some_and(|instance| instance.is_open) {
+                continue;
+            }
+            match self.program.structs.get(&type_id) {
+                Some(sem_struct) => self.gen_struct(sem_struct)?,
+                None => self.gen_enum(&self.program.enums[&type_id])?,
+            }
+        }
+
         for table in self.program.tables.values() {
             self.gen_table(&table)?;
         }
@@ -70,6 +111,9 @@ impl<'ctxt> CodeGen<'ctxt> {
         for function in self.program.functions.values() {
             self.declare_function(function)?;
         }
+        for migration in &self.program.migrations {
+            self.declare_migration(migration);
+        }
 
         // Now, define closures and functions
         for closure in self.program.closures.values() {
@@ -78,6 +122,9 @@ impl<'ctxt> CodeGen<'ctxt> {
         for function in self.program.functions.values() {
             self.define_function(&function)?;
         }
+        for (i, migration) in self.program.migrations.iter().enumerate() {
+            self.define_migration(i, migration)?;
+        }
         
         let main_fn_type = self.int_type().fn_type(
             &[self.int_type().into(), self.ptr_type().into()],
@@ -111,6 +158,8 @@ impl<'ctxt> CodeGen<'ctxt> {
     }
 
     fn int_type(&self) -> IntType<'ctxt> { self.context.i32_type() }
+    fn long_type(&self) -> IntType<'ctxt> { self.context.i64_type() }
+    fn float_type(&self) -> FloatType<'ctxt> { self.context.f64_type() }
     fn bool_type(&self) -> IntType<'ctxt> { self.context.bool_type() }
     fn ptr_type(&self) -> PointerType<'ctxt> { self.context.ptr_type(Default::default()) }
     fn void_type(&self) -> VoidType<'ctxt> { self.context.void_type() }
@@ -125,6 +174,10 @@ impl<'ctxt> CodeGen<'ctxt> {
                 let value = self.gen_eval(expr)?;
                 self.store_var(*variable_id, value)
             }
+            SemanticStatement::PlaceAssignment { place, expr } => {
+                let value = self.gen_eval(expr)?;
+                self.store_place(place, value)
+            }
             SemanticStatement::LoneExpression(expr) => {
                 let value = self.gen_eval(expr)?;
                 self.remove_if_owned(value)
@@ -135,6 +188,15 @@ impl<'ctxt> CodeGen<'ctxt> {
             SemanticStatement::ConditionalLoop { condition, body, id } => {
                 self.gen_loop(condition, body, *id)
             }
+            SemanticStatement::ArrayLoop { array_variable_id, index_variable_id, element_variable_id, body, id } => {
+                self.gen_array_loop(*array_variable_id, *index_variable_id, *element_variable_id, body, *id)
+            }
+            SemanticStatement::Match { variable_id, arms } => {
+                self.gen_match(*variable_id, arms)
+            }
+            SemanticStatement::RangeLoop { variable_id, range, body, id } => {
+                self.gen_range_loop(*variable_id, range, body, *id)
+            }
             SemanticStatement::Return(expr) => {
                 self.gen_return(expr)
             }
@@ -148,15 +210,32 @@ impl<'ctxt> CodeGen<'ctxt> {
                 self.vars_to_drop.push(*variable_id);
                 Ok(())
             }
+            SemanticStatement::Transaction { datasource_id, body, id } => {
+                self.gen_transaction(*datasource_id, body, *id)
+            }
+            SemanticStatement::RollbackTransaction(transaction_id) => {
+                self.transactions_to_rollback.push(*transaction_id);
+                Ok(())
+            }
+            SemanticStatement::SchemaChange { datasource_id, location, change } => {
+                self.gen_schema_change(*datasource_id, location, change)
+            }
         }
     }
 
     fn gen_eval(&mut self, expr: &SemanticExpression) -> Result<GenValue<'ctxt>, CodeGenError> {
-        let expr_type_kind = expr.sem_type.kind();
+        let expr_type = self.resolve_type(&expr.sem_type);
+        let expr_type_kind = expr_type.kind();
         match &expr.kind {
             SemanticExpressionKind::IntegerLiteral(value) => {
                 Ok(GenValue::Integer(self.int_type().const_int(*value as u64, false)))
             },
+            SemanticExpressionKind::LongLiteral(value) => {
+                Ok(GenValue::Integer(self.long_type().const_int(*value as u64, false)))
+            }
+            SemanticExpressionKind::FloatLiteral(value) => {
+                Ok(GenValue::Float(self.float_type().const_float(*value)))
+            },
             SemanticExpressionKind::BoolLiteral(value) => {
                 Ok(GenValue::Bool(self.bool_type().const_int(*value as u64, false)))
             },
@@ -169,6 +248,15 @@ impl<'ctxt> CodeGen<'ctxt> {
                 };
                 self.gen_struct_value(struct_id, fields)
             }
+            SemanticExpressionKind::Variant { variant_index, args } => {
+                let SemanticTypeKind::Enum(enum_id, _) = expr_type_kind else {
+                    panic!("Expected Enum type")
+                };
+                self.gen_variant_value(enum_id, *variant_index, args)
+            }
+            SemanticExpressionKind::Match { value, arms } => {
+                self.gen_match_value(value, arms, &expr_type)
+            }
             SemanticExpressionKind::Array(elements) => {
                 let SemanticTypeKind::Array(elem_type) = expr_type_kind else {
                     panic!("Expected Array type")
@@ -190,23 +278,44 @@ impl<'ctxt> CodeGen<'ctxt> {
                 let index_value = self.gen_eval(index_expr)?;
                 self.gen_array_index(array_value, index_value)
             }
-            SemanticExpressionKind::Add { left, right } => {
+            SemanticExpressionKind::Add { left, right, location } => {
                 let val1 = self.gen_eval(&left)?;
                 let val2 = self.gen_eval(&right)?;
-                self.gen_add(val1, val2)
+                self.gen_add(val1, val2, location)
             }
-            SemanticExpressionKind::Subtract { left, right } => {
+            SemanticExpressionKind::Subtract { left, right, location } => {
                 let val1 = self.gen_eval(&left)?;
                 let val2 = self.gen_eval(&right)?;
-                self.gen_subtract(val1, val2)
+                self.gen_subtract(val1, val2, location)
+            }
+            SemanticExpressionKind::Arithmetic { left, right, op, location } => {
+                let val1 = self.gen_eval(&left)?;
+                let val2 = self.gen_eval(&right)?;
+                self.gen_arithmetic(val1, val2, *op, location)
+            }
+            SemanticExpressionKind::Negate { expr, location } => {
+                let value = self.gen_eval(&expr)?;
+                self.gen_negate(value, location)
+            }
+            SemanticExpressionKind::And { left, right } => {
+                self.gen_logical(left, right, true)
+            }
+            SemanticExpressionKind::Or { left, right } => {
+                self.gen_logical(left, right, false)
+            }
+            SemanticExpressionKind::Not(expr) => {
+                let GenValue::Bool(bool_val) = self.gen_eval(expr)? else {
+                    panic!("Expected bool value");
+                };
+                Ok(GenValue::Bool(self.builder.build_not(bool_val, "not")?))
             }
             SemanticExpressionKind::Compare { left, right, op } => {
                 let val1 = self.gen_eval(&left)?;
                 let val2 = self.gen_eval(&right)?;
                 self.gen_compare(val1, val2, *op)
             }
-            SemanticExpressionKind::DirectFunctionCall { function_id, args } => {
-                self.gen_direct_call(*function_id, args)
+            SemanticExpressionKind::DirectFunctionCall { function_id, type_args, args } => {
+                self.gen_direct_call(*function_id, type_args, args)
             }
             SemanticExpressionKind::IndirectFunctionCall { function_expr, args } => {
                 self.gen_indirect_call(function_expr, args)
@@ -221,10 +330,26 @@ impl<'ctxt> CodeGen<'ctxt> {
             SemanticExpressionKind::ImmediateQuery(query) => {
                 self.gen_immediate_query(query)
             }
+            SemanticExpressionKind::Null => {
+                let SemanticTypeKind::Optional(inner_type) = expr_type_kind else {
+                    panic!("Expected Optional type")
+                };
+                Ok(self.gen_null(&inner_type))
+            }
+            SemanticExpressionKind::IsNull { expr, negated } => {
+                self.gen_is_null(expr, *negated)
+            }
+            SemanticExpressionKind::Unwrap(optional_expr) => {
+                self.gen_unwrap(optional_expr)
+            }
         }
     }
 
-    pub fn gen_code(program: &SemanticProgram) -> Result<(), CodeGenError> {
+    pub fn gen_code(
+        program: &SemanticProgram,
+        verify_schema: bool,
+        checked_arithmetic: bool
+    ) -> Result<(), CodeGenError> {
         let context = Context::create();
         let builder = context.create_builder();
         let module = context.create_module("main");
@@ -247,17 +372,26 @@ impl<'ctxt> CodeGen<'ctxt> {
 
         let codegen = CodeGen {
             program,
+            verify_schema,
+            checked_arithmetic,
             datasource_ptrs: HashMap::new(),
             llvm_variables: HashMap::new(),
+            borrowed_variables: HashSet::new(),
             llvm_functions: HashMap::new(),
+            migration_fns: vec![],
             table_info: HashMap::new(),
             struct_info: HashMap::new(),
+            enum_info: HashMap::new(),
             loop_info: HashMap::new(),
+            transaction_info: HashMap::new(),
             closure_info: HashMap::new(),
             runtime: Runtime::new(&context, &module),
             strings: HashMap::new(),
             cur_fn: None,
+            type_args: vec![],
+            cur_return_type: None,
             vars_to_drop: vec![],
+            transactions_to_rollback: vec![],
             context: &context,
             builder,
             module,
@@ -1,7 +1,10 @@
-use inkwell::values::AnyValue;
+use inkwell::intrinsics::Intrinsic;
+use inkwell::types::IntType;
+use inkwell::values::{AnyValue, FloatValue, FunctionValue, IntValue};
+use inkwell::IntPredicate;
 
 use super::{CodeGen, CodeGenError};
-use crate::{codegen::data::GenValue, semantics::Ownership, tokens::ComparisonType};
+use crate::{codegen::data::GenValue, semantics::{Ownership, SemanticExpression}, tokens::{ArithmeticType, ComparisonType, SourceLocation}};
 
 impl From<ComparisonType> for inkwell::IntPredicate {
     fn from(op: ComparisonType) -> Self {
@@ -16,11 +19,84 @@ impl From<ComparisonType> for inkwell::IntPredicate {
     }
 }
 
+// NaN compares unequal to everything, itself included
+impl From<ComparisonType> for inkwell::FloatPredicate {
+    fn from(op: ComparisonType) -> Self {
+        match op {
+            ComparisonType::Equal => inkwell::FloatPredicate::OEQ,
+            ComparisonType::NotEqual => inkwell::FloatPredicate::UNE,
+            ComparisonType::GreaterThan => inkwell::FloatPredicate::OGT,
+            ComparisonType::LessThan => inkwell::FloatPredicate::OLT,
+            ComparisonType::GreaterThanOrEqual => inkwell::FloatPredicate::OGE,
+            ComparisonType::LessThanOrEqual => inkwell::FloatPredicate::OLE,
+        }
+    }
+}
+
 impl<'ctxt> CodeGen<'ctxt> {
-    pub fn gen_add(&self, val1: GenValue<'ctxt>, val2: GenValue<'ctxt>) -> Result<GenValue<'ctxt>, CodeGenError> {
+    // Branches to a runtime error function, which reports the location and exits, when the condition holds
+    pub(super) fn gen_error_check(
+        &self,
+        failed: IntValue<'ctxt>,
+        error_fn: FunctionValue<'ctxt>,
+        location: &SourceLocation
+    ) -> Result<(), CodeGenError> {
+        let cur_fn = self.builder.get_insert_block().unwrap().get_parent().unwrap();
+        let error_block = self.context.append_basic_block(cur_fn, "arithmetic_error");
+        let continue_block = self.context.append_basic_block(cur_fn, "arithmetic_ok");
+        self.builder.build_conditional_branch(failed, error_block, continue_block)?;
+
+        self.builder.position_at_end(error_block);
+        let location_str = self.builder.build_global_string_ptr(&location.to_string(), "error_location")?;
+        self.builder.build_call(error_fn, &[location_str.as_pointer_value().into()], "arithmetic_error")?;
+        self.builder.build_unreachable()?;
+
+        self.builder.position_at_end(continue_block);
+        Ok(())
+    }
+
+    pub(super) fn gen_overflow_check(&self, overflowed: IntValue<'ctxt>, location: &SourceLocation) -> Result<(), CodeGenError> {
+        self.gen_error_check(overflowed, self.runtime.arithmetic_overflow, location)
+    }
+
+    // Uses one of LLVM's llvm.s*.with.overflow intrinsics, which return the result along with an overflow flag
+    fn gen_checked_int_op(
+        &self,
+        intrinsic_name: &str,
+        int1: IntValue<'ctxt>,
+        int2: IntValue<'ctxt>,
+        location: &SourceLocation
+    ) -> Result<IntValue<'ctxt>, CodeGenError> {
+        let intrinsic = Intrinsic::find(intrinsic_name).unwrap();
+        let intrinsic_fn = intrinsic.get_declaration(&self.module, &[int1.get_type().into()]).unwrap();
+        let result = self.builder.build_call(
+            intrinsic_fn,
+            &[int1.into(), int2.into()],
+            "checked_result"
+        )?.as_any_value_enum().into_struct_value();
+
+        let value = self.builder.build_extract_value(result, 0, "checked_value")?.into_int_value();
+        let overflowed = self.builder.build_extract_value(result, 1, "overflowed")?.into_int_value();
+        self.gen_overflow_check(overflowed, location)?;
+        Ok(value)
+    }
+
+    pub fn gen_add(
+        &self,
+        val1: GenValue<'ctxt>,
+        val2: GenValue<'ctxt>,
+        location: &SourceLocation
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
         if let (GenValue::Integer(int1), GenValue::Integer(int2)) = (&val1, &val2) {
-            let res = self.builder.build_int_add(*int1, *int2, "sum")?;
+            let res = if self.checked_arithmetic {
+                self.gen_checked_int_op("llvm.sadd.with.overflow", *int1, *int2, location)?
+            } else {
+                self.builder.build_int_add(*int1, *int2, "sum")?
+            };
             Ok(GenValue::Integer(res))
+        } else if let (GenValue::Float(float1), GenValue::Float(float2)) = (&val1, &val2) {
+            let res = self.builder.build_float_add(*float1, *float2, "fsum")?;
+            Ok(GenValue::Float(res))
         } else if let (GenValue::String { value: str1, .. }, GenValue::String { value: str2, .. })
             = (&val1, &val2) 
         {
@@ -42,19 +118,207 @@ impl<'ctxt> CodeGen<'ctxt> {
         }
     }
 
-    pub fn gen_subtract(&self, val1: GenValue<'ctxt>, val2: GenValue<'ctxt>) -> Result<GenValue<'ctxt>, CodeGenError> {
+    pub fn gen_subtract(
+        &self,
+        val1: GenValue<'ctxt>,
+        val2: GenValue<'ctxt>,
+        location: &SourceLocation
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
         if let (GenValue::Integer(int1), GenValue::Integer(int2)) = (&val1, &val2) {
-            let res = self.builder.build_int_sub(*int1, *int2, "sub")?;
+            let res = if self.checked_arithmetic {
+                self.gen_checked_int_op("llvm.ssub.with.overflow", *int1, *int2, location)?
+            } else {
+                self.builder.build_int_sub(*int1, *int2, "sub")?
+            };
             Ok(GenValue::Integer(res))
+        } else if let (GenValue::Float(float1), GenValue::Float(float2)) = (&val1, &val2) {
+            let res = self.builder.build_float_sub(*float1, *float2, "fsub")?;
+            Ok(GenValue::Float(res))
         } else {
             panic!("Unexpected types for subtraction");
         }
     }
 
+    pub fn gen_arithmetic(
+        &self,
+        val1: GenValue<'ctxt>,
+        val2: GenValue<'ctxt>,
+        op: ArithmeticType,
+        location: &SourceLocation
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        if let (GenValue::Integer(int1), GenValue::Integer(int2)) = (&val1, &val2) {
+            let (int1, int2) = (*int1, *int2);
+            let res = match op {
+                ArithmeticType::Multiply if self.checked_arithmetic => {
+                    self.gen_checked_int_op("llvm.smul.with.overflow", int1, int2, location)?
+                }
+                ArithmeticType::Multiply => self.builder.build_int_mul(int1, int2, "mul")?,
+                ArithmeticType::Divide | ArithmeticType::Modulo => {
+                    self.gen_int_division(int1, int2, op == ArithmeticType::Modulo, location)?
+                }
+                ArithmeticType::BitwiseAnd => self.builder.build_and(int1, int2, "and")?,
+                ArithmeticType::BitwiseOr => self.builder.build_or(int1, int2, "or")?,
+                ArithmeticType::BitwiseXor => self.builder.build_xor(int1, int2, "xor")?,
+                ArithmeticType::ShiftLeft | ArithmeticType::ShiftRight => {
+                    let amount = self.gen_shift_amount(int2, location)?;
+                    if op == ArithmeticType::ShiftLeft {
+                        self.builder.build_left_shift(int1, amount, "shl")?
+                    } else {
+                        self.builder.build_right_shift(int1, amount, true, "shr")?
+                    }
+                }
+            };
+            Ok(GenValue::Integer(res))
+        } else if let (GenValue::Float(float1), GenValue::Float(float2)) = (&val1, &val2) {
+            let res = match op {
+                ArithmeticType::Multiply => self.builder.build_float_mul(*float1, *float2, "fmul")?,
+                ArithmeticType::Divide => self.builder.build_float_div(*float1, *float2, "fdiv")?,
+                _ => panic!("Unexpected float operation"),
+            };
+            Ok(GenValue::Float(res))
+        } else {
+            panic!("Unexpected types for arithmetic");
+        }
+    }
+
+    // Division by zero always fails at runtime. Dividing the minimum value by -1 overflows, which
+    // LLVM leaves undefined, so -1 is handled apart from other divisors
+    fn gen_int_division(
+        &self,
+        dividend: IntValue<'ctxt>,
+        divisor: IntValue<'ctxt>,
+        is_modulo: bool,
+        location: &SourceLocation
+    ) -> Result<IntValue<'ctxt>, CodeGenError> {
+        let int_type = divisor.get_type();
+        let is_zero = self.builder.build_int_compare(IntPredicate::EQ, divisor, int_type.const_zero(), "is_zero")?;
+        self.gen_error_check(is_zero, self.runtime.division_by_zero, location)?;
+
+        let minus_one = int_type.const_all_ones();
+        let is_minus_one = self.builder.build_int_compare(IntPredicate::EQ, divisor, minus_one, "is_minus_one")?;
+        if self.checked_arithmetic && !is_modulo {
+            let min_value = int_type.const_int(1 << (int_type.get_bit_width() - 1), false);
+            let is_min = self.builder.build_int_compare(IntPredicate::EQ, dividend, min_value, "is_min")?;
+            let overflowed = self.builder.build_and(is_min, is_minus_one, "overflowed")?;
+            self.gen_overflow_check(overflowed, location)?;
+        }
+
+        let safe_divisor = self.builder.build_select(
+            is_minus_one,
+            int_type.const_int(1, false),
+            divisor,
+            "safe_divisor"
+        )?.into_int_value();
+        if is_modulo {
+            // Any number divided by 1 or -1 leaves no remainder
+            return Ok(self.builder.build_int_signed_rem(dividend, safe_divisor, "rem")?);
+        }
+        let quotient = self.builder.build_int_signed_div(dividend, safe_divisor, "div")?;
+        let negated = self.builder.build_int_neg(dividend, "negated")?;
+        Ok(self.builder.build_select(is_minus_one, negated, quotient, "quotient")?.into_int_value())
+    }
+
+    // Converting NaN, an infinity or a float outside the integer type's range gives poison in LLVM,
+    // so the conversion always fails at runtime for those. The bounds are checked once the float
+    // is truncated, as any value truncating to the minimum is still in range
+    pub(super) fn gen_float_to_int(
+        &self,
+        float_val: FloatValue<'ctxt>,
+        int_type: IntType<'ctxt>,
+        location: &SourceLocation
+    ) -> Result<IntValue<'ctxt>, CodeGenError> {
+        let float_type = float_val.get_type();
+        let trunc = Intrinsic::find("llvm.trunc").unwrap()
+            .get_declaration(&self.module, &[float_type.into()])
+            .unwrap();
+        let truncated = self.builder.build_call(trunc, &[float_val.into()], "truncated")?
+            .as_any_value_enum().into_float_value();
+
+        // Powers of two are exact as floats
+        let limit = 2f64.powi(int_type.get_bit_width() as i32 - 1);
+        let above_min = self.builder.build_float_compare(
+            inkwell::FloatPredicate::OGE, truncated, float_type.const_float(-limit), "above_min"
+        )?;
+        let below_max = self.builder.build_float_compare(
+            inkwell::FloatPredicate::OLT, truncated, float_type.const_float(limit), "below_max"
+        )?;
+        let in_range = self.builder.build_and(above_min, below_max, "in_range")?;
+        let out_of_range = self.builder.build_not(in_range, "out_of_range")?;
+        self.gen_error_check(out_of_range, self.runtime.invalid_float_conversion, location)?;
+        Ok(self.builder.build_float_to_signed_int(truncated, int_type, "to_int")?)
+    }
+
+    // Shifting by the bit width or more is undefined in LLVM, so the amount wraps around it
+    // unless arithmetic is checked
+    fn gen_shift_amount(&self, amount: IntValue<'ctxt>, location: &SourceLocation) -> Result<IntValue<'ctxt>, CodeGenError> {
+        let int_type = amount.get_type();
+        let bit_width = int_type.const_int(int_type.get_bit_width() as u64, false);
+        if self.checked_arithmetic {
+            let out_of_range = self.builder.build_int_compare(IntPredicate::UGE, amount, bit_width, "out_of_range")?;
+            self.gen_overflow_check(out_of_range, location)?;
+        }
+        let mask = int_type.const_int(int_type.get_bit_width() as u64 - 1, false);
+        Ok(self.builder.build_and(amount, mask, "shift_amount")?)
+    }
+
+    pub fn gen_negate(&self, value: GenValue<'ctxt>, location: &SourceLocation) -> Result<GenValue<'ctxt>, CodeGenError> {
+        match value {
+            GenValue::Integer(int_val) => {
+                let res = if self.checked_arithmetic {
+                    let zero = int_val.get_type().const_zero();
+                    self.gen_checked_int_op("llvm.ssub.with.overflow", zero, int_val, location)?
+                } else {
+                    self.builder.build_int_neg(int_val, "neg")?
+                };
+                Ok(GenValue::Integer(res))
+            }
+            GenValue::Float(float_val) => Ok(GenValue::Float(self.builder.build_float_neg(float_val, "fneg")?)),
+            _ => panic!("Unexpected type for negation"),
+        }
+    }
+
+    // The right operand is evaluated in its own block, which is skipped when the left operand
+    // already decides the result
+    pub fn gen_logical(
+        &mut self,
+        left: &SemanticExpression,
+        right: &SemanticExpression,
+        is_and: bool
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let GenValue::Bool(left_val) = self.gen_eval(left)? else {
+            panic!("Expected bool value");
+        };
+        let left_block = self.builder.get_insert_block().unwrap();
+        let cur_fn = left_block.get_parent().unwrap();
+        let right_block = self.context.append_basic_block(cur_fn, "logical_right");
+        let after_block = self.context.append_basic_block(cur_fn, "logical_after");
+        if is_and {
+            self.builder.build_conditional_branch(left_val, right_block, after_block)?;
+        } else {
+            self.builder.build_conditional_branch(left_val, after_block, right_block)?;
+        }
+
+        self.builder.position_at_end(right_block);
+        let GenValue::Bool(right_val) = self.gen_eval(right)? else {
+            panic!("Expected bool value");
+        };
+        let right_end_block = self.builder.get_insert_block().unwrap();
+        self.builder.build_unconditional_branch(after_block)?;
+
+        // A skipped right operand leaves the left operand as the result
+        self.builder.position_at_end(after_block);
+        let result = self.builder.build_phi(self.bool_type(), "logical")?;
+        result.add_incoming(&[(&left_val, left_block), (&right_val, right_end_block)]);
+        Ok(GenValue::Bool(result.as_basic_value().into_int_value()))
+    }
+
     pub fn gen_compare(&self, val1: GenValue<'ctxt>, val2: GenValue<'ctxt>, op: ComparisonType) -> Result<GenValue<'ctxt>, CodeGenError> {
         if let (GenValue::Integer(int1), GenValue::Integer(int2)) = (&val1, &val2) {
             let res = self.builder.build_int_compare(op.into(), *int1, *int2, "cmp")?;
             Ok(GenValue::Bool(res))
+        } else if let (GenValue::Float(float1), GenValue::Float(float2)) = (&val1, &val2) {
+            let res = self.builder.build_float_compare(op.into(), *float1, *float2, "fcmp")?;
+            Ok(GenValue::Bool(res))
         } else if let (GenValue::String { value: str1, .. }, GenValue::String { value: str2, .. })
             = (&val1, &val2) 
         {
@@ -68,6 +332,18 @@ impl<'ctxt> CodeGen<'ctxt> {
             self.remove_if_owned(val1)?;
             self.remove_if_owned(val2)?;
 
+            Ok(GenValue::Bool(cmp))
+        } else if let (GenValue::Enum { value: enum1, .. }, GenValue::Enum { value: enum2, .. })
+            = (&val1, &val2)
+        {
+            // Only enums without payloads are compared, so their tags alone tell them apart
+            let tag1 = self.builder.build_extract_value(*enum1, 0, "tag1")?.into_int_value();
+            let tag2 = self.builder.build_extract_value(*enum2, 0, "tag2")?.into_int_value();
+            let cmp = self.builder.build_int_compare(op.into(), tag1, tag2, "enum_cmp")?;
+
+            self.remove_if_owned(val1)?;
+            self.remove_if_owned(val2)?;
+
             Ok(GenValue::Bool(cmp))
         } else {
             panic!("Unexpected types for comparison");
@@ -0,0 +1,178 @@
+use inkwell::AddressSpace;
+use inkwell::types::StructType;
+use inkwell::values::{GlobalValue, StructValue};
+
+use super::{CodeGen, CodeGenError, GenValue};
+use super::runtime::QLType;
+use crate::semantics::{Ownership, SemanticExpression, SemanticType};
+
+impl<'ctxt> CodeGen<'ctxt> {
+    // Optionals are laid out as a presence flag followed by the value, which is zeroed when absent
+    pub(super) fn optional_struct_type(&self, inner_type: &SemanticType) -> StructType<'ctxt> {
+        self.context.struct_type(&[
+            self.bool_type().into(),
+            self.llvm_basic_type(inner_type),
+        ], false)
+    }
+
+    pub(super) fn get_optional_type_info(&self, inner_type: &SemanticType) -> GlobalValue<'ctxt> {
+        let name = format!("__ql__{}?_type_info", inner_type);
+        if let Some(type_info_global) = self.module.get_global(&name) {
+            return type_info_global;
+        }
+
+        let optional_type = self.optional_struct_type(inner_type);
+        let fields_arr = self.runtime.struct_field_type.const_array(&[
+            self.runtime.struct_field_type.const_named_struct(&[
+                self.int_type().const_int(0, false).into(),
+                self.runtime.bool_type_info.as_pointer_value().into(),
+            ]),
+            self.runtime.struct_field_type.const_named_struct(&[
+                self.int_type().const_int(self.target_data.offset_of_element(&optional_type, 1).unwrap(), false).into(),
+                self.get_type_info(inner_type).as_pointer_value().into(),
+            ]),
+        ]);
+        let fields_global = self.module.add_global(
+            self.runtime.struct_field_type.array_type(2),
+            Some(AddressSpace::default()),
+            &format!("__ql__{}?_fields", inner_type)
+        );
+        fields_global.set_initializer(&fields_arr);
+        fields_global.set_constant(true);
+
+        let type_info_value = self.runtime.type_info_type.const_named_struct(&[
+            self.int_type().const_int(QLType::Optional as u64, false).into(),
+            optional_type.size_of().unwrap().into(),
+            self.int_type().const_int(2, false).into(),
+            fields_global.as_pointer_value().into(),
+            self.ptr_type().const_null().into(),
+        ]);
+        let type_info_global = self.module.add_global(
+            self.runtime.type_info_type,
+            Some(AddressSpace::default()),
+            &name
+        );
+        type_info_global.set_initializer(&type_info_value);
+        type_info_global.set_constant(true);
+        type_info_global
+    }
+
+    pub(super) fn gen_null(&self, inner_type: &SemanticType) -> GenValue<'ctxt> {
+        GenValue::Optional {
+            value: self.optional_struct_type(inner_type).const_zero(),
+            inner_type: inner_type.clone(),
+            ownership: Ownership::Trivial,
+        }
+    }
+
+    // Wraps a value into an optional when it is stored where the target type expects one
+    pub(super) fn coerce(&self, value: GenValue<'ctxt>, target_type: &SemanticType) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let target_type = self.resolve_type(target_type);
+        if !target_type.is_optional() || matches!(value, GenValue::Optional { .. }) {
+            return Ok(value);
+        }
+
+        let inner_type = target_type.non_optional();
+        let ownership = value.ownership();
+        let optional_value = self.builder.build_insert_value(
+            self.optional_struct_type(&inner_type).get_undef(),
+            self.bool_type().const_int(1, false),
+            0,
+            "optional_present"
+        )?;
+        let optional_value = self.builder.build_insert_value(
+            optional_value,
+            value.as_llvm_basic_value(),
+            1,
+            "optional_value"
+        )?.into_struct_value();
+
+        Ok(GenValue::Optional {
+            value: optional_value,
+            inner_type,
+            ownership,
+        })
+    }
+
+    // Emits code running on the value held by an optional only when it is not null
+    pub(super) fn build_if_present(
+        &self,
+        optional_value: StructValue<'ctxt>,
+        inner_type: &SemanticType,
+        body: impl FnOnce(GenValue<'ctxt>) -> Result<(), CodeGenError>
+    ) -> Result<(), CodeGenError> {
+        let cur_fn = self.builder.get_insert_block().unwrap().get_parent().unwrap();
+        let present_block = self.context.append_basic_block(cur_fn, "optional_present");
+        let after_block = self.context.append_basic_block(cur_fn, "optional_after");
+
+        let is_present = self.builder.build_extract_value(optional_value, 0, "is_present")?.into_int_value();
+        self.builder.build_conditional_branch(is_present, present_block, after_block)?;
+
+        self.builder.position_at_end(present_block);
+        let value = self.builder.build_extract_value(optional_value, 1, "optional_value")?;
+        body(GenValue::new(inner_type, value, Ownership::Borrowed))?;
+        self.builder.build_unconditional_branch(after_block)?;
+
+        self.builder.position_at_end(after_block);
+        Ok(())
+    }
+
+    pub(super) fn gen_is_null(&mut self, expr: &SemanticExpression, negated: bool) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let optional_value = self.gen_eval(expr)?;
+        let GenValue::Optional { value, .. } = optional_value else {
+            panic!("Expected optional value");
+        };
+
+        let is_present = self.builder.build_extract_value(value, 0, "is_present")?.into_int_value();
+        let result = if negated {
+            is_present
+        } else {
+            self.builder.build_not(is_present, "is_null")?
+        };
+        self.remove_if_owned(optional_value)?;
+        Ok(GenValue::Bool(result))
+    }
+
+    pub(super) fn gen_unwrap(&mut self, expr: &SemanticExpression) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let optional_value = self.gen_eval(expr)?;
+        let GenValue::Optional { value, inner_type, ownership } = optional_value else {
+            panic!("Expected optional value");
+        };
+
+        let inner_value = self.builder.build_extract_value(value, 1, "unwrapped")?;
+        Ok(GenValue::new(&inner_type, inner_value, ownership))
+    }
+
+    pub(super) fn gen_unwrap_or(
+        &self,
+        optional_value: GenValue<'ctxt>,
+        default_value: GenValue<'ctxt>
+    ) -> Result<GenValue<'ctxt>, CodeGenError> {
+        let GenValue::Optional { value, ref inner_type, .. } = optional_value else {
+            panic!("Expected optional value");
+        };
+
+        let is_present = self.builder.build_extract_value(value, 0, "is_present")?.into_int_value();
+        let inner_value = self.builder.build_extract_value(value, 1, "optional_value")?;
+        let result = self.builder.build_select(
+            is_present,
+            inner_value,
+            default_value.as_llvm_basic_value(),
+            "unwrap_or"
+        )?;
+
+        // Take a reference of our own to whichever value was selected before releasing both
+        let borrowed_result = GenValue::new(inner_type, result, Ownership::Borrowed);
+        self.add_ref(&borrowed_result)?;
+        let result_type = inner_type.clone();
+        self.remove_if_owned(optional_value)?;
+        self.remove_if_owned(default_value)?;
+
+        let ownership = if result_type.can_be_owned() {
+            Ownership::Owned
+        } else {
+            Ownership::Trivial
+        };
+        Ok(GenValue::new(&result_type, result, ownership))
+    }
+}
@@ -2,33 +2,64 @@ use inkwell::AddressSpace;
 use inkwell::types::{StructType};
 use inkwell::{context::Context};
 use inkwell::module::{Linkage, Module};
-use inkwell::values::{FunctionValue, GlobalValue, IntValue};
+use inkwell::values::{FunctionValue, GlobalValue};
 
 use crate::codegen::CodeGen;
 use crate::semantics::{SemanticType, SemanticTypeKind};
 
+// Tags of the type infos generated by the compiler, numbered as in the runtime's metadata.h.
+// Those of the other types are defined by the runtime itself
 pub(super) enum QLType {
-    Integer,
-    Bool,
-    String,
-    Array,
-    Struct,
-    Callable,
+    Struct = 6,
+    Optional = 8,
+    Enum = 9,
+}
+
+pub(super) enum WhereNodeType {
+    Equal,
+    NotEqual,
+    GreaterThan,
+    LessThan,
+    GreaterThanOrEqual,
+    LessThanOrEqual,
+    Like,
+    In,
+    IsNull,
+    IsNotNull,
+    And,
+    Or,
+    Not,
+}
+
+pub(super) enum AggregateType {
+    Count,
+    Sum,
+    Average,
+    Minimum,
+    Maximum,
 }
 
 pub(super) struct Runtime<'ctxt> {
     pub(super) type_info_type: StructType<'ctxt>,
     pub(super) struct_field_type: StructType<'ctxt>,
     pub(super) int_type_info: GlobalValue<'ctxt>,
+    pub(super) long_type_info: GlobalValue<'ctxt>,
+    pub(super) float_type_info: GlobalValue<'ctxt>,
     pub(super) bool_type_info: GlobalValue<'ctxt>,
     pub(super) string_type_info: GlobalValue<'ctxt>,
     pub(super) array_type_info: GlobalValue<'ctxt>,
     pub(super) callable_type_info: GlobalValue<'ctxt>,
     pub(super) print_integer: FunctionValue<'ctxt>,
+    pub(super) print_long: FunctionValue<'ctxt>,
+    pub(super) print_float: FunctionValue<'ctxt>,
     pub(super) print_boolean: FunctionValue<'ctxt>,
     pub(super) print_string: FunctionValue<'ctxt>,
     pub(super) input_integer: FunctionValue<'ctxt>,
     pub(super) input_string: FunctionValue<'ctxt>,
+    pub(super) arithmetic_overflow: FunctionValue<'ctxt>,
+    pub(super) division_by_zero: FunctionValue<'ctxt>,
+    pub(super) invalid_float_conversion: FunctionValue<'ctxt>,
+    pub(super) invalid_range_step: FunctionValue<'ctxt>,
 
     pub(super) new_string: FunctionValue<'ctxt>,
     pub(super) add_string_ref: FunctionValue<'ctxt>,
@@ -46,17 +77,41 @@ pub(super) struct Runtime<'ctxt> {
 
     pub(super) init_dbs: FunctionValue<'ctxt>,
     pub(super) close_dbs: FunctionValue<'ctxt>,
+    pub(super) create_table: FunctionValue<'ctxt>,
+    pub(super) verify_table: FunctionValue<'ctxt>,
+    pub(super) check_schema: FunctionValue<'ctxt>,
+
+    // Migration functions
+    pub(super) migration_run: FunctionValue<'ctxt>,
+    pub(super) migration_change_schema: FunctionValue<'ctxt>,
+
+    // Transaction functions
+    pub(super) transaction_begin: FunctionValue<'ctxt>,
+    pub(super) transaction_commit: FunctionValue<'ctxt>,
+    pub(super) transaction_rollback: FunctionValue<'ctxt>,
+
+    // Where clause functions
+    pub(super) where_node_new_condition: FunctionValue<'ctxt>,
+    pub(super) where_node_new_column_comparison: FunctionValue<'ctxt>,
+    pub(super) where_node_new_logical: FunctionValue<'ctxt>,
+
+    // Returning clause functions
+    pub(super) returning_clause_new: FunctionValue<'ctxt>,
+    pub(super) returning_clause_add_column: FunctionValue<'ctxt>,
 
     // Delete query functions
     pub(super) delete_plan_new: FunctionValue<'ctxt>,
     pub(super) delete_plan_set_where: FunctionValue<'ctxt>,
+    pub(super) delete_plan_set_returning: FunctionValue<'ctxt>,
     pub(super) delete_plan_prepare: FunctionValue<'ctxt>,
     pub(super) prepared_delete_bind_where: FunctionValue<'ctxt>,
     pub(super) prepared_delete_exec: FunctionValue<'ctxt>,
+    pub(super) prepared_delete_exec_returning: FunctionValue<'ctxt>,
     pub(super) prepared_delete_finalize: FunctionValue<'ctxt>,
 
     // Insert query functions
     pub(super) insert_plan_new: FunctionValue<'ctxt>,
+    pub(super) insert_plan_set_returning: FunctionValue<'ctxt>,
     pub(super) insert_plan_prepare: FunctionValue<'ctxt>,
     pub(super) prepared_insert_exec_row: FunctionValue<'ctxt>,
     pub(super) prepared_insert_exec_array: FunctionValue<'ctxt>,
@@ -64,19 +119,35 @@ pub(super) struct Runtime<'ctxt> {
 
     // Select query functions
     pub(super) select_plan_new: FunctionValue<'ctxt>,
+    pub(super) select_plan_add_join: FunctionValue<'ctxt>,
+    pub(super) select_plan_set_distinct: FunctionValue<'ctxt>,
+    pub(super) select_plan_add_column: FunctionValue<'ctxt>,
+    pub(super) select_plan_add_aggregate: FunctionValue<'ctxt>,
     pub(super) select_plan_set_where: FunctionValue<'ctxt>,
+    pub(super) select_plan_add_group_by: FunctionValue<'ctxt>,
+    pub(super) select_plan_set_having: FunctionValue<'ctxt>,
+    pub(super) select_plan_add_ordering: FunctionValue<'ctxt>,
+    pub(super) select_plan_set_limit: FunctionValue<'ctxt>,
+    pub(super) select_plan_set_offset: FunctionValue<'ctxt>,
     pub(super) select_plan_prepare: FunctionValue<'ctxt>,
     pub(super) prepared_select_bind_where: FunctionValue<'ctxt>,
+    pub(super) prepared_select_bind_having: FunctionValue<'ctxt>,
+    pub(super) prepared_select_bind_limit: FunctionValue<'ctxt>,
+    pub(super) prepared_select_bind_offset: FunctionValue<'ctxt>,
     pub(super) prepared_select_execute: FunctionValue<'ctxt>,
+    pub(super) prepared_select_execute_scalar: FunctionValue<'ctxt>,
+    pub(super) prepared_select_execute_row: FunctionValue<'ctxt>,
     pub(super) prepared_select_finalize: FunctionValue<'ctxt>,
 
     // Update query functions
     pub(super) update_plan_new: FunctionValue<'ctxt>,
     pub(super) update_plan_set_where: FunctionValue<'ctxt>,
+    pub(super) update_plan_set_returning: FunctionValue<'ctxt>,
     pub(super) update_plan_prepare: FunctionValue<'ctxt>,
     pub(super) prepared_update_bind_where: FunctionValue<'ctxt>,
     pub(super) prepared_update_bind_assignment: FunctionValue<'ctxt>,
     pub(super) prepared_update_exec: FunctionValue<'ctxt>,
+    pub(super) prepared_update_exec_returning: FunctionValue<'ctxt>,
     pub(super) prepared_update_finalize: FunctionValue<'ctxt>,
 
     // Callable functions
@@ -95,6 +166,7 @@ impl<'ctxt> Runtime<'ctxt> {
         let void_type = context.void_type();
         let int_type = context.i32_type();
         let long_type = context.i64_type();
+        let float_type = context.f64_type();
         let bool_type = context.bool_type();
         let ptr_type = context.ptr_type(Default::default());
 
@@ -104,6 +176,18 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let print_long = module.add_function(
+            "printl",
+            void_type.fn_type(&[long_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let print_float = module.add_function(
+            "__ql__printf",
+            void_type.fn_type(&[float_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let print_boolean = module.add_function(
             "printb",
             void_type.fn_type(&[bool_type.into()], false),
@@ -128,6 +212,30 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let arithmetic_overflow = module.add_function(
+            "__ql__arithmetic_overflow",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let division_by_zero = module.add_function(
+            "__ql__division_by_zero",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let invalid_float_conversion = module.add_function(
+            "__ql__invalid_float_conversion",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let invalid_range_step = module.add_function(
+            "__ql__invalid_range_step",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let new_string = module.add_function(
             "__ql__QLString_new",
             ptr_type.fn_type(&[ptr_type.into(), int_type.into(), bool_type.into()], false),
@@ -217,6 +325,88 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let create_table = module.add_function(
+            "__ql__create_table",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let verify_table = module.add_function(
+            "__ql__verify_table",
+            bool_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let check_schema = module.add_function(
+            "__ql__check_schema",
+            void_type.fn_type(&[bool_type.into(), int_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        // Migration functions
+        let migration_run = module.add_function(
+            "__ql__Migration_run",
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let migration_change_schema = module.add_function(
+            "__ql__Migration_change_schema",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        // Transaction functions
+        let transaction_begin = module.add_function(
+            "__ql__Transaction_begin",
+            bool_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let transaction_commit = module.add_function(
+            "__ql__Transaction_commit",
+            void_type.fn_type(&[ptr_type.into(), bool_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let transaction_rollback = module.add_function(
+            "__ql__Transaction_rollback",
+            void_type.fn_type(&[ptr_type.into(), bool_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        // Where clause functions
+        let where_node_new_condition = module.add_function(
+            "__ql__WhereNode_new_condition",
+            ptr_type.fn_type(&[int_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let where_node_new_column_comparison = module.add_function(
+            "__ql__WhereNode_new_column_comparison",
+            ptr_type.fn_type(&[int_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let where_node_new_logical = module.add_function(
+            "__ql__WhereNode_new_logical",
+            ptr_type.fn_type(&[int_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        // Returning clause functions
+        let returning_clause_new = module.add_function(
+            "__ql__ReturningClause_new",
+            ptr_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let returning_clause_add_column = module.add_function(
+            "__ql__ReturningClause_add_column",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         // Delete query functions
         let delete_plan_new = module.add_function(
             "__ql__DeletePlan_new",
@@ -230,21 +420,33 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let delete_plan_set_returning = module.add_function(
+            "__ql__DeletePlan_set_returning",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let delete_plan_prepare = module.add_function(
             "__ql__DeletePlan_prepare",
-            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_delete_bind_where = module.add_function(
             "__ql__PreparedDelete_bind_where",
-            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into()], false),
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_delete_exec = module.add_function(
             "__ql__PreparedDelete_exec",
-            void_type.fn_type(&[ptr_type.into()], false),
+            int_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_delete_exec_returning = module.add_function(
+            "__ql__PreparedDelete_exec_returning",
+            ptr_type.fn_type(&[ptr_type.into()], false),
             Some(Linkage::External),
         );
 
@@ -257,25 +459,31 @@ impl<'ctxt> Runtime<'ctxt> {
         // Insert query functions
         let insert_plan_new = module.add_function(
             "__ql__InsertPlan_new",
-            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let insert_plan_set_returning = module.add_function(
+            "__ql__InsertPlan_set_returning",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let insert_plan_prepare = module.add_function(
             "__ql__InsertPlan_prepare",
-            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_insert_exec_row = module.add_function(
             "__ql__PreparedInsert_exec_row",
-            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_insert_exec_array = module.add_function(
             "__ql__PreparedInsert_exec_array",
-            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
@@ -292,21 +500,93 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let select_plan_add_join = module.add_function(
+            "__ql__SelectPlan_add_join",
+            void_type.fn_type(&[ptr_type.into(), bool_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_set_distinct = module.add_function(
+            "__ql__SelectPlan_set_distinct",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_add_column = module.add_function(
+            "__ql__SelectPlan_add_column",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_add_aggregate = module.add_function(
+            "__ql__SelectPlan_add_aggregate",
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let select_plan_set_where = module.add_function(
             "__ql__SelectPlan_set_where",
             void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
+        let select_plan_add_group_by = module.add_function(
+            "__ql__SelectPlan_add_group_by",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_set_having = module.add_function(
+            "__ql__SelectPlan_set_having",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_add_ordering = module.add_function(
+            "__ql__SelectPlan_add_ordering",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into(), bool_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_set_limit = module.add_function(
+            "__ql__SelectPlan_set_limit",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let select_plan_set_offset = module.add_function(
+            "__ql__SelectPlan_set_offset",
+            void_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let select_plan_prepare = module.add_function(
             "__ql__SelectPlan_prepare",
-            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_select_bind_where = module.add_function(
             "__ql__PreparedSelect_bind_where",
-            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into()], false),
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_select_bind_having = module.add_function(
+            "__ql__PreparedSelect_bind_having",
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_select_bind_limit = module.add_function(
+            "__ql__PreparedSelect_bind_limit",
+            void_type.fn_type(&[ptr_type.into(), int_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_select_bind_offset = module.add_function(
+            "__ql__PreparedSelect_bind_offset",
+            void_type.fn_type(&[ptr_type.into(), int_type.into()], false),
             Some(Linkage::External),
         );
 
@@ -316,6 +596,18 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let prepared_select_execute_scalar = module.add_function(
+            "__ql__PreparedSelect_execute_scalar",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_select_execute_row = module.add_function(
+            "__ql__PreparedSelect_execute_row",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let prepared_select_finalize = module.add_function(
             "__ql__PreparedSelect_finalize",
             void_type.fn_type(&[ptr_type.into()], false),
@@ -335,27 +627,39 @@ impl<'ctxt> Runtime<'ctxt> {
             Some(Linkage::External),
         );
 
+        let update_plan_set_returning = module.add_function(
+            "__ql__UpdatePlan_set_returning",
+            void_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
         let update_plan_prepare = module.add_function(
             "__ql__UpdatePlan_prepare",
-            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into()], false),
+            ptr_type.fn_type(&[ptr_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_update_bind_where = module.add_function(
             "__ql__PreparedUpdate_bind_where",
-            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into()], false),
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_update_bind_assignment = module.add_function(
             "__ql__PreparedUpdate_bind_assignment",
-            void_type.fn_type(&[ptr_type.into(), int_type.into(), int_type.into(), ptr_type.into()], false),
+            void_type.fn_type(&[ptr_type.into(), int_type.into(), ptr_type.into(), ptr_type.into()], false),
             Some(Linkage::External),
         );
 
         let prepared_update_exec = module.add_function(
             "__ql__PreparedUpdate_exec",
-            void_type.fn_type(&[ptr_type.into()], false),
+            int_type.fn_type(&[ptr_type.into()], false),
+            Some(Linkage::External),
+        );
+
+        let prepared_update_exec_returning = module.add_function(
+            "__ql__PreparedUpdate_exec_returning",
+            ptr_type.fn_type(&[ptr_type.into()], false),
             Some(Linkage::External),
         );
 
@@ -421,6 +725,7 @@ impl<'ctxt> Runtime<'ctxt> {
                 long_type.into(),      // size
                 int_type.into(),       // num_fields
                 ptr_type.into(),       // fields
+                ptr_type.into(),       // variant_names
             ],
             false,
         );
@@ -441,6 +746,20 @@ impl<'ctxt> Runtime<'ctxt> {
         );
         int_type_info.set_linkage(Linkage::External);
 
+        let long_type_info = module.add_global(
+            type_info_type,
+            Some(AddressSpace::default()),
+            "__ql__long_type_info"
+        );
+        long_type_info.set_linkage(Linkage::External);
+
+        let float_type_info = module.add_global(
+            type_info_type,
+            Some(AddressSpace::default()),
+            "__ql__float_type_info"
+        );
+        float_type_info.set_linkage(Linkage::External);
+
         let bool_type_info = module.add_global(
             type_info_type,
             Some(AddressSpace::default()),
@@ -473,16 +792,24 @@ impl<'ctxt> Runtime<'ctxt> {
             type_info_type,
             struct_field_type,
             int_type_info,
+            long_type_info,
+            float_type_info,
             bool_type_info,
             string_type_info,
             array_type_info,
             callable_type_info,
 
             print_integer,
+            print_long,
+            print_float,
             print_boolean,
             print_string,
             input_integer,
             input_string,
+            arithmetic_overflow,
+            division_by_zero,
+            invalid_float_conversion,
+            invalid_range_step,
             new_string,
             add_string_ref,
             remove_string_ref,
@@ -498,33 +825,69 @@ impl<'ctxt> Runtime<'ctxt> {
 
             init_dbs,
             close_dbs,
+            create_table,
+            verify_table,
+            check_schema,
+
+            migration_run,
+            migration_change_schema,
+
+            transaction_begin,
+            transaction_commit,
+            transaction_rollback,
+
+            where_node_new_condition,
+            where_node_new_column_comparison,
+            where_node_new_logical,
+
+            returning_clause_new,
+            returning_clause_add_column,
 
             delete_plan_new,
             delete_plan_set_where,
+            delete_plan_set_returning,
             delete_plan_prepare,
             prepared_delete_bind_where,
             prepared_delete_exec,
+            prepared_delete_exec_returning,
             prepared_delete_finalize,
 
             insert_plan_new,
+            insert_plan_set_returning,
             insert_plan_prepare,
             prepared_insert_exec_row,
             prepared_insert_exec_array,
             prepared_insert_finalize,
 
             select_plan_new,
+            select_plan_add_join,
+            select_plan_set_distinct,
+            select_plan_add_column,
+            select_plan_add_aggregate,
             select_plan_set_where,
+            select_plan_add_group_by,
+            select_plan_set_having,
+            select_plan_add_ordering,
+            select_plan_set_limit,
+            select_plan_set_offset,
             select_plan_prepare,
             prepared_select_bind_where,
+            prepared_select_bind_having,
+            prepared_select_bind_limit,
+            prepared_select_bind_offset,
             prepared_select_execute,
+            prepared_select_execute_scalar,
+            prepared_select_execute_row,
             prepared_select_finalize,
 
             update_plan_new,
             update_plan_set_where,
+            update_plan_set_returning,
             update_plan_prepare,
             prepared_update_bind_where,
             prepared_update_bind_assignment,
             prepared_update_exec,
+            prepared_update_exec_returning,
             prepared_update_finalize,
 
             callable_new,
@@ -541,29 +904,21 @@ impl<'ctxt> Runtime<'ctxt> {
 
 impl<'ctxt> CodeGen<'ctxt> {
     // Convert to runtime QLType enum value
-    pub(super) fn get_qltype(&self, sem_type: &SemanticType) -> IntValue<'ctxt> {
-        let enum_value = match sem_type.kind() {
-            SemanticTypeKind::Integer => QLType::Integer,
-            SemanticTypeKind::Bool => QLType::Bool,
-            SemanticTypeKind::String => QLType::String,
-            SemanticTypeKind::Array(_) => QLType::Array,
-            SemanticTypeKind::NamedStruct(_, _) => QLType::Struct,
-            SemanticTypeKind::Callable(_, _) => QLType::Callable,
-            _ => panic!("Unsupported type for type enum conversion"),
-        } as u64;
-        self.int_type().const_int(enum_value, false)
-    }
-
     pub(super) fn get_type_info(&self, sem_type: &SemanticType) -> GlobalValue<'ctxt> {
-        match sem_type.kind() {
+        match self.resolve_type(sem_type).kind() {
             SemanticTypeKind::Integer => self.runtime.int_type_info,
+            SemanticTypeKind::Long => self.runtime.long_type_info,
+            SemanticTypeKind::Ref(table_id, _) => self.get_type_info(&self.ref_key_type(table_id)),
+            SemanticTypeKind::Float => self.runtime.float_type_info,
             SemanticTypeKind::Bool => self.runtime.bool_type_info,
             SemanticTypeKind::String => self.runtime.string_type_info,
             SemanticTypeKind::Array(_) => self.runtime.array_type_info,
             SemanticTypeKind::NamedStruct(struct_id, _) => {
                 self.struct_info[&struct_id].type_info   
             },
+            SemanticTypeKind::Enum(enum_id, _) => self.enum_info[&enum_id].type_info,
             SemanticTypeKind::Callable(_, _) => self.runtime.callable_type_info,
+            SemanticTypeKind::Optional(inner_type) => self.get_optional_type_info(&inner_type),
             _ => panic!("Unsupported type for type info retrieval"),
         }
     }
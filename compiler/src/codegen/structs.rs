@@ -113,6 +113,7 @@ impl<'ctxt> CodeGen<'ctxt> {
             struct_type.size_of().unwrap().into(),
             self.int_type().const_int(num_fields as u64, false).into(),
             fields_global.as_pointer_value().into(),
+            self.ptr_type().const_null().into(),
         ]);
         let type_info_global = self.module.add_global(
             self.runtime.type_info_type,
@@ -161,7 +162,10 @@ impl<'ctxt> CodeGen<'ctxt> {
     ) -> Result<GenValue<'ctxt>, CodeGenError> {
         let sem_struct = &self.program.structs[&struct_id];
         let column_values = sem_struct.field_order.iter()
-            .map(|col_name| self.gen_eval(&columns[col_name]))
+            .map(|col_name| {
+                let column_value = self.gen_eval(&columns[col_name])?;
+                self.coerce(column_value, &sem_struct.fields[col_name])
+            })
             .collect::<Result<Vec<GenValue<'ctxt>>, CodeGenError>>()?;
 
         let struct_info = &self.struct_info[&struct_id];
@@ -1,17 +1,105 @@
 use inkwell::{builder::BuilderError, values::GlobalValue};
 
-use crate::semantics::SemanticTable;
+use crate::semantics::{CheckConstraint, ColumnConstraints, ConstantValue, SemanticTable, SemanticType, SemanticTypeKind};
+use crate::tokens::ComparisonType;
 use super::{CodeGen, CodeGenError};
     
 pub(super) struct GenTableInfo<'a> {
     pub(super) name_str: GlobalValue<'a>,
     pub(super) column_name_strs: Vec<GlobalValue<'a>>,
+    // SQL for each column's constraints, following its type in a column definition
+    pub(super) column_constraint_strs: Vec<GlobalValue<'a>>,
+}
+
+impl ConstantValue {
+    fn to_sql(&self) -> String {
+        match self {
+            ConstantValue::Integer(value) => value.to_string(),
+            ConstantValue::Long(value) => value.to_string(),
+            ConstantValue::Float(value) => format!("{:?}", value),
+            ConstantValue::Bool(value) => (*value as i32).to_string(),
+            ConstantValue::String(value) => format!("'{}'", value.replace('\'', "''")),
+            ConstantValue::Null => String::from("NULL"),
+        }
+    }
+}
+
+impl CheckConstraint {
+    fn to_sql(&self) -> String {
+        match self {
+            CheckConstraint::Comparison { column_name, op, value } => {
+                let op_str = match op {
+                    ComparisonType::Equal => "=",
+                    ComparisonType::NotEqual => "<>",
+                    ComparisonType::GreaterThan => ">",
+                    ComparisonType::LessThan => "<",
+                    ComparisonType::GreaterThanOrEqual => ">=",
+                    ComparisonType::LessThanOrEqual => "<=",
+                };
+                format!("{} {} {}", column_name, op_str, value.to_sql())
+            },
+            CheckConstraint::Like { column_name, pattern } => {
+                format!("{} LIKE {}", column_name, ConstantValue::String(pattern.clone()).to_sql())
+            },
+            CheckConstraint::In { column_name, values } => {
+                let value_strs = values.iter().map(|value| value.to_sql()).collect::<Vec<String>>();
+                format!("{} IN ({})", column_name, value_strs.join(", "))
+            },
+            CheckConstraint::IsNull { column_name, negated } => {
+                format!("{} IS {}NULL", column_name, if *negated { "NOT " } else { "" })
+            },
+            CheckConstraint::And(left, right) => format!("({} AND {})", left.to_sql(), right.to_sql()),
+            CheckConstraint::Or(left, right) => format!("({} OR {})", left.to_sql(), right.to_sql()),
+            CheckConstraint::Not(inner) => format!("NOT ({})", inner.to_sql()),
+        }
+    }
+}
+
+impl ColumnConstraints {
+    fn to_sql(&self) -> String {
+        let mut sql = String::new();
+        if self.is_primary_key {
+            sql.push_str(" PRIMARY KEY");
+        }
+        if self.is_autoincrement {
+            sql.push_str(" AUTOINCREMENT");
+        }
+        if self.is_unique {
+            sql.push_str(" UNIQUE");
+        }
+        for check in &self.checks {
+            sql.push_str(&format!(" CHECK ({})", check.to_sql()));
+        }
+        if let Some(default) = &self.default {
+            sql.push_str(&format!(" DEFAULT {}", default.to_sql()));
+        }
+        sql
+    }
 }
 
 impl<'ctxt> CodeGen<'ctxt> {
+    // SQL following a column's type in its definition, with the foreign key of a reference column
+    pub(super) fn column_constraints_sql(&self, sem_type: &SemanticType, constraints: &ColumnConstraints) -> String {
+        let mut sql = constraints.to_sql();
+        if let SemanticTypeKind::Ref(table_id, table_name) = sem_type.non_optional().kind() {
+            let table = &self.program.tables[&table_id];
+            let key_column = table.key_column().expect("Referenced tables have a primary key");
+            let key_name = &self.program.structs[&table.struct_id].field_order[key_column as usize];
+            sql.push_str(&format!(" REFERENCES {}({})", table_name, key_name));
+        }
+        sql
+    }
+
+    // References are held as the primary key of the row they point to, which is an int or a long
+    pub(super) fn ref_key_type(&self, table_id: u32) -> SemanticType {
+        let table = &self.program.tables[&table_id];
+        let key_column = table.key_column().expect("Referenced tables have a primary key");
+        let table_struct = &self.program.structs[&table.struct_id];
+        table_struct.fields[&table_struct.field_order[key_column as usize]].clone()
+    }
+
     pub fn gen_table(&mut self, table: &SemanticTable) -> Result<(), CodeGenError> {
         let table_struct = &self.program.structs[&table.struct_id];
-        self.gen_struct(table_struct)?;
 
         let name_str = self.builder.build_global_string_ptr(&table.name, &format!("{}_name", table.name))?;
         let column_name_strs = table_struct.field_order
@@ -22,10 +110,20 @@ impl<'ctxt> CodeGen<'ctxt> {
                     &format!("{}_col_{}", table.name, i)
                 )
             }).collect::<Result<Vec<GlobalValue>, BuilderError>>()?;
+        let column_constraint_strs = table.constraints
+            .iter().enumerate()
+            .map(|(i, constraints)| {
+                let column_type = &table_struct.fields[&table_struct.field_order[i]];
+                self.builder.build_global_string_ptr(
+                    &self.column_constraints_sql(column_type, constraints),
+                    &format!("{}_col_constraints_{}", table.name, i)
+                )
+            }).collect::<Result<Vec<GlobalValue>, BuilderError>>()?;
         
         let gen_table_info = GenTableInfo {
             name_str,
             column_name_strs,
+            column_constraint_strs,
         };
 
         self.table_info.insert(table.id, gen_table_info);
@@ -1,10 +1,12 @@
-use crate::semantics::Ownership;
+use inkwell::values::{AnyValue, PointerValue};
+
+use crate::semantics::{Ownership, SemanticExpression, SemanticExpressionKind, SemanticType, SemanticTypeKind};
 use super::{CodeGen, CodeGenError, data::GenValue};
 
 impl<'ctxt> CodeGen<'ctxt> {
     pub(super) fn load_var(&self, variable_id: u32) -> Result<GenValue<'ctxt>, CodeGenError> {
         let variable_ptr = self.llvm_variables[&variable_id];
-        let var_type = &self.program.variables[&variable_id].sem_type;
+        let var_type = &self.resolve_type(&self.program.variables[&variable_id].sem_type);
         let llvm_type = self.llvm_basic_type(var_type);
         let loaded_value = self.builder.build_load(llvm_type, variable_ptr, "load")?;
         Ok(GenValue::new(var_type, loaded_value, Ownership::Borrowed))
@@ -15,6 +17,7 @@ impl<'ctxt> CodeGen<'ctxt> {
         let llvm_type = self.llvm_basic_type(&variable.sem_type);
         let pointer = self.builder.build_alloca(llvm_type, &variable.name)?;
 
+        let value = self.coerce(value, &variable.sem_type)?;
         self.add_ref(&value)?;
         self.builder.build_store(pointer, value.as_llvm_basic_value())?;
         self.llvm_variables.insert(variable.id, pointer);        
@@ -29,14 +32,71 @@ impl<'ctxt> CodeGen<'ctxt> {
             self.remove_ref(prev_value)?;
         }
 
+        let value = self.coerce(value, var_type)?;
         self.add_ref(&value)?;
         self.builder.build_store(variable_ptr, value.as_llvm_basic_value())?;
         Ok(())
     }
 
+    // The address of a place is only taken once the assigned value is evaluated, as evaluating it
+    // may grow the arrays the place lies in
+    pub(super) fn store_place(&mut self, place: &SemanticExpression, value: GenValue<'ctxt>) -> Result<(), CodeGenError> {
+        let (place_ptr, place_type) = self.gen_place_ptr(place)?;
+        let value = self.coerce(value, &place_type)?;
+        // The new value is referenced before the old one is released, in case they are the same
+        self.add_ref(&value)?;
+        if place_type.can_be_owned() {
+            let llvm_type = self.llvm_basic_type(&place_type);
+            let prev_value = self.builder.build_load(llvm_type, place_ptr, "load_prev")?;
+            self.remove_ref(GenValue::new(&place_type, prev_value, Ownership::Borrowed))?;
+        }
+        self.builder.build_store(place_ptr, value.as_llvm_basic_value())?;
+        Ok(())
+    }
+
+    // Indices of places are hidden variables, so finding a place's address runs no user code
+    fn gen_place_ptr(&mut self, place: &SemanticExpression) -> Result<(PointerValue<'ctxt>, SemanticType), CodeGenError> {
+        match &place.kind {
+            SemanticExpressionKind::Variable(variable_id) => {
+                let var_type = self.resolve_type(&self.program.variables[variable_id].sem_type);
+                Ok((self.llvm_variables[variable_id], var_type))
+            }
+            SemanticExpressionKind::StructField { struct_expr, index } => {
+                let (struct_ptr, struct_type) = self.gen_place_ptr(struct_expr)?;
+                let SemanticTypeKind::NamedStruct(struct_id, _) = struct_type.kind() else {
+                    panic!("Expected struct type");
+                };
+                let sem_struct = &self.program.structs[&struct_id];
+                let field_name = &sem_struct.field_order[*index as usize];
+                let field_ptr = self.builder.build_struct_gep(
+                    self.struct_info[&struct_id].struct_type,
+                    struct_ptr,
+                    *index,
+                    &format!("{}.{}", sem_struct.name, field_name)
+                )?;
+                Ok((field_ptr, sem_struct.fields[field_name].clone()))
+            }
+            SemanticExpressionKind::ArrayIndex { array_expr, index_expr } => {
+                let (array_slot_ptr, array_type) = self.gen_place_ptr(array_expr)?;
+                let SemanticTypeKind::Array(elem_type) = array_type.kind() else {
+                    panic!("Expected array type");
+                };
+                let array_ptr = self.builder.build_load(self.ptr_type(), array_slot_ptr, "load_array")?;
+                let index_value = self.gen_eval(index_expr)?;
+                let elem_ptr = self.builder.build_call(
+                    self.runtime.index_array,
+                    &[array_ptr.into(), index_value.as_llvm_basic_value().into()],
+                    "array_index"
+                )?.as_any_value_enum().into_pointer_value();
+                Ok((elem_ptr, elem_type))
+            }
+            _ => panic!("Expected variable, struct field or array element"),
+        }
+    }
+
     pub(super) fn drop_var(&self, variable_id: u32) -> Result<(), CodeGenError> {
         let variable = &self.program.variables[&variable_id];
-        if variable.sem_type.can_be_owned() {
+        if variable.sem_type.can_be_owned() && !self.borrowed_variables.contains(&variable_id) {
             let value = self.load_var(variable_id)?;
             self.remove_ref(value)?;
         }
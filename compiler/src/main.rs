@@ -1,14 +1,15 @@
-use std::io::Read;
 use std::io::{Error as IOError, ErrorKind};
-use std::fs::File;
 use std::env::args;
+use std::path::Path;
 use std::process::Command;
 use lalrpop_util::lalrpop_mod;
 
 use crate::semantics::SemanticGen;
 use crate::codegen::CodeGen;
+use crate::modules::ModuleLoader;
 
 mod tokens;
+mod modules;
 mod semantics;
 mod codegen;
 
@@ -17,32 +18,29 @@ lalrpop_mod!(pub grammar);
 fn main() -> Result<(), IOError> {
     let args: Vec<String> = args().collect();
     if args.len() < 3 {
-        eprintln!("Usage: {} <source-file> <object-file>", args[0]);
+        eprintln!("Usage: {} <source-file> <object-file> [--skip-schema-check] [--checked-arithmetic]", args[0]);
         return Err(IOError::new(ErrorKind::InvalidInput, "Not enough arguments"));
     }
 
     let source_filepath = &args[1];
     let obj_filepath = &args[2];
+    let verify_schema = !args[3..].iter().any(|arg| arg == "--skip-schema-check");
+    let checked_arithmetic = args[3..].iter().any(|arg| arg == "--checked-arithmetic");
 
-    let parser = grammar::ProgramParser::new();
-    let mut source = String::new();
-    let mut file = File::open(source_filepath)?;
-    file.read_to_string(&mut source)?;
-
-    // Parsing
-    let program = parser.parse(&source).map_err(|e| {
-        eprintln!("Failed to parse {source_filepath}: \n{e}");
+    // Parsing, along with every module the program imports
+    let modules = ModuleLoader::load_program(Path::new(source_filepath)).map_err(|e| {
+        eprintln!("{e}");
         IOError::new(ErrorKind::InvalidData, "Parsing failed")
     })?;
 
     // Semantic analysis
-    let semantic_program = SemanticGen::gen_semantic(&program).map_err(|e| {
+    let semantic_program = SemanticGen::gen_semantic(&modules).map_err(|e| {
         eprintln!("Failed to analyze {source_filepath}: \n{e}");
         IOError::new(ErrorKind::InvalidData, "Semantic analysis failed")
     })?;
 
     // Code generation
-    CodeGen::gen_code(&semantic_program).map_err(|e| {
+    CodeGen::gen_code(&semantic_program, verify_schema, checked_arithmetic).map_err(|e| {
         eprintln!("Failed to build {source_filepath}: \n{e}");
         IOError::new(ErrorKind::InvalidData, "Building failed")
     })?;
@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::Error as IOError;
+use std::path::{Path, PathBuf};
+
+use crate::grammar;
+use crate::tokens::*;
+
+// A source file of the program, along with the modules it imports
+pub struct ModuleNode {
+    // The module's path from the root module's directory, such as models::people, which is empty
+    // for the root module
+    pub name: String,
+    pub program: ProgramNode,
+    // Names of the modules this one imports, by the name their functions and types are qualified with
+    pub imports: HashMap<String, String>,
+}
+
+pub enum ModuleError {
+    Read {
+        path: PathBuf,
+        error: IOError,
+    },
+    Parse {
+        path: PathBuf,
+        message: String,
+    },
+    InvalidModuleName {
+        path: PathBuf,
+    },
+    DuplicateImport {
+        path: PathBuf,
+        module_name: String,
+    },
+    ImportCycle {
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl Display for ModuleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), std::fmt::Error> {
+        match self {
+            ModuleError::Read { path, error } => {
+                write!(f, "Failed to read {}: {}", path.display(), error)
+            }
+            ModuleError::Parse { path, message } => {
+                write!(f, "Failed to parse {}: \n{}", path.display(), message)
+            }
+            ModuleError::InvalidModuleName { path } => {
+                write!(f, "Cannot import {}, as its file name is not a valid module name", path.display())
+            }
+            ModuleError::DuplicateImport { path, module_name } => {
+                write!(f, "Module {} is imported multiple times in {}", module_name, path.display())
+            }
+            ModuleError::ImportCycle { paths } => {
+                let paths = paths.iter()
+                    .map(|path| path.display().to_string())
+                    .collect::<Vec<String>>();
+                write!(f, "Modules import each other in a cycle: {}", paths.join(" -> "))
+            }
+        }
+    }
+}
+
+pub struct ModuleLoader {
+    root_dir: PathBuf,
+    // Names of the modules loaded so far, by their canonical path
+    loaded: HashMap<PathBuf, String>,
+    // Modules whose imports are being loaded, which may not be imported again until they are done
+    loading: Vec<PathBuf>,
+    modules: Vec<ModuleNode>,
+}
+
+impl ModuleLoader {
+    // Loads the root module and every module it imports, directly or not. Each module is loaded
+    // once however many modules import it, and comes after the modules it imports
+    pub fn load_program(root_path: &Path) -> Result<Vec<ModuleNode>, ModuleError> {
+        let root_path = Self::canonicalize(root_path)?;
+        let mut loader = ModuleLoader {
+            root_dir: root_path.parent().unwrap().to_path_buf(),
+            loaded: HashMap::new(),
+            loading: vec![],
+            modules: vec![],
+        };
+        loader.load_module(root_path, String::new())?;
+        Ok(loader.modules)
+    }
+
+    fn canonicalize(path: &Path) -> Result<PathBuf, ModuleError> {
+        path.canonicalize().map_err(|error| ModuleError::Read {
+            path: path.to_path_buf(),
+            error,
+        })
+    }
+
+    fn load_module(&mut self, path: PathBuf, name: String) -> Result<(), ModuleError> {
+        let source = fs::read_to_string(&path).map_err(|error| ModuleError::Read {
+            path: path.clone(),
+            error,
+        })?;
+        let program = grammar::ProgramParser::new().parse(&source, &source).map_err(|e| ModuleError::Parse {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+
+        self.loading.push(path.clone());
+        let dir = path.parent().unwrap().to_path_buf();
+        let mut imports = HashMap::new();
+        for import in &program.imports {
+            let (import_path, import_name) = match import {
+                ImportNode::File(file_path) => {
+                    let import_path = dir.join(file_path);
+                    let import_name = import_path.file_stem()
+                        .map(|stem| stem.to_string_lossy().into_owned())
+                        .filter(|stem| Self::is_module_name(stem))
+                        .ok_or_else(|| ModuleError::InvalidModuleName { path: import_path.clone() })?;
+                    (import_path, import_name)
+                }
+                ImportNode::Module(module_path) => {
+                    let mut import_path = dir.join(module_path.join("/"));
+                    import_path.set_extension("ql");
+                    (import_path, module_path.last().unwrap().clone())
+                }
+            };
+
+            let import_path = Self::canonicalize(&import_path)?;
+            if let Some(start) = self.loading.iter().position(|loading_path| *loading_path == import_path) {
+                let mut paths = self.loading[start..].to_vec();
+                paths.push(import_path);
+                return Err(ModuleError::ImportCycle { paths });
+            }
+            let module_name = match self.loaded.get(&import_path) {
+                Some(module_name) => module_name.clone(),
+                None => {
+                    let module_name = self.module_name(&import_path);
+                    self.load_module(import_path, module_name.clone())?;
+                    module_name
+                }
+            };
+            if imports.insert(import_name.clone(), module_name).is_some() {
+                return Err(ModuleError::DuplicateImport {
+                    path: path.clone(),
+                    module_name: import_name,
+                });
+            }
+        }
+        self.loading.pop();
+
+        self.loaded.insert(path, name.clone());
+        self.modules.push(ModuleNode { name, program, imports });
+        Ok(())
+    }
+
+    // Modules are named after their path from the root module's directory, with super standing
+    // for a parent directory, so modules in different directories never share a name
+    fn module_name(&self, path: &Path) -> String {
+        let root_components = self.root_dir.components().collect::<Vec<_>>();
+        let path = path.with_extension("");
+        let components = path.components().collect::<Vec<_>>();
+        let common_len = root_components.iter()
+            .zip(&components)
+            .take_while(|(root_component, component)| root_component == component)
+            .count();
+
+        let mut segments = vec!["super".to_string(); root_components.len() - common_len];
+        segments.extend(components[common_len..].iter()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned()));
+        segments.join("::")
+    }
+
+    // Imported files are referred to by their name, which must be usable as a qualifier
+    fn is_module_name(name: &str) -> bool {
+        let name = name.trim_start_matches('_');
+        name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+}
@@ -1,12 +1,36 @@
+use std::fmt::Display;
+
 use super::*;
 
+impl Display for ArithmeticType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArithmeticType::Multiply => write!(f, "multiplication"),
+            ArithmeticType::Divide => write!(f, "division"),
+            ArithmeticType::Modulo => write!(f, "remainder"),
+            ArithmeticType::BitwiseAnd => write!(f, "bitwise and"),
+            ArithmeticType::BitwiseOr => write!(f, "bitwise or"),
+            ArithmeticType::BitwiseXor => write!(f, "bitwise xor"),
+            ArithmeticType::ShiftLeft => write!(f, "left shift"),
+            ArithmeticType::ShiftRight => write!(f, "right shift"),
+        }
+    }
+}
+
 impl SemanticGen {
-    pub(super) fn eval_add(&mut self, left: &ExpressionNode, right: &ExpressionNode) -> Result<SemanticExpression, SemanticError> {
+    pub(super) fn eval_add(
+        &mut self,
+        left: &ExpressionNode,
+        location: SourceLocation,
+        right: &ExpressionNode
+    ) -> Result<SemanticExpression, SemanticError> {
         let sem_left = self.eval_expr(left)?;
         let sem_right = self.eval_expr(right)?;
         
         match (&sem_left.sem_type.kind(), &sem_right.sem_type.kind()) {
             (SemanticTypeKind::Integer, SemanticTypeKind::Integer) => {},
+            (SemanticTypeKind::Long, SemanticTypeKind::Long) => {},
+            (SemanticTypeKind::Float, SemanticTypeKind::Float) => {},
             (SemanticTypeKind::String, SemanticTypeKind::String) => {},
             _ => {
                 return Err(SemanticError::IncompatibleOperands {
@@ -27,16 +51,24 @@ impl SemanticGen {
             kind: SemanticExpressionKind::Add {
                 left: Box::new(sem_left),
                 right: Box::new(sem_right),
+                location,
             },
         })
     }
 
-    pub(super) fn eval_subtract(&mut self, left: &ExpressionNode, right: &ExpressionNode) -> Result<SemanticExpression, SemanticError> {
+    pub(super) fn eval_subtract(
+        &mut self,
+        left: &ExpressionNode,
+        location: SourceLocation,
+        right: &ExpressionNode
+    ) -> Result<SemanticExpression, SemanticError> {
         let sem_left = self.eval_expr(left)?;
         let sem_right = self.eval_expr(right)?;
 
         match (&sem_left.sem_type.kind(), &sem_right.sem_type.kind()) {
             (SemanticTypeKind::Integer, SemanticTypeKind::Integer) => {},
+            (SemanticTypeKind::Long, SemanticTypeKind::Long) => {},
+            (SemanticTypeKind::Float, SemanticTypeKind::Float) => {},
             _ => {
                 return Err(SemanticError::IncompatibleOperands {
                     operation: "subtraction".to_string(),
@@ -56,6 +88,66 @@ impl SemanticGen {
             kind: SemanticExpressionKind::Subtract {
                 left: Box::new(sem_left),
                 right: Box::new(sem_right),
+                location,
+            },
+        })
+    }
+
+    pub(super) fn eval_arithmetic(
+        &mut self,
+        left: &ExpressionNode,
+        location: SourceLocation,
+        op: ArithmeticType,
+        right: &ExpressionNode
+    ) -> Result<SemanticExpression, SemanticError> {
+        let sem_left = self.eval_expr(left)?;
+        let sem_right = self.eval_expr(right)?;
+
+        // Floats only support the operations that are not defined on bits
+        let allows_float = matches!(op, ArithmeticType::Multiply | ArithmeticType::Divide);
+        match (&sem_left.sem_type.kind(), &sem_right.sem_type.kind()) {
+            (SemanticTypeKind::Integer, SemanticTypeKind::Integer) => {},
+            (SemanticTypeKind::Long, SemanticTypeKind::Long) => {},
+            (SemanticTypeKind::Float, SemanticTypeKind::Float) if allows_float => {},
+            _ => {
+                return Err(SemanticError::IncompatibleOperands {
+                    operation: op.to_string(),
+                    left_type: sem_left.sem_type.clone(),
+                    right_type: sem_right.sem_type.clone(),
+                });
+            }
+        }
+
+        Ok(SemanticExpression {
+            sem_type: sem_left.sem_type.clone(),
+            ownership: Ownership::Trivial,
+            kind: SemanticExpressionKind::Arithmetic {
+                left: Box::new(sem_left),
+                right: Box::new(sem_right),
+                op,
+                location,
+            },
+        })
+    }
+
+    pub(super) fn eval_negate(&mut self, location: SourceLocation, expr: &ExpressionNode) -> Result<SemanticExpression, SemanticError> {
+        let sem_expr = self.eval_expr(expr)?;
+        match sem_expr.sem_type.kind() {
+            SemanticTypeKind::Integer | SemanticTypeKind::Long | SemanticTypeKind::Float => {},
+            _ => {
+                return Err(SemanticError::IncompatibleOperand {
+                    operation: "negation".to_string(),
+                    operand_type: sem_expr.sem_type.clone(),
+                });
+            }
+        }
+
+        Ok(SemanticExpression {
+            sem_type: sem_expr.sem_type.clone(),
+            ownership: Ownership::Trivial,
+            kind: SemanticExpressionKind::Negate {
+                expr: Box::new(sem_expr),
+                location,
             },
         })
     }
@@ -69,11 +161,30 @@ impl SemanticGen {
         let sem_left = self.eval_expr(left)?;
         let sem_right = self.eval_expr(right)?;
 
+        // Comparing with null checks whether an optional holds a value
+        if op == ComparisonType::Equal || op == ComparisonType::NotEqual {
+            let negated = op == ComparisonType::NotEqual;
+            if let SemanticExpressionKind::Null = sem_right.kind {
+                return self.eval_null_check(sem_left, negated);
+            }
+            if let SemanticExpressionKind::Null = sem_left.kind {
+                return self.eval_null_check(sem_right, negated);
+            }
+        }
+
         match (&sem_left.sem_type.kind(), &sem_right.sem_type.kind()) {
             (SemanticTypeKind::Integer, SemanticTypeKind::Integer) |
+            (SemanticTypeKind::Long, SemanticTypeKind::Long) |
+            (SemanticTypeKind::Float, SemanticTypeKind::Float) |
             (SemanticTypeKind::String, SemanticTypeKind::String) => {},
             (SemanticTypeKind::Bool, SemanticTypeKind::Bool)
                 if op == ComparisonType::Equal || op == ComparisonType::NotEqual => {},
+            (SemanticTypeKind::Ref(table_a, _), SemanticTypeKind::Ref(table_b, _))
+                if table_a == table_b && (op == ComparisonType::Equal || op == ComparisonType::NotEqual) => {},
+            // Only enums without payloads compare equal by their variant alone
+            (SemanticTypeKind::Enum(enum_a, _), SemanticTypeKind::Enum(enum_b, _))
+                if enum_a == enum_b && self.enums[*enum_a].is_unit_only()
+                    && (op == ComparisonType::Equal || op == ComparisonType::NotEqual) => {},
             _ => {
                 return Err(SemanticError::IncompatibleOperands {
                     operation: "comparison".to_string(),
@@ -93,4 +204,49 @@ impl SemanticGen {
             },
         })
     }
-}
\ No newline at end of file
+
+    pub(super) fn eval_logical(
+        &mut self,
+        left: &ExpressionNode,
+        right: &ExpressionNode,
+        is_and: bool
+    ) -> Result<SemanticExpression, SemanticError> {
+        let sem_left = self.eval_expr(left)?;
+        let sem_right = self.eval_expr(right)?;
+
+        if sem_left.sem_type != SemanticTypeKind::Bool || sem_right.sem_type != SemanticTypeKind::Bool {
+            return Err(SemanticError::IncompatibleOperands {
+                operation: if is_and { "and" } else { "or" }.to_string(),
+                left_type: sem_left.sem_type.clone(),
+                right_type: sem_right.sem_type.clone(),
+            });
+        }
+
+        let (left, right) = (Box::new(sem_left), Box::new(sem_right));
+        Ok(SemanticExpression {
+            sem_type: SemanticType::new(SemanticTypeKind::Bool),
+            ownership: Ownership::Trivial,
+            kind: if is_and {
+                SemanticExpressionKind::And { left, right }
+            } else {
+                SemanticExpressionKind::Or { left, right }
+            },
+        })
+    }
+
+    pub(super) fn eval_not(&mut self, expr: &ExpressionNode) -> Result<SemanticExpression, SemanticError> {
+        let sem_expr = self.eval_expr(expr)?;
+        if sem_expr.sem_type != SemanticTypeKind::Bool {
+            return Err(SemanticError::IncompatibleOperand {
+                operation: "not".to_string(),
+                operand_type: sem_expr.sem_type.clone(),
+            });
+        }
+
+        Ok(SemanticExpression {
+            sem_type: SemanticType::new(SemanticTypeKind::Bool),
+            ownership: Ownership::Trivial,
+            kind: SemanticExpressionKind::Not(Box::new(sem_expr)),
+        })
+    }
+}
@@ -7,6 +7,8 @@ pub struct SemanticClosure {
     pub param_ids: Vec<u32>,
     pub captured_variables: Vec<(u32, u32)>,
     pub return_type: SemanticType,
+    // The generic function the closure is written in, for which it is built once per instance
+    pub generic_function_id: Option<u32>,
     pub body: SemanticClosureBody,
 }
 
@@ -60,6 +62,7 @@ impl SemanticGen {
             param_ids,
             captured_variables: vec![],
             return_type: sem_ret_type.clone(),
+            generic_function_id: self.generic_function,
             body: SemanticClosureBody::dummy()
         });
 
@@ -0,0 +1,408 @@
+use std::fmt::Display;
+
+use super::*;
+
+#[derive(Clone, PartialEq)]
+pub enum ConstantValue {
+    Integer(i32),
+    Long(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Null,
+}
+
+// A check constraint over the columns of a single row
+#[derive(Clone, PartialEq)]
+pub enum CheckConstraint {
+    Comparison {
+        column_name: String,
+        op: ComparisonType,
+        value: ConstantValue,
+    },
+    Like {
+        column_name: String,
+        pattern: String,
+    },
+    In {
+        column_name: String,
+        values: Vec<ConstantValue>,
+    },
+    IsNull {
+        column_name: String,
+        negated: bool,
+    },
+    And(Box<CheckConstraint>, Box<CheckConstraint>),
+    Or(Box<CheckConstraint>, Box<CheckConstraint>),
+    Not(Box<CheckConstraint>),
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct ColumnConstraints {
+    pub is_primary_key: bool,
+    pub is_autoincrement: bool,
+    pub is_unique: bool,
+    pub checks: Vec<CheckConstraint>,
+    pub default: Option<ConstantValue>,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SemanticColumn {
+    pub name: String,
+    pub sem_type: SemanticType,
+    pub constraints: ColumnConstraints,
+}
+
+impl ConstantValue {
+    fn sem_type(&self) -> SemanticType {
+        SemanticType::new(match self {
+            ConstantValue::Integer(_) => SemanticTypeKind::Integer,
+            ConstantValue::Long(_) => SemanticTypeKind::Long,
+            ConstantValue::Float(_) => SemanticTypeKind::Float,
+            ConstantValue::Bool(_) => SemanticTypeKind::Bool,
+            ConstantValue::String(_) => SemanticTypeKind::String,
+            ConstantValue::Null => SemanticTypeKind::Optional(SemanticType::new(SemanticTypeKind::Any)),
+        })
+    }
+
+    // Null fills optional columns, while other values must match the type the column holds
+    fn fits_column(&self, column_type: &SemanticType) -> bool {
+        match self {
+            ConstantValue::Null => column_type.is_optional(),
+            _ => self.sem_type() == column_type.non_optional(),
+        }
+    }
+}
+
+impl CheckConstraint {
+    pub(super) fn rename_column(&mut self, column_name: &str, new_name: &str) {
+        match self {
+            CheckConstraint::Comparison { column_name: name, .. }
+            | CheckConstraint::Like { column_name: name, .. }
+            | CheckConstraint::In { column_name: name, .. }
+            | CheckConstraint::IsNull { column_name: name, .. } => {
+                if name == column_name {
+                    *name = new_name.to_string();
+                }
+            },
+            CheckConstraint::And(left, right) | CheckConstraint::Or(left, right) => {
+                left.rename_column(column_name, new_name);
+                right.rename_column(column_name, new_name);
+            },
+            CheckConstraint::Not(inner) => inner.rename_column(column_name, new_name),
+        }
+    }
+}
+
+impl ColumnConstraints {
+    // Whether inserts may leave the column out, letting the database fill it in
+    pub fn is_omittable(&self) -> bool {
+        self.is_autoincrement || self.default.is_some()
+    }
+}
+
+impl Display for ConstantValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConstantValue::Integer(value) => write!(f, "{}", value),
+            ConstantValue::Long(value) => write!(f, "{}L", value),
+            ConstantValue::Float(value) => write!(f, "{:?}", value),
+            ConstantValue::Bool(value) => write!(f, "{}", value),
+            ConstantValue::String(value) => write!(f, "\"{}\"", value),
+            ConstantValue::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl Display for CheckConstraint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckConstraint::Comparison { column_name, op, value } => {
+                let op_str = match op {
+                    ComparisonType::Equal => "==",
+                    ComparisonType::NotEqual => "!=",
+                    ComparisonType::GreaterThan => ">",
+                    ComparisonType::LessThan => "<",
+                    ComparisonType::GreaterThanOrEqual => ">=",
+                    ComparisonType::LessThanOrEqual => "<=",
+                };
+                write!(f, "{} {} {}", column_name, op_str, value)
+            },
+            CheckConstraint::Like { column_name, pattern } => write!(f, "{} like \"{}\"", column_name, pattern),
+            CheckConstraint::In { column_name, values } => {
+                let value_strs = values.iter().map(|value| value.to_string()).collect::<Vec<String>>();
+                write!(f, "{} in [{}]", column_name, value_strs.join(", "))
+            },
+            CheckConstraint::IsNull { column_name, negated } => {
+                write!(f, "{} is {}null", column_name, if *negated { "not " } else { "" })
+            },
+            CheckConstraint::And(left, right) => write!(f, "({} and {})", left, right),
+            CheckConstraint::Or(left, right) => write!(f, "({} or {})", left, right),
+            CheckConstraint::Not(inner) => write!(f, "not {}", inner),
+        }
+    }
+}
+
+impl Display for SemanticColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.name, self.sem_type)?;
+        if self.constraints.is_primary_key {
+            write!(f, " primary key")?;
+        }
+        if self.constraints.is_autoincrement {
+            write!(f, " autoincrement")?;
+        }
+        if self.constraints.is_unique {
+            write!(f, " unique")?;
+        }
+        for check in &self.constraints.checks {
+            write!(f, " check ({})", check)?;
+        }
+        if let Some(default) = &self.constraints.default {
+            write!(f, " default {}", default)?;
+        }
+        Ok(())
+    }
+}
+
+fn eval_constant(expr: &ExpressionNode) -> Result<Option<ConstantValue>, SemanticError> {
+    Ok(match expr {
+        ExpressionNode::IntegerLiteral(value) => Some(ConstantValue::Integer(int_literal(*value)?)),
+        ExpressionNode::LongLiteral(value) => Some(ConstantValue::Long(*value)),
+        ExpressionNode::FloatLiteral(value) => Some(ConstantValue::Float(*value)),
+        ExpressionNode::BoolLiteral(value) => Some(ConstantValue::Bool(*value)),
+        ExpressionNode::StringLiteral(value) => Some(ConstantValue::String(value.clone())),
+        ExpressionNode::NullLiteral => Some(ConstantValue::Null),
+        ExpressionNode::Negate(_, expr) => match eval_constant(expr)? {
+            Some(ConstantValue::Integer(value)) => value.checked_neg().map(ConstantValue::Integer),
+            Some(ConstantValue::Long(value)) => value.checked_neg().map(ConstantValue::Long),
+            Some(ConstantValue::Float(value)) => Some(ConstantValue::Float(-value)),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+impl SemanticGen {
+    fn eval_column_type(&mut self, table_name: &str, column_node: &ColumnNode) -> Result<SemanticType, SemanticError> {
+        // Optional columns are nullable, holding values of their inner type
+        let stored_type_node = match &column_node.type_node {
+            TypeNode::Optional(inner_type_node) => inner_type_node.as_ref(),
+            type_node => type_node,
+        };
+        let is_storable = match stored_type_node {
+            TypeNode::Integer | TypeNode::Long | TypeNode::Float | TypeNode::Bool | TypeNode::String | TypeNode::Ref(_) => true,
+            // Enums without payloads are stored as the names of their variants
+            TypeNode::Named(name, type_args) if type_args.is_empty() => {
+                self.enums.get_by_name(&self.resolve_name(name)?).is_some_and(|sem_enum| sem_enum.is_unit_only())
+            },
+            _ => false,
+        };
+        if !is_storable {
+            return Err(SemanticError::NonPrimitiveColumnType {
+                table_name: table_name.to_string(),
+                column_name: column_node.name.clone()
+            });
+        }
+        self.try_get_semantic_type(&column_node.type_node)
+    }
+
+    // Resolves the declared columns of a table, along with their constraints
+    pub(super) fn eval_columns(
+        &mut self,
+        table_name: &str,
+        column_nodes: &[ColumnNode],
+    ) -> Result<Vec<SemanticColumn>, SemanticError> {
+        // Column types come first, as checks may refer to any column of the table
+        let mut column_types: Vec<(String, SemanticType)> = vec![];
+        for column_node in column_nodes {
+            if column_types.iter().any(|(name, _)| *name == column_node.name) {
+                return Err(SemanticError::DuplicateColumn {
+                    table_name: table_name.to_string(),
+                    column_name: column_node.name.clone(),
+                });
+            }
+            column_types.push((column_node.name.clone(), self.eval_column_type(table_name, column_node)?));
+        }
+
+        let mut columns: Vec<SemanticColumn> = vec![];
+        for (column_node, (name, sem_type)) in column_nodes.iter().zip(&column_types) {
+            let constraints = self.eval_column_constraints(table_name, &column_types, column_node, sem_type)?;
+            if constraints.is_primary_key && columns.iter().any(|column| column.constraints.is_primary_key) {
+                return Err(SemanticError::MultiplePrimaryKeys { table_name: table_name.to_string() });
+            }
+            columns.push(SemanticColumn {
+                name: name.clone(),
+                sem_type: sem_type.clone(),
+                constraints,
+            });
+        }
+        Ok(columns)
+    }
+
+    // Resolves a column added to an existing table, whose checks may refer to the columns already there
+    pub(super) fn eval_added_column(
+        &mut self,
+        table_name: &str,
+        existing_columns: &[SemanticColumn],
+        column_node: &ColumnNode,
+    ) -> Result<SemanticColumn, SemanticError> {
+        let sem_type = self.eval_column_type(table_name, column_node)?;
+        let mut column_types = existing_columns.iter()
+            .map(|column| (column.name.clone(), column.sem_type.clone()))
+            .collect::<Vec<(String, SemanticType)>>();
+        column_types.push((column_node.name.clone(), sem_type.clone()));
+        let constraints = self.eval_column_constraints(table_name, &column_types, column_node, &sem_type)?;
+        Ok(SemanticColumn {
+            name: column_node.name.clone(),
+            sem_type,
+            constraints,
+        })
+    }
+
+    fn eval_column_constraints(
+        &self,
+        table_name: &str,
+        column_types: &[(String, SemanticType)],
+        column_node: &ColumnNode,
+        sem_type: &SemanticType,
+    ) -> Result<ColumnConstraints, SemanticError> {
+        let duplicate_constraint = |constraint: &'static str| SemanticError::DuplicateColumnConstraint {
+            table_name: table_name.to_string(),
+            column_name: column_node.name.clone(),
+            constraint,
+        };
+
+        let mut constraints = ColumnConstraints::default();
+        for constraint_node in &column_node.constraints {
+            match constraint_node {
+                ColumnConstraintNode::PrimaryKey => {
+                    if constraints.is_primary_key {
+                        return Err(duplicate_constraint("primary key"));
+                    }
+                    constraints.is_primary_key = true;
+                },
+                ColumnConstraintNode::Autoincrement => {
+                    if constraints.is_autoincrement {
+                        return Err(duplicate_constraint("autoincrement"));
+                    }
+                    constraints.is_autoincrement = true;
+                },
+                ColumnConstraintNode::Unique => {
+                    if constraints.is_unique {
+                        return Err(duplicate_constraint("unique"));
+                    }
+                    constraints.is_unique = true;
+                },
+                ColumnConstraintNode::Check(where_node) => {
+                    let check = self.eval_check_constraint(table_name, column_types, where_node)?;
+                    constraints.checks.push(check);
+                },
+                ColumnConstraintNode::Default(value_expr) => {
+                    if constraints.default.is_some() {
+                        return Err(duplicate_constraint("default"));
+                    }
+                    let value = eval_constant(value_expr)?.expect("Default values are parsed as literals");
+                    if !value.fits_column(sem_type) {
+                        return Err(SemanticError::IncompatibleColumnValue {
+                            table_name: table_name.to_string(),
+                            column_name: column_node.name.clone(),
+                            expected: sem_type.clone(),
+                            found: value.sem_type(),
+                        });
+                    }
+                    constraints.default = Some(value);
+                },
+            }
+        }
+
+        // SQLite only autoincrements integer primary keys
+        let is_integer = matches!(sem_type.kind(), SemanticTypeKind::Integer | SemanticTypeKind::Long);
+        if constraints.is_autoincrement && !(constraints.is_primary_key && is_integer) {
+            return Err(SemanticError::InvalidAutoincrement {
+                table_name: table_name.to_string(),
+                column_name: column_node.name.clone(),
+            });
+        }
+        Ok(constraints)
+    }
+
+    fn eval_check_constraint(
+        &self,
+        table_name: &str,
+        column_types: &[(String, SemanticType)],
+        where_node: &WhereNode,
+    ) -> Result<CheckConstraint, SemanticError> {
+        let resolve_column = |column: &ColumnRefNode| {
+            if let Some(column_table_name) = &column.table_name && column_table_name != table_name {
+                return Err(SemanticError::TableNotInQuery { table_name: column_table_name.clone() });
+            }
+            column_types.iter()
+                .find(|(name, _)| *name == column.column_name)
+                .map(|(name, sem_type)| (name.clone(), sem_type.clone()))
+                .ok_or_else(|| SemanticError::UndefinedColumn {
+                    table_name: table_name.to_string(),
+                    column_name: column.column_name.clone(),
+                })
+        };
+        let eval_value = |column_name: &str, column_type: &SemanticType, expr: &ExpressionNode| {
+            let value = eval_constant(expr)?.ok_or_else(|| SemanticError::NonConstantCheckValue {
+                table_name: table_name.to_string(),
+                column_name: column_name.to_string(),
+            })?;
+            if value.sem_type() != column_type.non_optional() {
+                return Err(SemanticError::IncompatibleColumnValue {
+                    table_name: table_name.to_string(),
+                    column_name: column_name.to_string(),
+                    expected: column_type.clone(),
+                    found: value.sem_type(),
+                });
+            }
+            Ok(value)
+        };
+
+        match where_node {
+            WhereNode::Comparison { column, op, value } => {
+                let (column_name, column_type) = resolve_column(column)?;
+                let value = eval_value(&column_name, &column_type, value)?;
+                Ok(CheckConstraint::Comparison { column_name, op: *op, value })
+            },
+            WhereNode::Like { column, pattern } => {
+                let (column_name, column_type) = resolve_column(column)?;
+                match eval_value(&column_name, &column_type, pattern)? {
+                    ConstantValue::String(pattern) => Ok(CheckConstraint::Like { column_name, pattern }),
+                    value => Err(SemanticError::IncompatibleOperands {
+                        left_type: column_type,
+                        right_type: value.sem_type(),
+                        operation: "LIKE".to_string(),
+                    }),
+                }
+            },
+            WhereNode::In { column, values } => {
+                let (column_name, column_type) = resolve_column(column)?;
+                let ExpressionNode::Array(elements) = values.as_ref() else {
+                    return Err(SemanticError::NonConstantCheckValue { table_name: table_name.to_string(), column_name });
+                };
+                let values = elements.iter()
+                    .map(|element| eval_value(&column_name, &column_type, element))
+                    .collect::<Result<Vec<ConstantValue>, SemanticError>>()?;
+                Ok(CheckConstraint::In { column_name, values })
+            },
+            WhereNode::IsNull { column, negated } => {
+                let (column_name, _) = resolve_column(column)?;
+                Ok(CheckConstraint::IsNull { column_name, negated: *negated })
+            },
+            WhereNode::And(left, right) => Ok(CheckConstraint::And(
+                Box::new(self.eval_check_constraint(table_name, column_types, left)?),
+                Box::new(self.eval_check_constraint(table_name, column_types, right)?),
+            )),
+            WhereNode::Or(left, right) => Ok(CheckConstraint::Or(
+                Box::new(self.eval_check_constraint(table_name, column_types, left)?),
+                Box::new(self.eval_check_constraint(table_name, column_types, right)?),
+            )),
+            WhereNode::Not(inner) => Ok(CheckConstraint::Not(
+                Box::new(self.eval_check_constraint(table_name, column_types, inner)?),
+            )),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use super::*;
+use super::optionals::null_checked_variable;
 
 pub struct SemanticBlock {
     pub statements: Vec<SemanticStatement>,
@@ -6,7 +7,7 @@ pub struct SemanticBlock {
 }
 
 impl SemanticStatement {
-    fn is_terminating(&self) -> bool {
+    pub(super) fn is_terminating(&self) -> bool {
         match self {
             SemanticStatement::Conditional { branches, else_branch } => {
                 let all_branches_terminate = branches.iter().all(|branch| {
@@ -18,6 +19,8 @@ impl SemanticStatement {
                 };
                 all_branches_terminate && else_terminates
             }
+            SemanticStatement::Match { arms, .. } => arms.iter().all(|arm| arm.body.terminates),
+            SemanticStatement::Transaction { body, .. } => body.terminates,
             SemanticStatement::Return(_) => true,
             SemanticStatement::Break(_) => true,
             SemanticStatement::Continue(_) => true,
@@ -76,6 +79,8 @@ impl SemanticGen {
         branches: &[ConditionalBranchNode],
         else_branch: &Option<Vec<StatementNode>>
     ) -> Result<SemanticStatement, SemanticError> {
+        // Optionals found to be null in a condition hold values in every later branch
+        let mut non_null_variables: Vec<u32> = vec![];
         let mut sem_branches = Vec::new();
         for branch in branches {
             let sem_condition = self.eval_expr(&branch.condition)?;
@@ -84,7 +89,18 @@ impl SemanticGen {
                     found_type: sem_condition.sem_type.clone(),
                 });
             }
-            let sem_block = self.eval_block(&branch.body, SemanticScopeType::Block)?;
+            let null_check = null_checked_variable(&sem_condition);
+            let mut branch_non_null_variables = non_null_variables.clone();
+            match null_check {
+                Some((var_id, true)) if !branch_non_null_variables.contains(&var_id) => {
+                    branch_non_null_variables.push(var_id);
+                },
+                Some((var_id, false)) if !non_null_variables.contains(&var_id) => {
+                    non_null_variables.push(var_id);
+                },
+                _ => {},
+            }
+            let sem_block = self.eval_narrowed_block(&branch.body, &branch_non_null_variables)?;
             sem_branches.push(SemanticConditionalBranch {
                 condition: sem_condition,
                 body: sem_block,
@@ -93,7 +109,7 @@ impl SemanticGen {
 
         let else_body = match else_branch {
             Some(else_statements) => {
-                let sem_else_block = self.eval_block(else_statements, SemanticScopeType::Block)?;
+                let sem_else_block = self.eval_narrowed_block(else_statements, &non_null_variables)?;
                 Some(sem_else_block)
             },
             None => None,
@@ -131,6 +147,149 @@ impl SemanticGen {
         })
     }
 
+    // The array is held by a hidden variable in a scope around the loop, keeping it alive
+    // even if the body reassigns the variable it came from
+    pub(super) fn eval_array_loop(
+        &mut self,
+        index_name: Option<&str>,
+        element_name: &str,
+        array: &ExpressionNode,
+        body: &[StatementNode],
+        label: &Option<String>,
+    ) -> Result<Vec<SemanticStatement>, SemanticError> {
+        let sem_array = self.eval_expr(array)?;
+        let SemanticTypeKind::Array(elem_type) = sem_array.sem_type.kind() else {
+            return Err(SemanticError::NonArrayLoop {
+                sem_type: sem_array.sem_type.clone(),
+            });
+        };
+        if index_name == Some(element_name) {
+            return Err(SemanticError::DuplicateVariableDefinition {
+                name: element_name.to_string(),
+            });
+        }
+
+        self.enter_scope(SemanticScopeType::Block);
+        let array_variable_id = self.declare_bound_variable("#array", sem_array.sem_type.clone());
+        let mut stmts = vec![SemanticStatement::VariableDeclaration {
+            variable_id: array_variable_id,
+            init_expr: sem_array,
+        }];
+
+        let loop_id = self.loop_id_gen.next_id();
+        self.enter_scope(SemanticScopeType::Loop(loop_id));
+        let index_variable_id = index_name.map(|name| {
+            self.declare_bound_variable(name, SemanticType::new(SemanticTypeKind::Integer))
+        });
+        let element_variable_id = self.declare_bound_variable(element_name, elem_type);
+
+        self.loops.push((label.clone(), loop_id));
+        let mut sem_body = self.eval_block(body, SemanticScopeType::Block)?;
+        self.loops.pop();
+        sem_body.statements.extend(self.exit_scope(!sem_body.terminates));
+
+        stmts.push(SemanticStatement::ArrayLoop {
+            array_variable_id,
+            index_variable_id,
+            element_variable_id,
+            body: sem_body,
+            id: loop_id,
+        });
+        // Drops only run at the end of the enclosing block, so the loop releases the array itself
+        self.exit_scope(false);
+        Ok(stmts)
+    }
+
+    pub(super) fn eval_range_loop(
+        &mut self,
+        variable_name: &str,
+        range: &RangeNode,
+        body: &[StatementNode],
+        label: &Option<String>,
+    ) -> Result<SemanticStatement, SemanticError> {
+        let sem_start = self.eval_expr(&range.start)?;
+        let sem_end = self.eval_expr(&range.end)?;
+        let range_type = sem_start.sem_type.clone();
+        if !matches!(range_type.kind(), SemanticTypeKind::Integer | SemanticTypeKind::Long) {
+            return Err(SemanticError::NonIntegralRange { sem_type: range_type });
+        }
+        if sem_end.sem_type != range_type {
+            return Err(SemanticError::IncompatibleOperands {
+                operation: "range".to_string(),
+                left_type: range_type,
+                right_type: sem_end.sem_type,
+            });
+        }
+        let sem_step = match &range.step {
+            Some((location, step)) => {
+                let sem_step = self.eval_expr(step)?;
+                if sem_step.sem_type != range_type {
+                    return Err(SemanticError::IncompatibleOperands {
+                        operation: "range step".to_string(),
+                        left_type: range_type,
+                        right_type: sem_step.sem_type,
+                    });
+                }
+                Some((*location, sem_step))
+            }
+            None => None,
+        };
+
+        let loop_id = self.loop_id_gen.next_id();
+        self.enter_scope(SemanticScopeType::Loop(loop_id));
+        let variable_id = self.declare_bound_variable(variable_name, range_type);
+
+        self.loops.push((label.clone(), loop_id));
+        let mut sem_body = self.eval_block(body, SemanticScopeType::Block)?;
+        self.loops.pop();
+        sem_body.statements.extend(self.exit_scope(!sem_body.terminates));
+
+        Ok(SemanticStatement::RangeLoop {
+            variable_id,
+            range: SemanticRange {
+                start: sem_start,
+                end: sem_end,
+                inclusive: range.inclusive,
+                step: sem_step,
+            },
+            body: sem_body,
+            id: loop_id,
+        })
+    }
+
+    pub(super) fn declare_bound_variable(&mut self, name: &str, sem_type: SemanticType) -> u32 {
+        let variable_id = self.variable_id_gen.next_id();
+        self.scopes.last_mut().unwrap().variables.insert(name.to_string(), variable_id);
+        self.variables.insert(variable_id, SemanticVariable {
+            name: name.to_string(),
+            id: variable_id,
+            sem_type,
+        });
+        self.bound_variables.insert(variable_id);
+        variable_id
+    }
+
+    pub(super) fn eval_transaction(
+        &mut self,
+        datasource_name: &str,
+        body: &[StatementNode],
+    ) -> Result<SemanticStatement, SemanticError> {
+        let datasource_id = self.datasources.get_by_name(datasource_name)
+            .ok_or_else(|| SemanticError::UndefinedDatasource {
+                name: datasource_name.to_string()
+            })?
+            .id;
+
+        let transaction_id = self.transaction_id_gen.next_id();
+        let sem_body = self.eval_block(body, SemanticScopeType::Transaction(transaction_id))?;
+
+        Ok(SemanticStatement::Transaction {
+            datasource_id,
+            body: sem_body,
+            id: transaction_id,
+        })
+    }
+
     pub(super) fn eval_return(
         &mut self,
         expr: Option<&ExpressionNode>,
@@ -158,7 +317,7 @@ impl SemanticGen {
         };
 
 
-        // Drop variables up to (but not including) functional scope
+        // Drop variables and roll back transactions up to (but not including) functional scope
         let mut stmts: Vec<SemanticStatement> = vec![];
         for scope in self.scopes.iter().rev() {
             match scope.scope_type {
@@ -170,6 +329,9 @@ impl SemanticGen {
                 let drop_stmt = SemanticStatement::DropVariable(*var_id);
                 stmts.push(drop_stmt);
             }
+            if let SemanticScopeType::Transaction(transaction_id) = scope.scope_type {
+                stmts.push(SemanticStatement::RollbackTransaction(transaction_id));
+            }
         }
         let return_stmt = SemanticStatement::Return(sem_expr);
         stmts.push(return_stmt);
@@ -185,13 +347,16 @@ impl SemanticGen {
             }
         })?;
 
-        // Drop variables in the current loop scope
+        // Drop variables and roll back transactions in the current loop scope
         let mut stmts: Vec<SemanticStatement> = vec![];
         for scope in self.scopes.iter().rev() {
             for var_id in scope.variables.values() {
                 let drop_stmt = SemanticStatement::DropVariable(*var_id);
                 stmts.push(drop_stmt);
             }
+            if let SemanticScopeType::Transaction(transaction_id) = scope.scope_type {
+                stmts.push(SemanticStatement::RollbackTransaction(transaction_id));
+            }
             if scope.scope_type == SemanticScopeType::Loop(loop_id) {
                 break;
             }
@@ -212,13 +377,16 @@ impl SemanticGen {
             }
         })?;
 
-        // Drop variables in the current loop scope
+        // Drop variables and roll back transactions in the current loop scope
         let mut stmts: Vec<SemanticStatement> = vec![];
         for scope in self.scopes.iter().rev() {
             for var_id in scope.variables.values() {
                 let drop_stmt = SemanticStatement::DropVariable(*var_id);
                 stmts.push(drop_stmt);
-            } 
+            }
+            if let SemanticScopeType::Transaction(transaction_id) = scope.scope_type {
+                stmts.push(SemanticStatement::RollbackTransaction(transaction_id));
+            }
             if scope.scope_type == SemanticScopeType::Loop(loop_id) {
                 break;
             }
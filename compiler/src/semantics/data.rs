@@ -14,9 +14,66 @@ pub struct SemanticStruct {
     pub id: u32,
     pub fields: HashMap<String, SemanticType>,
     pub field_order: Vec<String>,
+    pub is_synthesized: bool,
 }
 
 impl SemanticGen {
+    // Fields may only refer to types declared before this struct, so a struct never contains itself
+    pub(super) fn define_struct(
+        &mut self,
+        name: &str,
+        is_public: bool,
+        type_params: &[String],
+        field_nodes: &[TypedQNameNode]
+    ) -> Result<(), SemanticError> {
+        let name = &self.declared_name(name);
+        if self.type_exists(name) {
+            return Err(SemanticError::DuplicateTypeDefinition { name: name.to_string() });
+        }
+        self.declare_visibility(name, is_public);
+        if !type_params.is_empty() {
+            return self.define_generic_struct(name, type_params, field_nodes);
+        }
+
+        let (fields, field_order) = self.eval_struct_fields(name, field_nodes)?;
+        let struct_id = self.struct_id_gen.next_id();
+        self.structs.insert(name.to_string(), struct_id, SemanticStruct {
+            name: name.to_string(),
+            id: struct_id,
+            fields,
+            field_order,
+            is_synthesized: false,
+        });
+        Ok(())
+    }
+
+    pub(super) fn eval_struct_fields(
+        &mut self,
+        struct_name: &str,
+        field_nodes: &[TypedQNameNode]
+    ) -> Result<(HashMap<String, SemanticType>, Vec<String>), SemanticError> {
+        let mut fields = HashMap::new();
+        let mut field_order = vec![];
+        for field_node in field_nodes {
+            if fields.contains_key(&field_node.name) {
+                return Err(SemanticError::DuplicateStructField {
+                    struct_name: struct_name.to_string(),
+                    field_name: field_node.name.clone(),
+                });
+            }
+            let field_type = self.try_get_semantic_type(&field_node.type_node)?;
+            if field_type == SemanticTypeKind::Void {
+                return Err(SemanticError::VoidFieldType {
+                    struct_name: struct_name.to_string(),
+                    field_name: field_node.name.clone(),
+                });
+            }
+            fields.insert(field_node.name.clone(), field_type);
+            field_order.push(field_node.name.clone());
+        }
+        Ok((fields, field_order))
+    }
+
     pub fn eval_struct(&mut self, name: Option<&str>, column_values: &[ColumnValueNode]) -> Result<SemanticExpression, SemanticError> {
         let mut fields = HashMap::new();
         for col_val in column_values {
@@ -32,7 +89,11 @@ impl SemanticGen {
             field_types.insert(field_name.clone(), sem_expr.sem_type.clone());
         }
 
-        let struct_type = match name {
+        let resolved_name = name.map(|struct_name| self.resolve_name(struct_name)).transpose()?;
+        let struct_type = match resolved_name.as_deref() {
+            Some(struct_name) if self.generic_structs.contains_key(struct_name) => {
+                self.eval_generic_struct_literal(struct_name, field_types)?
+            },
             Some(struct_name) => {
                 if let Some(named_struct) = self.structs.get_by_name(struct_name) {
                     if self.try_downcast_struct(&named_struct.fields, &mut field_types) {
@@ -109,7 +170,12 @@ impl SemanticGen {
         let mut sem_exprs: Vec<SemanticExpression> = vec![];
         for elem in elements {
             let mut sem_expr = self.eval_expr(elem)?;
-            if !self.try_unify(&sem_expr.sem_type, &elem_type) {
+            // Arrays mixing values with nulls hold optionals of those values
+            if sem_expr.sem_type.is_optional() && elem_type.is_concrete() && !elem_type.is_optional() {
+                elem_type.make_optional();
+                self.note_optional_type_param(&elem_type.non_optional());
+            }
+            if !self.try_unify(&elem_type, &sem_expr.sem_type) {
                 return Err(SemanticError::HeterogeneousArray {
                     type_a: elem_type,
                     type_b: sem_expr.sem_type,
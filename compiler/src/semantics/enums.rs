@@ -0,0 +1,344 @@
+use super::*;
+
+pub struct SemanticEnum {
+    pub name: String,
+    pub id: u32,
+    pub variants: Vec<SemanticVariant>,
+}
+
+pub struct SemanticVariant {
+    pub name: String,
+    // The struct holding the fields of the variant's payload, which variants without fields lack
+    pub payload_struct_id: Option<u32>,
+}
+
+// A payload field bound to a variable by a match pattern
+struct BoundField {
+    field_index: u32,
+    name: String,
+    sem_type: SemanticType,
+}
+
+impl SemanticEnum {
+    pub fn is_unit_only(&self) -> bool {
+        self.variants.iter().all(|variant| variant.payload_struct_id.is_none())
+    }
+
+    fn variant_index(&self, variant_name: &str) -> Option<usize> {
+        self.variants.iter().position(|variant| variant.name == variant_name)
+    }
+}
+
+impl SemanticGen {
+    // Payloads are structs of their own named after the variant, so like the fields of a struct
+    // they may only refer to types declared before the enum
+    pub(super) fn define_enum(
+        &mut self,
+        name: &str,
+        is_public: bool,
+        variant_nodes: &[VariantNode]
+    ) -> Result<(), SemanticError> {
+        let name = &self.declared_name(name);
+        if self.type_exists(name) {
+            return Err(SemanticError::DuplicateTypeDefinition { name: name.to_string() });
+        }
+        self.declare_visibility(name, is_public);
+
+        let mut variants: Vec<SemanticVariant> = vec![];
+        for variant_node in variant_nodes {
+            if variants.iter().any(|variant| variant.name == variant_node.name) {
+                return Err(SemanticError::DuplicateVariant {
+                    enum_name: name.to_string(),
+                    variant_name: variant_node.name.clone(),
+                });
+            }
+
+            let payload_struct_id = if variant_node.fields.is_empty() {
+                None
+            } else {
+                let payload_name = format!("{}.{}", name, variant_node.name);
+                let (fields, field_order) = self.eval_struct_fields(&payload_name, &variant_node.fields)?;
+                let struct_id = self.struct_id_gen.next_id();
+                self.structs.insert(payload_name.clone(), struct_id, SemanticStruct {
+                    name: payload_name,
+                    id: struct_id,
+                    fields,
+                    field_order,
+                    is_synthesized: false,
+                });
+                Some(struct_id)
+            };
+            variants.push(SemanticVariant {
+                name: variant_node.name.clone(),
+                payload_struct_id,
+            });
+        }
+
+        // Enums share ids with structs and take theirs after their payloads, so codegen can
+        // build both kinds of types in the order they were declared
+        let enum_id = self.struct_id_gen.next_id();
+        self.enums.insert(name.to_string(), enum_id, SemanticEnum {
+            name: name.to_string(),
+            id: enum_id,
+            variants,
+        });
+        Ok(())
+    }
+
+    fn payload_fields(&self, payload_struct_id: Option<u32>) -> Vec<(String, SemanticType)> {
+        let Some(struct_id) = payload_struct_id else {
+            return vec![];
+        };
+        let payload_struct = &self.structs[struct_id];
+        payload_struct.field_order.iter()
+            .map(|field_name| (field_name.clone(), payload_struct.fields[field_name].clone()))
+            .collect()
+    }
+
+    pub(super) fn eval_variant(
+        &mut self,
+        enum_name: &str,
+        variant_name: &str,
+        args: &[Box<ExpressionNode>]
+    ) -> Result<SemanticExpression, SemanticError> {
+        let enum_name = &self.resolve_name(enum_name)?;
+        let sem_enum = self.enums.get_by_name(enum_name)
+            .ok_or_else(|| SemanticError::UndefinedEnum { name: enum_name.to_string() })?;
+        let enum_id = sem_enum.id;
+        let variant_index = sem_enum.variant_index(variant_name)
+            .ok_or_else(|| SemanticError::UndefinedVariant {
+                enum_name: enum_name.to_string(),
+                variant_name: variant_name.to_string(),
+            })?;
+        let payload_fields = self.payload_fields(sem_enum.variants[variant_index].payload_struct_id);
+
+        let sem_args = args.iter()
+            .map(|arg| self.eval_expr(arg))
+            .collect::<Result<Vec<SemanticExpression>, SemanticError>>()?;
+        if sem_args.len() != payload_fields.len() {
+            return Err(SemanticError::MismatchingVariantArity {
+                variant_name: format!("{}.{}", enum_name, variant_name),
+                expected: payload_fields.len(),
+                found: sem_args.len(),
+            });
+        }
+        for (arg, (field_name, field_type)) in sem_args.iter().zip(&payload_fields) {
+            if !self.try_downcast(field_type, &arg.sem_type) {
+                return Err(SemanticError::IncompatibleVariantField {
+                    variant_name: format!("{}.{}", enum_name, variant_name),
+                    field_name: field_name.clone(),
+                    expected: field_type.clone(),
+                    found: arg.sem_type.clone(),
+                });
+            }
+        }
+
+        Ok(SemanticExpression {
+            kind: SemanticExpressionKind::Variant {
+                variant_index: variant_index as u32,
+                args: sem_args,
+            },
+            sem_type: SemanticType::new(SemanticTypeKind::Enum(enum_id, enum_name.to_string())),
+            ownership: Ownership::Owned,
+        })
+    }
+
+    // The matched value is held by a hidden variable around the match, which keeps the payload
+    // its arms bind alive even if an arm reassigns the variable it came from
+    pub(super) fn eval_match_statement(
+        &mut self,
+        value: &ExpressionNode,
+        arm_nodes: &[MatchArmNode],
+    ) -> Result<Vec<SemanticStatement>, SemanticError> {
+        let sem_value = self.eval_expr(value)?;
+        let enum_type = sem_value.sem_type.clone();
+
+        self.enter_scope(SemanticScopeType::Block);
+        let variable_id = self.declare_bound_variable("#match", enum_type.clone());
+        let mut stmts = vec![SemanticStatement::VariableDeclaration {
+            variable_id,
+            init_expr: sem_value,
+        }];
+
+        let arms = self.eval_match_arms(&enum_type, arm_nodes, |sem_gen, body| {
+            match body {
+                ClosureBodyNode::Statements(statements) => {
+                    sem_gen.eval_block(statements, SemanticScopeType::Block)
+                }
+                ClosureBodyNode::Expression(expr) => {
+                    let sem_expr = sem_gen.eval_expr(expr)?;
+                    Ok(SemanticBlock {
+                        statements: vec![SemanticStatement::LoneExpression(sem_expr)],
+                        terminates: false,
+                    })
+                }
+            }
+        })?;
+
+        // Drops only run at the end of the enclosing block, so the match releases the value itself
+        self.exit_scope(false);
+        stmts.push(SemanticStatement::Match { variable_id, arms });
+        Ok(stmts)
+    }
+
+    pub(super) fn eval_match_expression(
+        &mut self,
+        value: &ExpressionNode,
+        arm_nodes: &[MatchArmNode],
+    ) -> Result<SemanticExpression, SemanticError> {
+        let sem_value = self.eval_expr(value)?;
+        let enum_type = sem_value.sem_type.clone();
+
+        let mut result_type: Option<SemanticType> = None;
+        let arms = self.eval_match_arms(&enum_type, arm_nodes, |sem_gen, body| {
+            let ClosureBodyNode::Expression(expr) = body else {
+                return Err(SemanticError::NonExpressionMatchArm);
+            };
+            let sem_expr = sem_gen.eval_expr(expr)?;
+            match &result_type {
+                Some(expected) => {
+                    if !sem_gen.try_unify(expected, &sem_expr.sem_type) {
+                        return Err(SemanticError::IncompatibleMatchArms {
+                            expected: expected.clone(),
+                            found: sem_expr.sem_type,
+                        });
+                    }
+                }
+                None => result_type = Some(sem_expr.sem_type.clone()),
+            }
+            Ok(sem_expr)
+        })?;
+
+        let sem_type = result_type.unwrap_or_else(|| SemanticType::new(SemanticTypeKind::Void));
+        Ok(SemanticExpression {
+            ownership: if sem_type.can_be_owned() {
+                Ownership::Owned
+            } else {
+                Ownership::Trivial
+            },
+            sem_type,
+            kind: SemanticExpressionKind::Match {
+                value: Box::new(sem_value),
+                arms,
+            },
+        })
+    }
+
+    // Each arm binds fields of its variant's payload in a scope of its own. The bindings borrow
+    // from the matched value, so they are never dropped
+    fn eval_match_arms<T>(
+        &mut self,
+        enum_type: &SemanticType,
+        arm_nodes: &[MatchArmNode],
+        mut eval_body: impl FnMut(&mut Self, &ClosureBodyNode) -> Result<T, SemanticError>,
+    ) -> Result<Vec<SemanticMatchArm<T>>, SemanticError> {
+        let SemanticTypeKind::Enum(enum_id, _) = enum_type.kind() else {
+            return Err(SemanticError::NonEnumMatch { sem_type: enum_type.clone() });
+        };
+
+        let mut covered = vec![false; self.enums[enum_id].variants.len()];
+        let mut arms: Vec<SemanticMatchArm<T>> = vec![];
+        for arm_node in arm_nodes {
+            let (variant_indices, bound_fields) = self.eval_pattern(enum_id, enum_type, &arm_node.pattern, &covered)?;
+            for &variant_index in &variant_indices {
+                covered[variant_index as usize] = true;
+            }
+
+            self.enter_scope(SemanticScopeType::Block);
+            let mut bindings: Vec<(u32, u32)> = vec![];
+            for BoundField { field_index, name, sem_type } in bound_fields {
+                if self.scopes.last().unwrap().variables.contains_key(&name) {
+                    return Err(SemanticError::DuplicateVariableDefinition { name });
+                }
+                bindings.push((field_index, self.declare_bound_variable(&name, sem_type)));
+            }
+            let body = eval_body(self, &arm_node.body)?;
+            self.exit_scope(false);
+
+            arms.push(SemanticMatchArm {
+                variant_indices,
+                bindings,
+                body,
+            });
+        }
+
+        let sem_enum = &self.enums[enum_id];
+        let missing_variants = sem_enum.variants.iter()
+            .zip(&covered)
+            .filter(|(_, is_covered)| !**is_covered)
+            .map(|(variant, _)| format!("{}.{}", sem_enum.name, variant.name))
+            .collect::<Vec<String>>();
+        if !missing_variants.is_empty() {
+            return Err(SemanticError::InexhaustiveMatch {
+                enum_type: enum_type.clone(),
+                missing_variants,
+            });
+        }
+        Ok(arms)
+    }
+
+    // Resolves the variants a pattern handles, along with the payload fields it binds
+    fn eval_pattern(
+        &self,
+        enum_id: u32,
+        enum_type: &SemanticType,
+        pattern: &PatternNode,
+        covered: &[bool],
+    ) -> Result<(Vec<u32>, Vec<BoundField>), SemanticError> {
+        let sem_enum = &self.enums[enum_id];
+        match pattern {
+            PatternNode::Wildcard => {
+                let variant_indices = covered.iter()
+                    .enumerate()
+                    .filter(|(_, is_covered)| !**is_covered)
+                    .map(|(i, _)| i as u32)
+                    .collect::<Vec<u32>>();
+                if variant_indices.is_empty() {
+                    return Err(SemanticError::UnreachableMatchArm { pattern: "_".to_string() });
+                }
+                Ok((variant_indices, vec![]))
+            }
+            PatternNode::Variant { enum_name, variant_name, bindings } => {
+                let pattern_str = format!("{}.{}", enum_name, variant_name);
+                if self.resolve_name(enum_name)? != sem_enum.name {
+                    return Err(SemanticError::MismatchingPattern {
+                        enum_type: enum_type.clone(),
+                        pattern: pattern_str,
+                    });
+                }
+                let variant_index = sem_enum.variant_index(variant_name)
+                    .ok_or_else(|| SemanticError::UndefinedVariant {
+                        enum_name: enum_name.clone(),
+                        variant_name: variant_name.clone(),
+                    })?;
+                if covered[variant_index] {
+                    return Err(SemanticError::UnreachableMatchArm { pattern: pattern_str });
+                }
+
+                let Some(binding_names) = bindings else {
+                    return Ok((vec![variant_index as u32], vec![]));
+                };
+                let payload_fields = self.payload_fields(sem_enum.variants[variant_index].payload_struct_id);
+                if binding_names.len() != payload_fields.len() {
+                    return Err(SemanticError::MismatchingPatternArity {
+                        variant_name: pattern_str,
+                        expected: payload_fields.len(),
+                        found: binding_names.len(),
+                    });
+                }
+                let bound_fields = binding_names.iter()
+                    .zip(payload_fields)
+                    .enumerate()
+                    .filter_map(|(i, (binding_name, (_, field_type)))| {
+                        binding_name.as_ref().map(|name| BoundField {
+                            field_index: i as u32,
+                            name: name.clone(),
+                            sem_type: field_type,
+                        })
+                    })
+                    .collect();
+                Ok((vec![variant_index as u32], bound_fields))
+            }
+        }
+    }
+}
@@ -2,6 +2,9 @@ use std::{collections::HashMap, fmt::{Display, Formatter}};
 use super::{SemanticType};
 
 pub enum SemanticError {
+    IntegerLiteralOutOfRange {
+        value: i64,
+    },
     UndefinedDatasource {
         name: String,
     },
@@ -19,16 +22,100 @@ pub enum SemanticError {
         datasource_name: String,
         table_name: String,
     },
+    ReadonlyDatasourceCreatesTables {
+        name: String,
+    },
+    MigrationOnReadonlyDatasource {
+        datasource_name: String,
+    },
+    DuplicateMigration {
+        datasource_name: String,
+        version: i32,
+    },
+    MigrationTableExists {
+        table_name: String,
+        version: i32,
+    },
+    MigrationUndefinedTable {
+        table_name: String,
+        version: i32,
+    },
+    MigrationColumnExists {
+        table_name: String,
+        column_name: String,
+        version: i32,
+    },
+    MigrationUndefinedColumn {
+        table_name: String,
+        column_name: String,
+        version: i32,
+    },
+    TableNotMigrated {
+        table_name: String,
+        datasource_name: String,
+    },
+    TableMigrationMismatch {
+        table_name: String,
+        declared: String,
+        migrated: String,
+    },
     IncompatibleColumnValue {
         table_name: String,
         column_name: String,
         expected: SemanticType,
         found: SemanticType,
     },
+    DuplicateColumn {
+        table_name: String,
+        column_name: String,
+    },
+    DuplicateColumnConstraint {
+        table_name: String,
+        column_name: String,
+        constraint: &'static str,
+    },
+    MultiplePrimaryKeys {
+        table_name: String,
+    },
+    InvalidAutoincrement {
+        table_name: String,
+        column_name: String,
+    },
+    NonConstantCheckValue {
+        table_name: String,
+        column_name: String,
+    },
+    MigrationAddsKeyColumn {
+        table_name: String,
+        column_name: String,
+        version: i32,
+    },
+    MigrationAddsRequiredColumn {
+        table_name: String,
+        column_name: String,
+        version: i32,
+    },
+    MissingInsertColumn {
+        table_name: String,
+        column_name: String,
+    },
     NonPrimitiveColumnType {
         table_name: String,
         column_name: String,
     },
+    CrossDatasourceReference {
+        table_name: String,
+        column_name: String,
+        referenced_table_name: String,
+    },
+    UnreferenceableTable {
+        table_name: String,
+    },
+    AmbiguousBackReference {
+        table_name: String,
+        referenced_table_name: String,
+        method_name: String,
+    },
     DuplicateTableDefinition {
         name: String,
     },
@@ -45,6 +132,100 @@ pub enum SemanticError {
     UndefinedStruct {
         name: String,
     },
+    DuplicateTypeDefinition {
+        name: String,
+    },
+    DuplicateTypeParameter {
+        name: String,
+        type_param: String,
+    },
+    MissingTypeArguments {
+        name: String,
+    },
+    NonGenericType {
+        name: String,
+    },
+    MismatchingTypeArgumentCount {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    InvalidTypeArgument {
+        name: String,
+        type_arg: SemanticType,
+    },
+    UninferredTypeParameter {
+        name: String,
+        type_param: String,
+    },
+    UnboundedInstantiation {
+        function_name: String,
+    },
+    UndefinedModule {
+        name: String,
+    },
+    PrivateItem {
+        name: String,
+        module_name: String,
+    },
+    InModule {
+        module_name: String,
+        error: Box<SemanticError>,
+    },
+    DuplicateStructField {
+        struct_name: String,
+        field_name: String,
+    },
+    VoidFieldType {
+        struct_name: String,
+        field_name: String,
+    },
+    UndefinedEnum {
+        name: String,
+    },
+    UndefinedVariant {
+        enum_name: String,
+        variant_name: String,
+    },
+    DuplicateVariant {
+        enum_name: String,
+        variant_name: String,
+    },
+    MismatchingVariantArity {
+        variant_name: String,
+        expected: usize,
+        found: usize,
+    },
+    IncompatibleVariantField {
+        variant_name: String,
+        field_name: String,
+        expected: SemanticType,
+        found: SemanticType,
+    },
+    NonEnumMatch {
+        sem_type: SemanticType,
+    },
+    MismatchingPattern {
+        enum_type: SemanticType,
+        pattern: String,
+    },
+    MismatchingPatternArity {
+        variant_name: String,
+        expected: usize,
+        found: usize,
+    },
+    UnreachableMatchArm {
+        pattern: String,
+    },
+    InexhaustiveMatch {
+        enum_type: SemanticType,
+        missing_variants: Vec<String>,
+    },
+    NonExpressionMatchArm,
+    IncompatibleMatchArms {
+        expected: SemanticType,
+        found: SemanticType,
+    },
     DuplicateFieldInitialization {
         name: String,
     },
@@ -97,11 +278,39 @@ pub enum SemanticError {
         var_type: SemanticType,
         expr_type: SemanticType
     },
+    BoundVariableAssignment {
+        var_name: String,
+    },
+    IncompatiblePlaceAssignment {
+        place_type: SemanticType,
+        expr_type: SemanticType,
+    },
+    InvalidAssignmentTarget,
+    NonArrayLoop {
+        sem_type: SemanticType,
+    },
+    NonIntegralRange {
+        sem_type: SemanticType,
+    },
+    NarrowedVariableAssignment {
+        var_name: String,
+        narrowed_type: SemanticType,
+    },
+    InvalidOptionalType {
+        inner_type: SemanticType,
+    },
+    NonOptionalNullCheck {
+        found_type: SemanticType,
+    },
     IncompatibleOperands {
         operation: String,
         left_type: SemanticType,
         right_type: SemanticType
     },
+    IncompatibleOperand {
+        operation: String,
+        operand_type: SemanticType,
+    },
     MismatchingCallArity {
         function_name: String,
         expected: usize,
@@ -124,6 +333,40 @@ pub enum SemanticError {
         table_name: String,
         found_type: SemanticType,
     },
+    TableNotInQuery {
+        table_name: String,
+    },
+    UnresolvedColumn {
+        column_name: String,
+    },
+    AmbiguousColumn {
+        column_name: String,
+    },
+    CrossDatasourceJoin {
+        table_name: String,
+        joined_table_name: String,
+    },
+    DuplicateJoinedTable {
+        table_name: String,
+    },
+    DuplicateSelectColumn {
+        name: String,
+    },
+    UndefinedAggregate {
+        name: String,
+    },
+    IncompatibleAggregateColumn {
+        function_name: String,
+        column_type: SemanticType,
+    },
+    UngroupedSelectColumn {
+        column_name: String,
+    },
+    HavingWithoutGroupBy,
+    NonIntegralQueryBound {
+        clause: &'static str,
+        found_type: SemanticType,
+    },
 
     NonBoolCondition {
         found_type: SemanticType,
@@ -159,8 +402,58 @@ impl Display for SemanticError {
             SemanticError::UndefinedTable { name } => {
                 write!(f, "Table {} is undefined", name)
             }
+            SemanticError::DuplicateColumn { table_name, column_name } => {
+                write!(f, "Column {} of table {} is declared multiple times", column_name, table_name)
+            }
+            SemanticError::DuplicateColumnConstraint { table_name, column_name, constraint } => {
+                write!(f, "Column {} of table {} is declared {} multiple times", column_name, table_name, constraint)
+            }
+            SemanticError::MultiplePrimaryKeys { table_name } => {
+                write!(f, "Table {} declares multiple primary keys", table_name)
+            }
+            SemanticError::InvalidAutoincrement { table_name, column_name } => {
+                write!(f, "Column {} of table {} must be an int or long primary key to autoincrement", column_name, table_name)
+            }
+            SemanticError::NonConstantCheckValue { table_name, column_name } => {
+                write!(f, "Check on column {} of table {} must compare against literal values", column_name, table_name)
+            }
+            SemanticError::IntegerLiteralOutOfRange { value } => {
+                write!(f, "Integer literal {} out of range for int, use a long literal such as {}L instead", value, value)
+            }
+            SemanticError::MigrationAddsKeyColumn { table_name, column_name, version } => {
+                write!(f, "Migration {} cannot add primary key or unique column {} to existing table {}",
+                    version, column_name, table_name)
+            }
+            SemanticError::MigrationAddsRequiredColumn { table_name, column_name, version } => {
+                write!(f, "Migration {} cannot add column {} to existing table {} without a default, as it is not optional",
+                    version, column_name, table_name)
+            }
+            SemanticError::MissingInsertColumn { table_name, column_name } => {
+                write!(f, "Insert into table {} must give column {}, which has no default", table_name, column_name)
+            }
             SemanticError::NonPrimitiveColumnType { table_name, column_name } => {
-                write!(f, "Column {} of table {} must be of a primitive type", column_name, table_name)
+                write!(f, "Column {} of table {} must be of a primitive or reference type, or an enum without payloads", column_name, table_name)
+            }
+            SemanticError::CrossDatasourceReference { table_name, column_name, referenced_table_name } => {
+                write!(
+                    f,
+                    "Column {} of table {} cannot refer to table {} from a different datasource",
+                    column_name,
+                    table_name,
+                    referenced_table_name
+                )
+            }
+            SemanticError::UnreferenceableTable { table_name } => {
+                write!(f, "Table {} needs an int or long primary key to be referenced", table_name)
+            }
+            SemanticError::AmbiguousBackReference { table_name, referenced_table_name, method_name } => {
+                write!(
+                    f,
+                    "Table {} refers to table {} from multiple columns, so {}() is ambiguous; query it instead",
+                    table_name,
+                    referenced_table_name,
+                    method_name
+                )
             }
             SemanticError::UndefinedColumn { table_name, column_name } => {
                 write!(f, "Table {} has no column named {}", table_name, column_name)
@@ -170,6 +463,37 @@ impl Display for SemanticError {
                     "Table {} must be declared read-only, as it is from a read-only datasource {}",
                     table_name, datasource_name)
             }
+            SemanticError::ReadonlyDatasourceCreatesTables { name } => {
+                write!(f, "Read-only datasource {} cannot create its tables", name)
+            }
+            SemanticError::MigrationOnReadonlyDatasource { datasource_name } => {
+                write!(f, "Read-only datasource {} cannot be migrated", datasource_name)
+            }
+            SemanticError::DuplicateMigration { datasource_name, version } => {
+                write!(f, "Migration {} on datasource {} is declared multiple times", version, datasource_name)
+            }
+            SemanticError::MigrationTableExists { table_name, version } => {
+                write!(f, "Migration {} cannot create table {}, which already exists", version, table_name)
+            }
+            SemanticError::MigrationUndefinedTable { table_name, version } => {
+                write!(f, "Migration {} changes table {}, which does not exist", version, table_name)
+            }
+            SemanticError::MigrationColumnExists { table_name, column_name, version } => {
+                write!(f, "Migration {} cannot add column {} to table {}, which already has it",
+                    version, column_name, table_name)
+            }
+            SemanticError::MigrationUndefinedColumn { table_name, column_name, version } => {
+                write!(f, "Migration {} changes column {} of table {}, which does not exist",
+                    version, column_name, table_name)
+            }
+            SemanticError::TableNotMigrated { table_name, datasource_name } => {
+                write!(f, "Table {} is never created by the migrations of datasource {}",
+                    table_name, datasource_name)
+            }
+            SemanticError::TableMigrationMismatch { table_name, declared, migrated } => {
+                write!(f, "Table {} is declared as {}, but its migrations leave it as {}",
+                    table_name, declared, migrated)
+            }
             SemanticError::IncompatibleColumnValue { table_name, column_name, expected, found } => {
                 write!(f, "Value of type {} is incompatible with {} column {} of table {}", found, expected, column_name, table_name)
             }
@@ -188,6 +512,81 @@ impl Display for SemanticError {
             SemanticError::UndefinedStruct { name } => {
                 write!(f, "Struct {} is undefined", name)
             }
+            SemanticError::DuplicateTypeDefinition { name } => {
+                write!(f, "Cannot redefine existing type {}", name)
+            }
+            SemanticError::DuplicateTypeParameter { name, type_param } => {
+                write!(f, "Type parameter {} of {} is declared multiple times", type_param, name)
+            }
+            SemanticError::MissingTypeArguments { name } => {
+                write!(f, "Generic struct {} is used without type arguments", name)
+            }
+            SemanticError::NonGenericType { name } => {
+                write!(f, "Type {} takes no type arguments", name)
+            }
+            SemanticError::MismatchingTypeArgumentCount { name, expected, found } => {
+                write!(f, "Generic struct {} takes {} type arguments but {} were provided", name, expected, found)
+            }
+            SemanticError::InvalidTypeArgument { name, type_arg } => {
+                write!(f, "Type {} cannot be a type argument of {}", type_arg, name)
+            }
+            SemanticError::UninferredTypeParameter { name, type_param } => {
+                write!(f, "Cannot infer type parameter {} of {} from the values given", type_param, name)
+            }
+            SemanticError::UnboundedInstantiation { function_name } => {
+                write!(f, "Function {} instantiates itself with ever larger type arguments", function_name)
+            }
+            SemanticError::UndefinedModule { name } => {
+                write!(f, "No module is imported as {}", name)
+            }
+            SemanticError::PrivateItem { name, module_name } => {
+                write!(f, "{} is not declared pub in module {}", name, module_name)
+            }
+            SemanticError::InModule { module_name, error } => {
+                write!(f, "In module {}: {}", module_name, error)
+            }
+            SemanticError::DuplicateStructField { struct_name, field_name } => {
+                write!(f, "Field {} of struct {} is declared multiple times", field_name, struct_name)
+            }
+            SemanticError::VoidFieldType { struct_name, field_name } => {
+                write!(f, "Field {} of struct {} cannot have type void", field_name, struct_name)
+            }
+            SemanticError::UndefinedEnum { name } => {
+                write!(f, "Enum {} is undefined", name)
+            }
+            SemanticError::UndefinedVariant { enum_name, variant_name } => {
+                write!(f, "Enum {} has no variant named {}", enum_name, variant_name)
+            }
+            SemanticError::DuplicateVariant { enum_name, variant_name } => {
+                write!(f, "Variant {} of enum {} is declared multiple times", variant_name, enum_name)
+            }
+            SemanticError::MismatchingVariantArity { variant_name, expected, found } => {
+                write!(f, "Variant {} holds {} fields but {} were provided", variant_name, expected, found)
+            }
+            SemanticError::IncompatibleVariantField { variant_name, field_name, expected, found } => {
+                write!(f, "Cannot pass {} value to {} field {} of variant {}", found, expected, field_name, variant_name)
+            }
+            SemanticError::NonEnumMatch { sem_type } => {
+                write!(f, "Cannot match on a value of non-enum type {}", sem_type)
+            }
+            SemanticError::MismatchingPattern { enum_type, pattern } => {
+                write!(f, "Pattern {} cannot match a value of type {}", pattern, enum_type)
+            }
+            SemanticError::MismatchingPatternArity { variant_name, expected, found } => {
+                write!(f, "Variant {} holds {} fields but its pattern binds {}", variant_name, expected, found)
+            }
+            SemanticError::UnreachableMatchArm { pattern } => {
+                write!(f, "Match arm {} is unreachable, as earlier arms cover all of its variants", pattern)
+            }
+            SemanticError::InexhaustiveMatch { enum_type, missing_variants } => {
+                write!(f, "Match on {} does not cover variants {}", enum_type, missing_variants.join(", "))
+            }
+            SemanticError::NonExpressionMatchArm => {
+                write!(f, "Arms of a match used as a value must consist of a single expression")
+            }
+            SemanticError::IncompatibleMatchArms { expected, found } => {
+                write!(f, "Match arm of type {} is incompatible with earlier arms of type {}", found, expected)
+            }
             SemanticError::DuplicateFieldInitialization { name } => {
                 write!(f, "Struct field {} is initialized multiple times", name)
             }
@@ -231,9 +630,36 @@ impl Display for SemanticError {
             SemanticError::IncompatibleAssignment { var_name, var_type, expr_type } => {
                 write!(f, "Cannot assign value of type {} to variable {} of type {}", expr_type, var_name, var_type)
             }
+            SemanticError::BoundVariableAssignment { var_name } => {
+                write!(f, "Cannot assign to variable {}, which is bound by its loop or match arm", var_name)
+            }
+            SemanticError::IncompatiblePlaceAssignment { place_type, expr_type } => {
+                write!(f, "Cannot assign value of type {} to a field or element of type {}", expr_type, place_type)
+            }
+            SemanticError::InvalidAssignmentTarget => {
+                write!(f, "Only variables, struct fields and array elements can be assigned to")
+            }
+            SemanticError::NonArrayLoop { sem_type } => {
+                write!(f, "Cannot loop over non-array type {}", sem_type)
+            }
+            SemanticError::NonIntegralRange { sem_type } => {
+                write!(f, "Range bounds must be of integer type, found {}", sem_type)
+            }
+            SemanticError::NarrowedVariableAssignment { var_name, narrowed_type } => {
+                write!(f, "Cannot assign to variable {} where a null check narrows it to {}", var_name, narrowed_type)
+            }
+            SemanticError::InvalidOptionalType { inner_type } => {
+                write!(f, "Type {} cannot be made optional", inner_type)
+            }
+            SemanticError::NonOptionalNullCheck { found_type } => {
+                write!(f, "Cannot check a value of non-optional type {} for null", found_type)
+            }
             SemanticError::IncompatibleOperands { operation, left_type, right_type } => {
                 write!(f, "Operands of types {} and {} are incompatible under {}", left_type, right_type, operation)
             }
+            SemanticError::IncompatibleOperand { operation, operand_type } => {
+                write!(f, "Operand of type {} is incompatible under {}", operand_type, operation)
+            }
             SemanticError::MismatchingCallArity { function_name, expected, found } => {
                 write!(f, "Function {} expects {} arguments but {} were provided", function_name, expected, found)
             }
@@ -249,6 +675,39 @@ impl Display for SemanticError {
             SemanticError::IncompatibleInsertData { table_name, found_type } => {
                 write!(f, "Expected {} row in INSERT, got {} instead", table_name, found_type)
             }
+            SemanticError::TableNotInQuery { table_name } => {
+                write!(f, "Table {} is not part of the query", table_name)
+            }
+            SemanticError::UnresolvedColumn { column_name } => {
+                write!(f, "No table in the query has a column named {}", column_name)
+            }
+            SemanticError::AmbiguousColumn { column_name } => {
+                write!(f, "Column {} is ambiguous, qualify it with a table name", column_name)
+            }
+            SemanticError::CrossDatasourceJoin { table_name, joined_table_name } => {
+                write!(f, "Cannot join table {} with table {} from a different datasource", table_name, joined_table_name)
+            }
+            SemanticError::DuplicateJoinedTable { table_name } => {
+                write!(f, "Table {} is joined multiple times", table_name)
+            }
+            SemanticError::DuplicateSelectColumn { name } => {
+                write!(f, "Column {} is selected multiple times", name)
+            }
+            SemanticError::UndefinedAggregate { name } => {
+                write!(f, "Undefined aggregate function {}", name)
+            }
+            SemanticError::IncompatibleAggregateColumn { function_name, column_type } => {
+                write!(f, "Aggregate {} cannot be applied to a column of type {}", function_name, column_type)
+            }
+            SemanticError::UngroupedSelectColumn { column_name } => {
+                write!(f, "Column {} must appear in GROUP BY or be used in an aggregate", column_name)
+            }
+            SemanticError::HavingWithoutGroupBy => {
+                write!(f, "HAVING requires a GROUP BY clause")
+            }
+            SemanticError::NonIntegralQueryBound { clause, found_type } => {
+                write!(f, "{} expression must be of integer type, found {}", clause, found_type)
+            }
             SemanticError::NonBoolCondition { found_type } => {
                 write!(f, "Condition expression must be of boolean type, found {}", found_type)
             }
@@ -262,7 +721,7 @@ impl Display for SemanticError {
                 write!(f, "Not all code paths in function {} return a value", function_name)
             }
             SemanticError::InvalidMainSignature => {
-                write!(f, "Function main must return an integer and accept no parameters")
+                write!(f, "Function main must return an integer and accept no parameters or type parameters")
             }
             SemanticError::MissingMainFunction => {
                 write!(f, "Program must contain a main function")
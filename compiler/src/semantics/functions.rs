@@ -3,6 +3,9 @@ use super::*;
 pub struct SemanticFunction {
     pub name: String,
     pub id: u32,
+    pub type_params: Vec<String>,
+    // Concrete type arguments of every instance of a generic function the program calls
+    pub instances: Vec<Vec<SemanticType>>,
     pub param_ids: Vec<u32>,
     pub return_type: SemanticType,
     pub body: SemanticBlock,
@@ -11,13 +14,15 @@ pub struct SemanticFunction {
 const BUILTIN_FNS: &[&str] = &[
     "prints",
     "printi",
+    "printl",
+    "printf",
     "printb",
     "inputs",
     "inputi",
 ];
 
 impl SemanticGen {
-    fn check_args(
+    pub(super) fn check_args(
         &self,
         fn_name: &str,
         arg_exprs: &[SemanticExpression],
@@ -69,6 +74,28 @@ impl SemanticGen {
                     ownership: Ownership::Trivial,
                 })
             }
+            "printl" => {
+                self.check_args("printl", &arg_exprs, &[SemanticType::new(SemanticTypeKind::Long)])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Void),
+                    kind: SemanticExpressionKind::BuiltinFunctionCall {
+                        function: BuiltinFunction::PrintLong,
+                        args: arg_exprs,
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            "printf" => {
+                self.check_args("printf", &arg_exprs, &[SemanticType::new(SemanticTypeKind::Float)])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Void),
+                    kind: SemanticExpressionKind::BuiltinFunctionCall {
+                        function: BuiltinFunction::PrintFloat,
+                        args: arg_exprs,
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
             "printb" => {
                 self.check_args("printb", &arg_exprs, &[SemanticType::new(SemanticTypeKind::Bool)])?;
                 Ok(SemanticExpression {
@@ -109,26 +136,35 @@ impl SemanticGen {
     pub(super) fn declare_function(
         &mut self,
         name: &str,
+        is_public: bool,
+        type_params: &[String],
         param_nodes: &[TypedQNameNode],
         return_type: &TypeNode,
     ) -> Result<(), SemanticError> {
+        let name = &self.declared_name(name);
         if self.functions.contains_name(name) {
             return Err(SemanticError::DuplicateFunctionDefinition {
                 name: name.to_string(),
             });
         }
 
-        let sem_return_type = self.try_get_semantic_type(return_type)?;
         let function_id = self.function_id_gen.next_id();
+        self.enter_generic_function(function_id, name, type_params)?;
+        let sem_return_type = self.try_get_semantic_type(return_type)?;
         let param_ids = self.eval_params(param_nodes)?;
+        self.exit_generic_function();
 
-        if name == "main" && (!param_ids.is_empty() || sem_return_type != SemanticTypeKind::Integer) {
+        let is_generic = !type_params.is_empty();
+        if name == "main" && (is_generic || !param_ids.is_empty() || sem_return_type != SemanticTypeKind::Integer) {
             return Err(SemanticError::InvalidMainSignature);
         }
 
+        self.declare_visibility(name, is_public);
         self.functions.insert(name.to_string(), function_id, SemanticFunction {
             name: name.to_string(),
             id: function_id,
+            type_params: type_params.to_vec(),
+            instances: vec![],
             param_ids,
             return_type: sem_return_type,
             body: SemanticBlock {
@@ -151,7 +187,11 @@ impl SemanticGen {
 
         // Evaluate function body
         self.cur_return_type = self.functions[id].return_type.clone();
+        let name = self.functions[id].name.clone();
+        let type_params = self.functions[id].type_params.clone();
+        self.enter_generic_function(id, &name, &type_params)?;
         let mut body_block = self.eval_block(body, SemanticScopeType::Block)?;
+        self.exit_generic_function();
         if !body_block.terminates {
             if self.cur_return_type == SemanticTypeKind::Void {
                 let ret_stmt = SemanticStatement::Return(None);
@@ -231,18 +271,25 @@ impl SemanticGen {
                     found_type: var_type.clone(),
                 })
             }
-        } else if let Some(func) = self.functions.get_by_name(name) {
-            let param_types: Vec<SemanticType> = func.param_ids.iter()
-                .map(|&param_id| self.variables[&param_id].sem_type.clone())
-                .collect();
+        } else if let Some(func) = self.functions.get_by_name(&self.resolve_name(name)?) {
+            let function_id = func.id;
+            let (param_types, return_type, type_args) = if func.type_params.is_empty() {
+                let param_types: Vec<SemanticType> = func.param_ids.iter()
+                    .map(|&param_id| self.variables[&param_id].sem_type.clone())
+                    .collect();
+                (param_types, func.return_type.clone(), vec![])
+            } else {
+                self.instantiate_call(function_id, &sem_args)?
+            };
             self.check_args(name, &sem_args, &param_types)?;
             Ok(SemanticExpression {
-                sem_type: func.return_type.clone(),
+                sem_type: return_type.clone(),
                 kind: SemanticExpressionKind::DirectFunctionCall {
-                    function_id: func.id,
+                    function_id,
+                    type_args,
                     args: sem_args,
                 },
-                ownership: if func.return_type.can_be_owned() {
+                ownership: if return_type.can_be_owned() {
                     Ownership::Owned
                 } else {
                     Ownership::Trivial
@@ -258,6 +305,7 @@ impl SemanticGen {
     pub(super) fn call_method(
         &mut self,
         receiver: &ExpressionNode,
+        location: SourceLocation,
         method_name: &str,
         arg_exprs: &[Box<ExpressionNode>]
     ) -> Result<SemanticExpression, SemanticError> {
@@ -308,6 +356,90 @@ impl SemanticGen {
                     },
                 })
             }
+            (SemanticTypeKind::Optional(inner_type), "unwrap_or") => {
+                self.check_args("Optional.unwrap_or", &sem_args, &[inner_type.clone()])?;
+                Ok(SemanticExpression {
+                    ownership: if inner_type.can_be_owned() {
+                        Ownership::Owned
+                    } else {
+                        Ownership::Trivial
+                    },
+                    sem_type: inner_type,
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::OptionalUnwrapOr,
+                        args: sem_args
+                    },
+                })
+            }
+            (SemanticTypeKind::Integer, "to_long") => {
+                self.check_args("int.to_long", &sem_args, &[])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Long),
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::IntegerToLong,
+                        args: vec![]
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            // Wraps around when out of range, or traps when built with checked arithmetic
+            (SemanticTypeKind::Long, "to_int") => {
+                self.check_args("long.to_int", &sem_args, &[])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Integer),
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::LongToInteger(location),
+                        args: vec![]
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            (SemanticTypeKind::Integer | SemanticTypeKind::Long, "to_float") => {
+                self.check_args(&format!("{}.to_float", receiver_type), &sem_args, &[])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Float),
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::IntegerToFloat,
+                        args: vec![]
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            // Truncates towards zero
+            (SemanticTypeKind::Float, "to_int") => {
+                self.check_args("float.to_int", &sem_args, &[])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Integer),
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::FloatToInteger(location),
+                        args: vec![]
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            (SemanticTypeKind::Float, "to_long") => {
+                self.check_args("float.to_long", &sem_args, &[])?;
+                Ok(SemanticExpression {
+                    sem_type: SemanticType::new(SemanticTypeKind::Long),
+                    kind: SemanticExpressionKind::BuiltinMethodCall {
+                        receiver: Box::new(sem_receiver),
+                        method: BuiltinMethod::FloatToLong(location),
+                        args: vec![]
+                    },
+                    ownership: Ownership::Trivial,
+                })
+            }
+            (SemanticTypeKind::Ref(table_id, _), "fetch") => {
+                self.eval_fetch(table_id, location, sem_receiver, &sem_args)
+            }
+            (SemanticTypeKind::NamedStruct(_, _), _) => {
+                self.call_row_method(sem_receiver, location, method_name, &sem_args)
+            }
             _ => {
                 Err(SemanticError::UndefinedMethod {
                     receiver_type: sem_receiver.sem_type,
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use super::*;
+
+// A type parameter of a generic function, as the function and the parameter's index
+type TypeParamRef = (u32, u32);
+
+// Whether the type parameter flows into another along the edges of the generic call graph
+fn flows_into(edges: &HashMap<TypeParamRef, Vec<(TypeParamRef, bool)>>, from: TypeParamRef, to: TypeParamRef) -> bool {
+    let mut visited = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(type_param) = stack.pop() {
+        if type_param == to {
+            return true;
+        }
+        if visited.insert(type_param) {
+            stack.extend(edges.get(&type_param).into_iter().flatten().map(|&(next, _)| next));
+        }
+    }
+    false
+}
+
+pub struct GenericStruct {
+    type_params: Vec<String>,
+    fields: HashMap<String, SemanticType>,
+    field_order: Vec<String>,
+}
+
+// The struct a generic struct gives for a list of type arguments. Instances whose arguments refer
+// to type parameters are open, and only stand for the concrete instances of a generic function
+pub struct SemanticStructInstance {
+    pub generic_name: String,
+    pub type_args: Vec<SemanticType>,
+    pub is_open: bool,
+}
+
+impl SemanticGen {
+    fn eval_type_params(&self, name: &str, type_params: &[String]) -> Result<Vec<String>, SemanticError> {
+        for (i, type_param) in type_params.iter().enumerate() {
+            if type_params[..i].contains(type_param) {
+                return Err(SemanticError::DuplicateTypeParameter {
+                    name: name.to_string(),
+                    type_param: type_param.clone(),
+                });
+            }
+            if self.type_exists(&self.resolve_name(type_param)?) {
+                return Err(SemanticError::DuplicateTypeDefinition { name: type_param.clone() });
+            }
+        }
+        Ok(type_params.to_vec())
+    }
+
+    // Brings the type parameters of a function into scope while its signature or body is checked
+    pub(super) fn enter_generic_function(
+        &mut self,
+        function_id: u32,
+        name: &str,
+        type_params: &[String]
+    ) -> Result<(), SemanticError> {
+        if type_params.is_empty() {
+            return Ok(());
+        }
+        self.type_params = self.eval_type_params(name, type_params)?;
+        self.generic_function = Some(function_id);
+        Ok(())
+    }
+
+    // Optionals of a type parameter would nest when it stands for an optional type, so the function
+    // may only be instantiated with other types for it
+    pub(super) fn note_optional_type_param(&mut self, inner_type: &SemanticType) {
+        if let (SemanticTypeKind::TypeParameter(index, _), Some(function_id)) = (inner_type.kind(), self.generic_function) {
+            self.optional_type_params.entry(function_id).or_default().insert(index);
+        }
+    }
+
+    pub(super) fn exit_generic_function(&mut self) {
+        self.type_params = vec![];
+        self.generic_function = None;
+    }
+
+    pub(super) fn define_generic_struct(
+        &mut self,
+        name: &str,
+        type_params: &[String],
+        field_nodes: &[TypedQNameNode]
+    ) -> Result<(), SemanticError> {
+        self.type_params = self.eval_type_params(name, type_params)?;
+        let (fields, field_order) = self.eval_struct_fields(name, field_nodes)?;
+        self.type_params = vec![];
+
+        self.generic_structs.insert(name.to_string(), GenericStruct {
+            type_params: type_params.to_vec(),
+            fields,
+            field_order,
+        });
+        Ok(())
+    }
+
+    pub(super) fn eval_generic_struct_type(
+        &mut self,
+        name: &str,
+        type_arg_nodes: &[TypeNode]
+    ) -> Result<SemanticType, SemanticError> {
+        let Some(generic_struct) = self.generic_structs.get(name) else {
+            if self.structs.contains_name(name) || self.enums.contains_name(name) {
+                return Err(SemanticError::NonGenericType { name: name.to_string() });
+            }
+            return Err(SemanticError::UndefinedStruct { name: name.to_string() });
+        };
+        if generic_struct.type_params.len() != type_arg_nodes.len() {
+            return Err(SemanticError::MismatchingTypeArgumentCount {
+                name: name.to_string(),
+                expected: generic_struct.type_params.len(),
+                found: type_arg_nodes.len(),
+            });
+        }
+
+        let mut type_args = vec![];
+        for type_arg_node in type_arg_nodes {
+            let type_arg = self.try_get_semantic_type(type_arg_node)?;
+            if type_arg == SemanticTypeKind::Void {
+                return Err(SemanticError::InvalidTypeArgument {
+                    name: name.to_string(),
+                    type_arg,
+                });
+            }
+            type_args.push(type_arg);
+        }
+        self.instantiate_struct(name, type_args)
+    }
+
+    // Each list of type arguments gives a struct of its own, created the first time it is used
+    pub(super) fn instantiate_struct(
+        &mut self,
+        generic_name: &str,
+        type_args: Vec<SemanticType>
+    ) -> Result<SemanticType, SemanticError> {
+        let is_open = type_args.iter().any(|type_arg| self.is_generic(type_arg));
+        let existing_id = self.struct_instances.iter()
+            .find(|(_, instance)| instance.generic_name == generic_name && instance.type_args == type_args)
+            .map(|(&struct_id, _)| struct_id);
+
+        let struct_id = match existing_id {
+            Some(struct_id) => struct_id,
+            None => {
+                let generic_struct = &self.generic_structs[generic_name];
+                let field_order = generic_struct.field_order.clone();
+                let template_fields = generic_struct.fields.clone();
+
+                // Structs the fields hold are instantiated first, so they precede this one
+                let mut fields = HashMap::new();
+                for field_name in &field_order {
+                    let field_type = self.substitute_type(&template_fields[field_name], &type_args)?;
+                    fields.insert(field_name.clone(), field_type);
+                }
+
+                let name = format!(
+                    "{}<{}>",
+                    generic_name,
+                    type_args.iter().map(|type_arg| type_arg.to_string()).collect::<Vec<String>>().join(", ")
+                );
+                let struct_id = self.struct_id_gen.next_id();
+                self.structs.insert(name.clone(), struct_id, SemanticStruct {
+                    name,
+                    id: struct_id,
+                    fields,
+                    field_order,
+                    is_synthesized: false,
+                });
+                self.struct_instances.insert(struct_id, SemanticStructInstance {
+                    generic_name: generic_name.to_string(),
+                    type_args,
+                    is_open,
+                });
+                struct_id
+            }
+        };
+
+        if is_open && let Some(function_id) = self.generic_function {
+            self.generic_struct_uses.entry(function_id).or_default().push(struct_id);
+        }
+        Ok(SemanticType::new(SemanticTypeKind::NamedStruct(struct_id, self.structs[struct_id].name.clone())))
+    }
+
+    // Whether a type refers to type parameters, and so differs between instances
+    pub(super) fn is_generic(&self, sem_type: &SemanticType) -> bool {
+        match sem_type.kind() {
+            SemanticTypeKind::TypeParameter(_, _) => true,
+            SemanticTypeKind::Array(inner_type) | SemanticTypeKind::Optional(inner_type) => self.is_generic(&inner_type),
+            SemanticTypeKind::Callable(param_types, ret_type) => {
+                param_types.iter().any(|param_type| self.is_generic(param_type)) || self.is_generic(&ret_type)
+            }
+            SemanticTypeKind::AnonymousStruct(fields) => fields.values().any(|field_type| self.is_generic(field_type)),
+            SemanticTypeKind::NamedStruct(struct_id, _) => {
+                self.struct_instances.get(&struct_id).is_some_and(|instance| instance.is_open)
+            }
+            _ => false,
+        }
+    }
+
+    // Replaces the type parameters within a type by the given type arguments, instantiating the
+    // generic structs it holds along the way. Optionals of a type parameter standing for an
+    // optional would nest, which no type may do
+    pub(super) fn substitute_type(
+        &mut self,
+        sem_type: &SemanticType,
+        type_args: &[SemanticType]
+    ) -> Result<SemanticType, SemanticError> {
+        let kind = match sem_type.kind() {
+            SemanticTypeKind::TypeParameter(index, _) => return Ok(type_args[index as usize].deep_clone()),
+            SemanticTypeKind::Array(elem_type) => SemanticTypeKind::Array(self.substitute_type(&elem_type, type_args)?),
+            SemanticTypeKind::Optional(inner_type) => {
+                let inner_type = self.substitute_type(&inner_type, type_args)?;
+                if inner_type.is_optional() {
+                    return Err(SemanticError::InvalidOptionalType { inner_type });
+                }
+                SemanticTypeKind::Optional(inner_type)
+            }
+            SemanticTypeKind::Callable(param_types, ret_type) => SemanticTypeKind::Callable(
+                param_types.iter()
+                    .map(|param_type| self.substitute_type(param_type, type_args))
+                    .collect::<Result<Vec<SemanticType>, SemanticError>>()?,
+                self.substitute_type(&ret_type, type_args)?,
+            ),
+            SemanticTypeKind::AnonymousStruct(fields) => SemanticTypeKind::AnonymousStruct(
+                fields.iter()
+                    .map(|(field_name, field_type)| Ok((field_name.clone(), self.substitute_type(field_type, type_args)?)))
+                    .collect::<Result<_, SemanticError>>()?
+            ),
+            SemanticTypeKind::NamedStruct(struct_id, name) => match self.struct_instances.get(&struct_id) {
+                Some(instance) if instance.is_open => {
+                    let generic_name = instance.generic_name.clone();
+                    let open_args = instance.type_args.clone();
+                    let instance_args = open_args.iter()
+                        .map(|open_arg| self.substitute_type(open_arg, type_args))
+                        .collect::<Result<Vec<SemanticType>, SemanticError>>()?;
+                    return self.instantiate_struct(&generic_name, instance_args);
+                }
+                _ => SemanticTypeKind::NamedStruct(struct_id, name),
+            },
+            kind => kind,
+        };
+        Ok(SemanticType::new(kind))
+    }
+
+    // Binds the type parameters within the type of a parameter to the matching parts of the
+    // argument's type. Mismatches are left for the arguments to be checked against the parameters
+    fn infer_type_args(&self, param_type: &SemanticType, arg_type: &SemanticType, type_args: &[SemanticType]) {
+        match (param_type.kind(), arg_type.kind()) {
+            (SemanticTypeKind::TypeParameter(index, _), _) => {
+                self.try_unify(&type_args[index as usize], arg_type);
+            }
+            (SemanticTypeKind::Array(param_elem), SemanticTypeKind::Array(arg_elem)) => {
+                self.infer_type_args(&param_elem, &arg_elem, type_args);
+            }
+            (SemanticTypeKind::Optional(param_inner), SemanticTypeKind::Optional(arg_inner)) => {
+                self.infer_type_args(&param_inner, &arg_inner, type_args);
+            }
+            (SemanticTypeKind::Optional(param_inner), _) => {
+                self.infer_type_args(&param_inner, arg_type, type_args);
+            }
+            (SemanticTypeKind::Callable(param_params, param_ret), SemanticTypeKind::Callable(arg_params, arg_ret))
+                if param_params.len() == arg_params.len() =>
+            {
+                for (param_param, arg_param) in param_params.iter().zip(&arg_params) {
+                    self.infer_type_args(param_param, arg_param, type_args);
+                }
+                self.infer_type_args(&param_ret, &arg_ret, type_args);
+            }
+            (SemanticTypeKind::NamedStruct(param_id, _), SemanticTypeKind::NamedStruct(arg_id, _)) => {
+                let (Some(param_instance), Some(arg_instance)) = (
+                    self.struct_instances.get(&param_id),
+                    self.struct_instances.get(&arg_id)
+                ) else {
+                    return;
+                };
+                if param_instance.generic_name == arg_instance.generic_name {
+                    for (param_arg, arg_arg) in param_instance.type_args.iter().zip(&arg_instance.type_args) {
+                        self.infer_type_args(param_arg, arg_arg, type_args);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Each type parameter starts out undetermined, and the values given determine it through
+    // the parts of their types matching it
+    fn infer_type_args_from(
+        &self,
+        name: &str,
+        type_params: &[String],
+        pairs: &[(SemanticType, SemanticType)],
+    ) -> Result<Vec<SemanticType>, SemanticError> {
+        let type_args = type_params.iter()
+            .map(|_| SemanticType::new(SemanticTypeKind::Any))
+            .collect::<Vec<SemanticType>>();
+        for (param_type, arg_type) in pairs {
+            self.infer_type_args(param_type, arg_type, &type_args);
+        }
+
+        for (type_param, type_arg) in type_params.iter().zip(&type_args) {
+            if !type_arg.is_concrete() {
+                return Err(SemanticError::UninferredTypeParameter {
+                    name: name.to_string(),
+                    type_param: type_param.clone(),
+                });
+            }
+            if *type_arg == SemanticTypeKind::Void {
+                return Err(SemanticError::InvalidTypeArgument {
+                    name: name.to_string(),
+                    type_arg: type_arg.clone(),
+                });
+            }
+        }
+        // The arguments share parts with the types they were inferred from
+        Ok(type_args.iter().map(|type_arg| type_arg.deep_clone()).collect())
+    }
+
+    // Resolves the parameter and return types of a call to a generic function from its arguments
+    pub(super) fn instantiate_call(
+        &mut self,
+        function_id: u32,
+        sem_args: &[SemanticExpression],
+    ) -> Result<(Vec<SemanticType>, SemanticType, Vec<SemanticType>), SemanticError> {
+        let function = &self.functions[function_id];
+        let name = function.name.clone();
+        let type_params = function.type_params.clone();
+        let generic_return_type = function.return_type.clone();
+        let generic_param_types = function.param_ids.iter()
+            .map(|param_id| self.variables[param_id].sem_type.clone())
+            .collect::<Vec<SemanticType>>();
+        if sem_args.len() != generic_param_types.len() {
+            return Err(SemanticError::MismatchingCallArity {
+                function_name: name,
+                expected: generic_param_types.len(),
+                found: sem_args.len(),
+            });
+        }
+
+        let pairs = generic_param_types.iter()
+            .zip(sem_args)
+            .map(|(param_type, arg)| (param_type.clone(), arg.sem_type.clone()))
+            .collect::<Vec<(SemanticType, SemanticType)>>();
+        let type_args = self.infer_type_args_from(&name, &type_params, &pairs)?;
+
+        // Calls made by a generic function may depend on its own type arguments, and are only
+        // instantiated along with it
+        match self.generic_function {
+            Some(caller_id) if type_args.iter().any(|type_arg| self.is_generic(type_arg)) => {
+                self.generic_calls.entry(caller_id).or_default().push((function_id, type_args.clone()));
+            }
+            _ => self.function_instantiations.push((function_id, type_args.clone())),
+        }
+
+        let param_types = generic_param_types.iter()
+            .map(|param_type| self.substitute_type(param_type, &type_args))
+            .collect::<Result<Vec<SemanticType>, SemanticError>>()?;
+        let return_type = self.substitute_type(&generic_return_type, &type_args)?;
+        Ok((param_types, return_type, type_args))
+    }
+
+    // A generic struct literal takes on the instance its field values determine
+    pub(super) fn eval_generic_struct_literal(
+        &mut self,
+        name: &str,
+        mut field_types: HashMap<String, SemanticType>,
+    ) -> Result<SemanticType, SemanticError> {
+        let generic_struct = &self.generic_structs[name];
+        let type_params = generic_struct.type_params.clone();
+        let has_same_fields = field_types.len() == generic_struct.fields.len()
+            && field_types.keys().all(|field_name| generic_struct.fields.contains_key(field_name));
+        if !has_same_fields {
+            return Err(SemanticError::IncompatibleStructInitialization {
+                name: name.to_string(),
+                expected_fields: generic_struct.fields.clone(),
+                found_fields: field_types,
+            });
+        }
+
+        let pairs = field_types.iter()
+            .map(|(field_name, field_type)| (generic_struct.fields[field_name].clone(), field_type.clone()))
+            .collect::<Vec<(SemanticType, SemanticType)>>();
+        let type_args = self.infer_type_args_from(name, &type_params, &pairs)?;
+        let struct_type = self.instantiate_struct(name, type_args)?;
+
+        let SemanticTypeKind::NamedStruct(struct_id, _) = struct_type.kind() else {
+            unreachable!("Instances of generic structs are named structs");
+        };
+        let instance_fields = &self.structs[struct_id].fields;
+        if !self.try_downcast_struct(instance_fields, &mut field_types) {
+            return Err(SemanticError::IncompatibleStructInitialization {
+                name: struct_type.to_string(),
+                expected_fields: instance_fields.clone(),
+                found_fields: field_types,
+            });
+        }
+        Ok(struct_type)
+    }
+
+    // Collects the type parameters a type refers to, with whether each is nested within a larger type
+    fn collect_type_params(&self, sem_type: &SemanticType, is_nested: bool, type_params: &mut Vec<(u32, bool)>) {
+        match sem_type.kind() {
+            SemanticTypeKind::TypeParameter(index, _) => type_params.push((index, is_nested)),
+            SemanticTypeKind::Array(inner_type) | SemanticTypeKind::Optional(inner_type) => {
+                self.collect_type_params(&inner_type, true, type_params);
+            }
+            SemanticTypeKind::Callable(param_types, ret_type) => {
+                for param_type in &param_types {
+                    self.collect_type_params(param_type, true, type_params);
+                }
+                self.collect_type_params(&ret_type, true, type_params);
+            }
+            SemanticTypeKind::AnonymousStruct(fields) => {
+                for field_type in fields.values() {
+                    self.collect_type_params(field_type, true, type_params);
+                }
+            }
+            SemanticTypeKind::NamedStruct(struct_id, _) => {
+                for type_arg in self.struct_instances.get(&struct_id).into_iter().flat_map(|instance| &instance.type_args) {
+                    self.collect_type_params(type_arg, true, type_params);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Generic functions whose instances never run out. A type parameter that comes back to its
+    // function through the calls it makes, having been nested within a larger type argument on
+    // the way, is nested one level deeper by every round of calls. Type arguments that only grow
+    // once, or leave the cycle, are left to be instantiated
+    fn expanding_functions(&self) -> HashSet<u32> {
+        let mut edges: HashMap<TypeParamRef, Vec<(TypeParamRef, bool)>> = HashMap::new();
+        for (&caller_id, calls) in &self.generic_calls {
+            for (callee_id, callee_args) in calls {
+                for (arg_index, callee_arg) in callee_args.iter().enumerate() {
+                    let mut type_params = vec![];
+                    self.collect_type_params(callee_arg, false, &mut type_params);
+                    for (param_index, is_nested) in type_params {
+                        edges.entry((caller_id, param_index))
+                            .or_default()
+                            .push(((*callee_id, arg_index as u32), is_nested));
+                    }
+                }
+            }
+        }
+        edges.iter()
+            .flat_map(|(&from, targets)| targets.iter()
+                .filter(|(_, is_nested)| *is_nested)
+                .map(move |&(to, _)| (from, to)))
+            .filter(|&(from, to)| flows_into(&edges, to, from))
+            .map(|((function_id, _), _)| function_id)
+            .collect()
+    }
+
+    // Instantiates every generic function the program calls, along with the functions and
+    // structs each instance needs in turn
+    pub(super) fn instantiate_generic_functions(&mut self) -> Result<(), SemanticError> {
+        let expanding_functions = self.expanding_functions();
+        let mut pending = std::mem::take(&mut self.function_instantiations);
+        while let Some((function_id, type_args)) = pending.pop() {
+            let function = &mut self.functions[function_id];
+            if function.instances.contains(&type_args) {
+                continue;
+            }
+            if expanding_functions.contains(&function_id) {
+                return Err(SemanticError::UnboundedInstantiation { function_name: function.name.clone() });
+            }
+            for &index in self.optional_type_params.get(&function_id).into_iter().flatten() {
+                let type_arg = &type_args[index as usize];
+                if type_arg.is_optional() {
+                    return Err(SemanticError::InvalidTypeArgument {
+                        name: function.name.clone(),
+                        type_arg: type_arg.clone(),
+                    });
+                }
+            }
+            function.instances.push(type_args.clone());
+
+            let struct_uses = self.generic_struct_uses.get(&function_id).cloned().unwrap_or_default();
+            for struct_id in struct_uses {
+                let open_type = SemanticType::new(SemanticTypeKind::NamedStruct(struct_id, self.structs[struct_id].name.clone()));
+                self.substitute_type(&open_type, &type_args)?;
+            }
+            let calls = self.generic_calls.get(&function_id).cloned().unwrap_or_default();
+            for (callee_id, callee_args) in calls {
+                let callee_args = callee_args.iter()
+                    .map(|callee_arg| self.substitute_type(callee_arg, &type_args))
+                    .collect::<Result<Vec<SemanticType>, SemanticError>>()?;
+                pending.push((callee_id, callee_args));
+            }
+        }
+        Ok(())
+    }
+}
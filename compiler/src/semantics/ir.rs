@@ -15,6 +15,11 @@ pub enum SemanticStatement {
         variable_id: u32,
         expr: SemanticExpression,
     },
+    // The place is a chain of struct fields and array elements reached from a variable
+    PlaceAssignment {
+        place: SemanticExpression,
+        expr: SemanticExpression,
+    },
     LoneExpression(SemanticExpression),
     Conditional {
         branches: Vec<SemanticConditionalBranch>,
@@ -25,10 +30,68 @@ pub enum SemanticStatement {
         body: SemanticBlock,
         id: u32,
     },
+    // Runs the body once per element of the array held by the array variable
+    ArrayLoop {
+        array_variable_id: u32,
+        index_variable_id: Option<u32>,
+        element_variable_id: u32,
+        body: SemanticBlock,
+        id: u32,
+    },
+    // Counts the variable through the range, with its bounds and step evaluated once beforehand
+    RangeLoop {
+        variable_id: u32,
+        range: SemanticRange,
+        body: SemanticBlock,
+        id: u32,
+    },
+    // Runs the arm handling the variant of the enum held by the variable
+    Match {
+        variable_id: u32,
+        arms: Vec<SemanticMatchArm<SemanticBlock>>,
+    },
     Return(Option<SemanticExpression>),
     Break(u32),
     Continue(u32),
     DropVariable(u32),
+    Transaction {
+        datasource_id: u32,
+        body: SemanticBlock,
+        id: u32,
+    },
+    RollbackTransaction(u32),
+    SchemaChange {
+        datasource_id: u32,
+        location: SourceLocation,
+        change: SchemaChange,
+    },
+}
+
+pub enum SchemaChange {
+    CreateTable {
+        table_name: String,
+        columns: Vec<SemanticColumn>,
+    },
+    DropTable {
+        table_name: String,
+    },
+    RenameTable {
+        table_name: String,
+        new_name: String,
+    },
+    AddColumn {
+        table_name: String,
+        column: SemanticColumn,
+    },
+    DropColumn {
+        table_name: String,
+        column_name: String,
+    },
+    RenameColumn {
+        table_name: String,
+        column_name: String,
+        new_name: String,
+    },
 }
 
 pub struct SemanticConditionalBranch {
@@ -36,6 +99,21 @@ pub struct SemanticConditionalBranch {
     pub body: SemanticBlock,
 }
 
+pub struct SemanticRange {
+    pub start: SemanticExpression,
+    pub end: SemanticExpression,
+    pub inclusive: bool,
+    pub step: Option<(SourceLocation, SemanticExpression)>,
+}
+
+pub struct SemanticMatchArm<T> {
+    // The variants handled by the arm, which are all those left by earlier arms for a wildcard
+    pub variant_indices: Vec<u32>,
+    // Fields of the variant's payload bound by the arm, as field indices paired with variable ids
+    pub bindings: Vec<(u32, u32)>,
+    pub body: T,
+}
+
 pub struct SemanticExpression {
     pub kind: SemanticExpressionKind,
     pub sem_type: SemanticType,
@@ -44,8 +122,12 @@ pub struct SemanticExpression {
 
 pub enum SemanticExpressionKind {
     IntegerLiteral(i32),
+    LongLiteral(i64),
+    FloatLiteral(f64),
     BoolLiteral(bool),
     StringLiteral(String),
+    // The null of an optional type
+    Null,
     Struct(HashMap<String, SemanticExpression>),
     Array(Vec<SemanticExpression>),
     Closure(u32),
@@ -60,19 +142,50 @@ pub enum SemanticExpressionKind {
     },
     Add {
         left: Box<SemanticExpression>,
-        right: Box<SemanticExpression>
+        right: Box<SemanticExpression>,
+        location: SourceLocation,
     },
     Subtract {
         left: Box<SemanticExpression>,
-        right: Box<SemanticExpression>
+        right: Box<SemanticExpression>,
+        location: SourceLocation,
+    },
+    Arithmetic {
+        left: Box<SemanticExpression>,
+        right: Box<SemanticExpression>,
+        op: ArithmeticType,
+        location: SourceLocation,
+    },
+    Negate {
+        expr: Box<SemanticExpression>,
+        location: SourceLocation,
     },
     Compare {
         left: Box<SemanticExpression>,
         right: Box<SemanticExpression>,
         op: ComparisonType
     },
+    // The right operand of a logical operator is only evaluated when the left does not decide the result
+    And {
+        left: Box<SemanticExpression>,
+        right: Box<SemanticExpression>,
+    },
+    Or {
+        left: Box<SemanticExpression>,
+        right: Box<SemanticExpression>,
+    },
+    Not(Box<SemanticExpression>),
+    IsNull {
+        expr: Box<SemanticExpression>,
+        negated: bool,
+    },
+    // The value held by an optional that is known not to be null
+    Unwrap(Box<SemanticExpression>),
     DirectFunctionCall {
         function_id: u32,
+        // Type arguments of a call to a generic function, which may refer to the type parameters
+        // of the function making the call
+        type_args: Vec<SemanticType>,
         args: Vec<SemanticExpression>,
     },
     IndirectFunctionCall {
@@ -89,12 +202,22 @@ pub enum SemanticExpressionKind {
         args: Vec<SemanticExpression>,
     },
     ImmediateQuery(SemanticQuery),
+    Variant {
+        variant_index: u32,
+        args: Vec<SemanticExpression>,
+    },
+    Match {
+        value: Box<SemanticExpression>,
+        arms: Vec<SemanticMatchArm<SemanticExpression>>,
+    },
 }
 
 #[derive(Clone, Copy)]
 pub enum BuiltinFunction {
     PrintString,
     PrintInteger,
+    PrintLong,
+    PrintFloat,
     PrintBool,
     InputInteger,
     InputString,
@@ -104,35 +227,125 @@ pub enum BuiltinFunction {
 pub enum BuiltinMethod {
     ArrayLength,
     ArrayAppend,
-    ArrayPop
+    ArrayPop,
+    IntegerToLong,
+    LongToInteger(SourceLocation),
+    IntegerToFloat,
+    FloatToInteger(SourceLocation),
+    FloatToLong(SourceLocation),
+    OptionalUnwrapOr,
 }
 
 pub enum SemanticQuery {
     Select {
         table_id: u32,
+        location: SourceLocation,
+        joins: Vec<Join>,
+        is_distinct: bool,
+        columns: Vec<SelectColumn>,
+        result_type: SemanticType,
+        shape: SelectShape,
         where_clause: Option<WhereClause>,
+        group_by: Vec<u32>,
+        having: Option<WhereClause>,
+        order_by: Vec<Ordering>,
+        limit: Option<Box<SemanticExpression>>,
+        offset: Option<Box<SemanticExpression>>,
     },
     Insert {
         table_id: u32,
+        location: SourceLocation,
+        // Indices of the table columns given by each row, in the order of the row's fields
+        columns: Vec<u32>,
         value: Box<SemanticExpression>,
+        returning: Option<Returning>,
     },
     Update {
         table_id: u32,
+        location: SourceLocation,
         assignments: Vec<UpdateAssignment>,
         where_clause: Option<WhereClause>,
+        returning: Option<Returning>,
     },
     Delete {
         table_id: u32,
+        location: SourceLocation,
         where_clause: Option<WhereClause>,
+        returning: Option<Returning>,
     }
 }
 
+pub struct Join {
+    pub table_id: u32,
+    pub is_left: bool,
+    pub conditions: Vec<JoinCondition>,
+}
+
+pub struct JoinCondition {
+    pub left_column: u32,
+    pub op: ComparisonType,
+    pub right_column: u32,
+}
+
+// How the rows found by a select are handed back
+#[derive(Clone, Copy, PartialEq)]
+pub enum SelectShape {
+    // Every row, as an array
+    Rows,
+    // The value of a lone aggregate
+    Scalar,
+    // The one row a reference points to, which must exist
+    Row,
+}
+
+pub enum SelectColumn {
+    Column(u32),
+    Aggregate(AggregateFunction, Option<u32>),
+}
+
+#[derive(Clone, Copy)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Average,
+    Minimum,
+    Maximum,
+}
+
+pub struct Ordering {
+    pub column_index: u32,
+    pub descending: bool,
+}
+
+pub struct Returning {
+    pub columns: Vec<u32>,
+    pub result_type: SemanticType,
+}
+
 pub struct UpdateAssignment {
     pub column_index: u32,
     pub value: SemanticExpression,
 }
 
-pub struct WhereClause {
-    pub column_index: u32,
-    pub value: Box<SemanticExpression>,
+pub enum WhereClause {
+    Comparison {
+        column_index: u32,
+        op: ComparisonType,
+        value: Box<SemanticExpression>,
+    },
+    Like {
+        column_index: u32,
+        pattern: Box<SemanticExpression>,
+    },
+    In {
+        column_index: u32,
+        values: Box<SemanticExpression>,
+    },
+    IsNull {
+        column_index: u32,
+        negated: bool,
+    },
+    And(Box<WhereClause>, Box<WhereClause>),
+    Or(Box<WhereClause>, Box<WhereClause>),
+    Not(Box<WhereClause>),
 }
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use super::*;
+
+pub struct SemanticMigration {
+    pub version: i32,
+    pub datasource_id: u32,
+    pub body: SemanticBlock,
+}
+
+// Columns of each table as left by the migrations evaluated so far
+pub(super) type MigratedSchema = HashMap<String, Vec<SemanticColumn>>;
+
+fn columns_str(columns: &[SemanticColumn]) -> String {
+    let column_strs = columns.iter()
+        .map(|column| column.to_string())
+        .collect::<Vec<String>>();
+    format!("{{{}}}", column_strs.join(", "))
+}
+
+impl SemanticGen {
+    fn table_columns(&self, table: &SemanticTable) -> Vec<SemanticColumn> {
+        let table_struct = &self.structs[table.struct_id];
+        table_struct.field_order.iter()
+            .zip(&table.constraints)
+            .map(|(name, constraints)| SemanticColumn {
+                name: name.clone(),
+                sem_type: table_struct.fields[name].clone(),
+                constraints: constraints.clone(),
+            })
+            .collect()
+    }
+
+    // The table standing for one the migrations so far have left on a datasource. That is the
+    // declared table where the two agree, and otherwise a table of its own for those columns,
+    // whose row struct is named after the version first seeing them
+    pub(super) fn migrated_table(&mut self, datasource_id: u32, version: i32, name: &str) -> Option<u32> {
+        let columns = self.migrated_schemas.get(&datasource_id)?.get(name)?.clone();
+        let existing_id = self.tables.values()
+            .find(|table| {
+                table.name == name
+                    && table.datasource_id == datasource_id
+                    && self.table_columns(table) == columns
+            })
+            .map(|table| table.id);
+        if existing_id.is_some() {
+            return existing_id;
+        }
+
+        let mut struct_name = format!("{}@{}", name, version);
+        while self.structs.contains_name(&struct_name) {
+            struct_name.push('\'');
+        }
+        let table_id = self.table_id_gen.next_id();
+        let struct_id = self.struct_id_gen.next_id();
+        self.tables.insert(struct_name.clone(), table_id, SemanticTable {
+            name: name.to_string(),
+            id: table_id,
+            is_readonly: false,
+            struct_id,
+            datasource_id,
+            constraints: columns.iter().map(|column| column.constraints.clone()).collect(),
+            migration_version: Some(version),
+        });
+        self.structs.insert(struct_name.clone(), struct_id, SemanticStruct {
+            name: struct_name,
+            id: struct_id,
+            fields: columns.iter().map(|column| (column.name.clone(), column.sem_type.clone())).collect(),
+            field_order: columns.iter().map(|column| column.name.clone()).collect(),
+            is_synthesized: false,
+        });
+        Some(table_id)
+    }
+
+    pub(super) fn define_migrations(&mut self, modules: &[ModuleNode]) -> Result<(), SemanticError> {
+        // Migrations are evaluated in the order they are applied, whichever modules they are in
+        let mut sorted_migrations = modules.iter()
+            .flat_map(|module| module.program.migrations.iter().map(move |migration| (module, migration)))
+            .collect::<Vec<(&ModuleNode, &MigrationNode)>>();
+        sorted_migrations.sort_by_key(|(_, migration)| migration.version);
+        for (i, (_, migration)) in sorted_migrations.iter().enumerate() {
+            let is_duplicate = sorted_migrations[..i].iter().any(|(_, other)| {
+                other.datasource_name == migration.datasource_name && other.version == migration.version
+            });
+            if is_duplicate {
+                return Err(SemanticError::DuplicateMigration {
+                    datasource_name: migration.datasource_name.clone(),
+                    version: migration.version,
+                });
+            }
+        }
+        for (module, migration) in sorted_migrations {
+            self.in_module(module, |sem_gen| sem_gen.define_migration(migration))?;
+        }
+
+        // Tables of a migrated datasource must be declared as their migrations leave them
+        for table in self.tables.values().filter(|table| table.migration_version.is_none()) {
+            let Some(schema) = self.migrated_schemas.get(&table.datasource_id) else {
+                continue;
+            };
+            let declared_columns = self.table_columns(table);
+            match schema.get(&table.name) {
+                Some(migrated_columns) => {
+                    if declared_columns != *migrated_columns {
+                        return Err(SemanticError::TableMigrationMismatch {
+                            table_name: table.name.clone(),
+                            declared: columns_str(&declared_columns),
+                            migrated: columns_str(migrated_columns),
+                        });
+                    }
+                },
+                None => {
+                    return Err(SemanticError::TableNotMigrated {
+                        table_name: table.name.clone(),
+                        datasource_name: self.datasources[table.datasource_id].name.clone(),
+                    });
+                },
+            }
+        }
+
+        Ok(())
+    }
+
+    fn define_migration(&mut self, migration: &MigrationNode) -> Result<(), SemanticError> {
+        let datasource = self.datasources.get_by_name(&migration.datasource_name)
+            .ok_or_else(|| SemanticError::UndefinedDatasource {
+                name: migration.datasource_name.clone()
+            })?;
+        if datasource.is_readonly {
+            return Err(SemanticError::MigrationOnReadonlyDatasource {
+                datasource_name: datasource.name.clone(),
+            });
+        }
+        let datasource_id = datasource.id;
+
+        // Evaluate the migration like the body of a void function
+        self.migration = Some((datasource_id, migration.version));
+        self.cur_return_type = SemanticType::new(SemanticTypeKind::Void);
+        self.enter_scope(SemanticScopeType::Function);
+        self.enter_scope(SemanticScopeType::Block);
+
+        let mut statements: Vec<SemanticStatement> = vec![];
+        let mut terminates = false;
+        for step in &migration.steps {
+            let mut cur_stmts = match step {
+                MigrationStepNode::SchemaChange(change) => {
+                    vec![self.eval_schema_change(datasource_id, migration.version, change)?]
+                },
+                MigrationStepNode::Statement(stmt) => self.eval_stmt(stmt)?,
+            };
+            terminates = match cur_stmts.last() {
+                Some(last_stmt) => last_stmt.is_terminating(),
+                None => false,
+            };
+            statements.append(&mut cur_stmts);
+            if terminates {
+                break;
+            }
+        }
+        statements.append(&mut self.exit_scope(!terminates));
+        self.exit_scope(false);
+        self.migration = None;
+        if !terminates {
+            statements.push(SemanticStatement::Return(None));
+        }
+
+        self.migrations.push(SemanticMigration {
+            version: migration.version,
+            datasource_id,
+            body: SemanticBlock {
+                statements,
+                terminates: true,
+            },
+        });
+        Ok(())
+    }
+
+    fn eval_schema_change(
+        &mut self,
+        datasource_id: u32,
+        version: i32,
+        change: &SchemaChangeNode,
+    ) -> Result<SemanticStatement, SemanticError> {
+        let (location, table_name) = match change {
+            SchemaChangeNode::CreateTable { location, table_name, .. }
+            | SchemaChangeNode::DropTable { location, table_name }
+            | SchemaChangeNode::RenameTable { location, table_name, .. }
+            | SchemaChangeNode::AddColumn { location, table_name, .. }
+            | SchemaChangeNode::DropColumn { location, table_name, .. }
+            | SchemaChangeNode::RenameColumn { location, table_name, .. } => (*location, table_name),
+        };
+
+        // Every change but a creation applies to a table the earlier changes left behind
+        let mut schema = self.migrated_schemas.remove(&datasource_id).unwrap_or_default();
+        let undefined_table = || SemanticError::MigrationUndefinedTable {
+            table_name: table_name.clone(),
+            version,
+        };
+        let undefined_column = |column_name: &str| SemanticError::MigrationUndefinedColumn {
+            table_name: table_name.clone(),
+            column_name: column_name.to_string(),
+            version,
+        };
+        let existing_table = |name: &str| SemanticError::MigrationTableExists {
+            table_name: name.to_string(),
+            version,
+        };
+        let existing_column = |column_name: &str| SemanticError::MigrationColumnExists {
+            table_name: table_name.clone(),
+            column_name: column_name.to_string(),
+            version,
+        };
+
+        let sem_change = match change {
+            SchemaChangeNode::CreateTable { columns, .. } => {
+                if schema.contains_key(table_name) {
+                    return Err(existing_table(table_name));
+                }
+                let sem_columns = self.eval_columns(table_name, columns)?;
+                self.check_references(table_name, datasource_id, &sem_columns)?;
+                schema.insert(table_name.clone(), sem_columns.clone());
+                SchemaChange::CreateTable {
+                    table_name: table_name.clone(),
+                    columns: sem_columns,
+                }
+            },
+            SchemaChangeNode::DropTable { .. } => {
+                schema.remove(table_name).ok_or_else(undefined_table)?;
+                SchemaChange::DropTable {
+                    table_name: table_name.clone(),
+                }
+            },
+            SchemaChangeNode::RenameTable { new_name, .. } => {
+                if schema.contains_key(new_name) {
+                    return Err(existing_table(new_name));
+                }
+                let columns = schema.remove(table_name).ok_or_else(undefined_table)?;
+                schema.insert(new_name.clone(), columns);
+                SchemaChange::RenameTable {
+                    table_name: table_name.clone(),
+                    new_name: new_name.clone(),
+                }
+            },
+            SchemaChangeNode::AddColumn { column, .. } => {
+                let columns = schema.get_mut(table_name).ok_or_else(undefined_table)?;
+                if columns.iter().any(|existing| existing.name == column.name) {
+                    return Err(existing_column(&column.name));
+                }
+                let sem_column = self.eval_added_column(table_name, columns, column)?;
+                self.check_references(table_name, datasource_id, std::slice::from_ref(&sem_column))?;
+                // SQLite cannot add key columns to a table that may already hold rows
+                if sem_column.constraints.is_primary_key || sem_column.constraints.is_unique {
+                    return Err(SemanticError::MigrationAddsKeyColumn {
+                        table_name: table_name.clone(),
+                        column_name: column.name.clone(),
+                        version,
+                    });
+                }
+                // Nor can it add a NOT NULL column without a value for the rows it holds
+                if !sem_column.sem_type.is_optional() && sem_column.constraints.default.is_none() {
+                    return Err(SemanticError::MigrationAddsRequiredColumn {
+                        table_name: table_name.clone(),
+                        column_name: column.name.clone(),
+                        version,
+                    });
+                }
+                columns.push(sem_column.clone());
+                SchemaChange::AddColumn {
+                    table_name: table_name.clone(),
+                    column: sem_column,
+                }
+            },
+            SchemaChangeNode::DropColumn { column_name, .. } => {
+                let columns = schema.get_mut(table_name).ok_or_else(undefined_table)?;
+                let index = columns.iter().position(|column| column.name == *column_name)
+                    .ok_or_else(|| undefined_column(column_name))?;
+                columns.remove(index);
+                SchemaChange::DropColumn {
+                    table_name: table_name.clone(),
+                    column_name: column_name.clone(),
+                }
+            },
+            SchemaChangeNode::RenameColumn { column_name, new_name, .. } => {
+                let columns = schema.get_mut(table_name).ok_or_else(undefined_table)?;
+                if columns.iter().any(|column| column.name == *new_name) {
+                    return Err(existing_column(new_name));
+                }
+                let column = columns.iter_mut().find(|column| column.name == *column_name)
+                    .ok_or_else(|| undefined_column(column_name))?;
+                column.name = new_name.clone();
+                for column in columns.iter_mut() {
+                    for check in &mut column.constraints.checks {
+                        check.rename_column(column_name, new_name);
+                    }
+                }
+                SchemaChange::RenameColumn {
+                    table_name: table_name.clone(),
+                    column_name: column_name.clone(),
+                    new_name: new_name.clone(),
+                }
+            },
+        };
+        self.migrated_schemas.insert(datasource_id, schema);
+
+        Ok(SemanticStatement::SchemaChange {
+            datasource_id,
+            location,
+            change: sem_change,
+        })
+    }
+}
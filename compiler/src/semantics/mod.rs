@@ -5,12 +5,19 @@ mod queries;
 mod functions;
 mod closures;
 mod control_flow;
+mod migrations;
+mod constraints;
+mod references;
+mod optionals;
 mod data;
+mod enums;
+mod generics;
+mod modules;
 mod binops;
 mod errors;
 mod util;
 
-use std::{collections::HashMap};
+use std::collections::{HashMap, HashSet};
 use util::*;
 
 pub use types::*;
@@ -18,23 +25,57 @@ pub use variables::*;
 pub use functions::*;
 pub use closures::*;
 pub use control_flow::*;
+pub use migrations::*;
+pub use constraints::*;
 pub use data::*;
+pub use enums::*;
+pub use generics::*;
 pub use ir::*;
 pub use queries::*;
 pub use errors::SemanticError;
 
 use crate::tokens::*;
+use crate::modules::ModuleNode;
 
 pub struct SemanticGen {
     datasources: DualLookup<SemanticDatasource>,
     tables: DualLookup<SemanticTable>,
     structs: DualLookup<SemanticStruct>,
+    enums: DualLookup<SemanticEnum>,
+    // Generic structs by name, each instance of which is a struct of its own
+    generic_structs: HashMap<String, GenericStruct>,
+    struct_instances: HashMap<u32, SemanticStructInstance>,
     functions: DualLookup<SemanticFunction>,
     closures: HashMap<u32, SemanticClosure>,
     variables: HashMap<u32, SemanticVariable>,
+    migrations: Vec<SemanticMigration>,
+    migrated_schemas: HashMap<u32, MigratedSchema>,
+    // The datasource and version of the migration whose statements are being checked
+    migration: Option<(u32, i32)>,
     scopes: Vec<SemanticScope>,
     loops: Vec<(Option<String>, u32)>,
+    // Variables shadowing an optional one within the branches where it is known not to be null
+    narrowed_variables: HashSet<u32>,
+    // Variables bound by array loops, range loops and match arms rather than declared
+    bound_variables: HashSet<u32>,
     cur_return_type: SemanticType,
+    // Type parameters of the generic function or struct being declared
+    type_params: Vec<String>,
+    // The generic function whose signature or body is being checked
+    generic_function: Option<u32>,
+    // Calls and struct instances within each generic function whose type arguments depend on its
+    // own, instantiated for each list of type arguments the function is instantiated with
+    generic_calls: HashMap<u32, Vec<(u32, Vec<SemanticType>)>>,
+    generic_struct_uses: HashMap<u32, Vec<u32>>,
+    // Type parameters each generic function makes optional, which may not stand for optionals
+    optional_type_params: HashMap<u32, HashSet<u32>>,
+    // Generic functions called with concrete type arguments
+    function_instantiations: Vec<(u32, Vec<SemanticType>)>,
+    // The module being checked, and the names of the modules it imports by their qualifier
+    module_name: String,
+    imports: HashMap<String, String>,
+    // Functions and types declared pub, by the name they were declared under
+    public_names: HashSet<String>,
 
     datasource_id_gen: IdGenerator,
     table_id_gen: IdGenerator,
@@ -43,15 +84,24 @@ pub struct SemanticGen {
     closure_id_gen: IdGenerator,
     variable_id_gen: IdGenerator,
     loop_id_gen: IdGenerator,
+    transaction_id_gen: IdGenerator,
 }
     
 pub struct SemanticProgram {
     pub datasources: HashMap<u32, SemanticDatasource>,
     pub tables: HashMap<u32, SemanticTable>,
     pub structs: HashMap<u32, SemanticStruct>,
+    pub enums: HashMap<u32, SemanticEnum>,
+    pub struct_instances: HashMap<u32, SemanticStructInstance>,
     pub functions: HashMap<u32, SemanticFunction>,
     pub closures: HashMap<u32, SemanticClosure>,
     pub variables: HashMap<u32, SemanticVariable>,
+    pub migrations: Vec<SemanticMigration>,
+}
+
+// Literals are parsed wider than an int, so that negated ones reach the minimum int
+fn int_literal(value: i64) -> Result<i32, SemanticError> {
+    i32::try_from(value).map_err(|_| SemanticError::IntegerLiteralOutOfRange { value })
 }
 
 impl SemanticGen {
@@ -60,12 +110,29 @@ impl SemanticGen {
             datasources: DualLookup::new(),
             tables: DualLookup::new(),
             structs: DualLookup::new(),
+            enums: DualLookup::new(),
+            generic_structs: HashMap::new(),
+            struct_instances: HashMap::new(),
             functions: DualLookup::new(),
             closures: HashMap::new(),
             variables: HashMap::new(),
+            migrations: vec![],
+            migrated_schemas: HashMap::new(),
+            migration: None,
             scopes: vec![],
             loops: vec![],
+            narrowed_variables: HashSet::new(),
+            bound_variables: HashSet::new(),
             cur_return_type: SemanticType::new(SemanticTypeKind::Void),
+            type_params: vec![],
+            generic_function: None,
+            generic_calls: HashMap::new(),
+            generic_struct_uses: HashMap::new(),
+            optional_type_params: HashMap::new(),
+            function_instantiations: vec![],
+            module_name: String::new(),
+            imports: HashMap::new(),
+            public_names: HashSet::new(),
 
             datasource_id_gen: IdGenerator::new(),
             table_id_gen: IdGenerator::new(),
@@ -74,6 +141,7 @@ impl SemanticGen {
             closure_id_gen: IdGenerator::new(),
             variable_id_gen: IdGenerator::new(),
             loop_id_gen: IdGenerator::new(),
+            transaction_id_gen: IdGenerator::new(),
         }
     }
 
@@ -82,10 +150,14 @@ impl SemanticGen {
             StatementNode::VariableDefinition { var_type, name, init_expr } => {
                 self.define_variable(name, var_type, init_expr).map(|s| vec![s])
             },
-            StatementNode::Assignment { name, expr } => {
-                self.assign_variable(name, expr).map(|s| vec![s])
+            StatementNode::Assignment { target, op, expr } => {
+                self.eval_assignment(target, *op, expr)
             },
             StatementNode::LoneExpression(expr) => {
+                // A match standing alone may run statements in its arms
+                if let ExpressionNode::Match(value, arms) = expr.as_ref() {
+                    return self.eval_match_statement(value, arms);
+                }
                 let sem_expr = self.eval_expr(expr)?;
                 Ok(vec![SemanticStatement::LoneExpression(sem_expr)])
             },
@@ -95,6 +167,12 @@ impl SemanticGen {
             StatementNode::ConditionalLoop { condition, body, label } => {
                 self.eval_conditional_loop(condition, body, label).map(|s| vec![s])
             },
+            StatementNode::ArrayLoop { index, element, array, body, label } => {
+                self.eval_array_loop(index.as_deref(), element, array, body, label)
+            },
+            StatementNode::RangeLoop { variable, range, body, label } => {
+                self.eval_range_loop(variable, range, body, label).map(|s| vec![s])
+            },
             StatementNode::Return(expr) => {
                 self.eval_return(expr.as_deref())
             },
@@ -104,6 +182,9 @@ impl SemanticGen {
             StatementNode::Continue(label) => {
                 self.eval_continue(label)
             },
+            StatementNode::Transaction { datasource_name, body } => {
+                self.eval_transaction(datasource_name, body).map(|s| vec![s])
+            },
         }
     }
 
@@ -111,11 +192,25 @@ impl SemanticGen {
         match expr {
             ExpressionNode::IntegerLiteral(val) => {
                 Ok(SemanticExpression {
-                    kind: SemanticExpressionKind::IntegerLiteral(*val),
+                    kind: SemanticExpressionKind::IntegerLiteral(int_literal(*val)?),
                     sem_type: SemanticType::new(SemanticTypeKind::Integer),
                     ownership: Ownership::Trivial,
                 })
             },
+            ExpressionNode::LongLiteral(val) => {
+                Ok(SemanticExpression {
+                    kind: SemanticExpressionKind::LongLiteral(*val),
+                    sem_type: SemanticType::new(SemanticTypeKind::Long),
+                    ownership: Ownership::Trivial,
+                })
+            },
+            ExpressionNode::FloatLiteral(val) => {
+                Ok(SemanticExpression {
+                    kind: SemanticExpressionKind::FloatLiteral(*val),
+                    sem_type: SemanticType::new(SemanticTypeKind::Float),
+                    ownership: Ownership::Trivial,
+                })
+            },
             ExpressionNode::BoolLiteral(val) => {
                 Ok(SemanticExpression {
                     kind: SemanticExpressionKind::BoolLiteral(*val),
@@ -130,6 +225,13 @@ impl SemanticGen {
                     ownership: Ownership::Borrowed,
                 })
             },
+            ExpressionNode::NullLiteral => {
+                Ok(SemanticExpression {
+                    kind: SemanticExpressionKind::Null,
+                    sem_type: SemanticType::new(SemanticTypeKind::Optional(SemanticType::new(SemanticTypeKind::Any))),
+                    ownership: Ownership::Trivial,
+                })
+            },
             ExpressionNode::Struct(struct_name_opt, column_values) => {
                 self.eval_struct(struct_name_opt.as_deref(), column_values)
             },
@@ -154,20 +256,39 @@ impl SemanticGen {
             ExpressionNode::ArrayIndex(array_expr, index_expr) => {
                 self.eval_array_index(array_expr, index_expr)
             },
-            ExpressionNode::Add(left, right) => {
-                self.eval_add(left, right)
+            ExpressionNode::Add(left, location, right) => {
+                self.eval_add(left, *location, right)
             }
-            ExpressionNode::Subtract(left, right) => {
-                self.eval_subtract(left, right)
+            ExpressionNode::Subtract(left, location, right) => {
+                self.eval_subtract(left, *location, right)
+            },
+            ExpressionNode::Arithmetic(left, location, op, right) => {
+                self.eval_arithmetic(left, *location, *op, right)
+            },
+            ExpressionNode::Negate(location, expr) => {
+                self.eval_negate(*location, expr)
             },
             ExpressionNode::Comparison(left, right, op) => {
                 self.eval_compare(left, right, *op)
             }
+            ExpressionNode::And(left, right) => {
+                self.eval_logical(left, right, true)
+            }
+            ExpressionNode::Or(left, right) => {
+                self.eval_logical(left, right, false)
+            }
+            ExpressionNode::Not(expr) => {
+                self.eval_not(expr)
+            }
+            ExpressionNode::IsNull(expr, negated) => {
+                let sem_expr = self.eval_expr(expr)?;
+                self.eval_null_check(sem_expr, *negated)
+            }
             ExpressionNode::FunctionCall(func_name, args) => {
                 self.call_function(func_name, args)
             }
-            ExpressionNode::MethodCall(receiver, method_name, args) => {
-                self.call_method(receiver, method_name, args)
+            ExpressionNode::MethodCall(receiver, location, method_name, args) => {
+                self.call_method(receiver, *location, method_name, args)
             }
             ExpressionNode::Closure(params, return_type, body) => {
                 self.eval_closure(params, return_type.as_ref(), body)
@@ -178,42 +299,99 @@ impl SemanticGen {
             ExpressionNode::ParameterizedQuery { parameters, query } => {
                 self.eval_parameterized_query(parameters, query)
             }
+            ExpressionNode::Variant { enum_name, variant_name, args } => {
+                self.eval_variant(enum_name, variant_name, args)
+            }
+            ExpressionNode::Match(value, arms) => {
+                self.eval_match_expression(value, arms)
+            }
         }
     }
 
-    pub fn eval_program(mut self, program: &ProgramNode) -> Result<SemanticProgram, SemanticError> {
-        for datasource in &program.datasources {
-            self.declare_datasource(&datasource.name, datasource.is_readonly)?;
+    // Datasources, tables and migrations belong to the database, which all modules share. Modules
+    // come after the modules they import, so their declarations may refer to those of imports
+    pub fn eval_modules(mut self, modules: &[ModuleNode]) -> Result<SemanticProgram, SemanticError> {
+        for module in modules {
+            self.in_module(module, |sem_gen| {
+                for datasource in &module.program.datasources {
+                    sem_gen.declare_datasource(&datasource.name, datasource.is_readonly, datasource.creates_tables)?;
+                }
+                Ok(())
+            })?;
         }
 
-        for table in &program.tables {
-            self.define_table(&table.name, &table.columns, table.is_readonly, &table.datasource_name)?;
+        for module in modules {
+            self.in_module(module, |sem_gen| {
+                for declaration in &module.program.declarations {
+                    match declaration {
+                        DeclarationNode::Table(table) => {
+                            sem_gen.define_table(&table.name, &table.columns, table.is_readonly, &table.datasource_name)?;
+                        }
+                        DeclarationNode::Struct(struct_node) => {
+                            sem_gen.define_struct(
+                                &struct_node.name,
+                                struct_node.is_public,
+                                &struct_node.type_params,
+                                &struct_node.fields,
+                            )?;
+                        }
+                        DeclarationNode::Enum(enum_node) => {
+                            sem_gen.define_enum(&enum_node.name, enum_node.is_public, &enum_node.variants)?;
+                        }
+                    }
+                }
+                Ok(())
+            })?;
         }
 
-        for function in &program.functions {
-            self.declare_function(&function.name, &function.params, &function.return_type)?;
+        // Functions of every module are declared before any is defined, so modules may call
+        // each other's functions whichever way they import each other
+        for module in modules {
+            self.in_module(module, |sem_gen| {
+                for function in &module.program.functions {
+                    sem_gen.declare_function(
+                        &function.name,
+                        function.is_public,
+                        &function.type_params,
+                        &function.params,
+                        &function.return_type,
+                    )?;
+                }
+                Ok(())
+            })?;
         }
         if !self.functions.contains_name("main") {
             return Err(SemanticError::MissingMainFunction);
         }
 
-        for function in &program.functions {
-            let func_id = self.functions[function.name.as_str()].id;
-            self.define_function(func_id, &function.body)?;
+        self.define_migrations(modules)?;
+
+        for module in modules {
+            self.in_module(module, |sem_gen| {
+                for function in &module.program.functions {
+                    let func_id = sem_gen.functions[sem_gen.declared_name(&function.name).as_str()].id;
+                    sem_gen.define_function(func_id, &function.body)?;
+                }
+                Ok(())
+            })?;
         }
+        self.instantiate_generic_functions()?;
 
         Ok(SemanticProgram {
             datasources: self.datasources.collect_id_value_map(),
             tables: self.tables.collect_id_value_map(),
             structs: self.structs.collect_id_value_map(),
+            enums: self.enums.collect_id_value_map(),
+            struct_instances: self.struct_instances,
             functions: self.functions.collect_id_value_map(),
             closures: self.closures,
             variables: self.variables,
+            migrations: self.migrations,
         })
     }
 
-    pub fn gen_semantic(program: &ProgramNode) -> Result<SemanticProgram, SemanticError> {
+    pub fn gen_semantic(modules: &[ModuleNode]) -> Result<SemanticProgram, SemanticError> {
         let sem_gen = SemanticGen::new();
-        sem_gen.eval_program(program)
+        sem_gen.eval_modules(modules)
     }
 }
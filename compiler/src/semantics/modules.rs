@@ -0,0 +1,66 @@
+use super::*;
+
+impl SemanticGen {
+    // Checks part of a module with its imports in scope. Errors outside the root module are
+    // reported along with the module they were found in
+    pub(super) fn in_module<T>(
+        &mut self,
+        module: &ModuleNode,
+        eval: impl FnOnce(&mut Self) -> Result<T, SemanticError>,
+    ) -> Result<T, SemanticError> {
+        self.module_name = module.name.clone();
+        self.imports = module.imports.clone();
+        eval(self).map_err(|error| {
+            if module.name.is_empty() {
+                error
+            } else {
+                SemanticError::InModule {
+                    module_name: module.name.clone(),
+                    error: Box::new(error),
+                }
+            }
+        })
+    }
+
+    // Functions and types are declared under the name of their module, so those of different
+    // modules never clash. The root module's keep their own names
+    pub(super) fn declared_name(&self, name: &str) -> String {
+        if self.module_name.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}::{}", self.module_name, name)
+        }
+    }
+
+    pub(super) fn declare_visibility(&mut self, declared_name: &str, is_public: bool) {
+        if is_public {
+            self.public_names.insert(declared_name.to_string());
+        }
+    }
+
+    // Resolves the name of a function or type, as written in the current module, to the name it
+    // was declared under. Qualified names refer to the public functions and types of imports
+    pub(super) fn resolve_name(&self, name: &str) -> Result<String, SemanticError> {
+        let Some((qualifier, item_name)) = name.split_once("::") else {
+            let declared_name = self.declared_name(name);
+            // Tables belong to the database rather than to a module, so every module may name
+            // the structs of their rows
+            if !self.type_exists(&declared_name) && self.tables.contains_name(name) {
+                return Ok(name.to_string());
+            }
+            return Ok(declared_name);
+        };
+
+        let module_name = self.imports.get(qualifier)
+            .ok_or_else(|| SemanticError::UndefinedModule { name: qualifier.to_string() })?;
+        let resolved_name = format!("{}::{}", module_name, item_name);
+        let is_declared = self.functions.contains_name(&resolved_name) || self.type_exists(&resolved_name);
+        if is_declared && !self.public_names.contains(&resolved_name) {
+            return Err(SemanticError::PrivateItem {
+                name: item_name.to_string(),
+                module_name: module_name.clone(),
+            });
+        }
+        Ok(resolved_name)
+    }
+}
@@ -0,0 +1,88 @@
+use super::*;
+
+// The variable a condition checks against null, and whether the check is negated
+pub(super) fn null_checked_variable(condition: &SemanticExpression) -> Option<(u32, bool)> {
+    let SemanticExpressionKind::IsNull { expr, negated } = &condition.kind else {
+        return None;
+    };
+    match expr.kind {
+        SemanticExpressionKind::Variable(var_id) => Some((var_id, *negated)),
+        _ => None,
+    }
+}
+
+impl SemanticGen {
+    pub(super) fn eval_null_check(&self, sem_expr: SemanticExpression, negated: bool) -> Result<SemanticExpression, SemanticError> {
+        if !sem_expr.sem_type.is_optional() || !sem_expr.sem_type.is_concrete() {
+            return Err(SemanticError::NonOptionalNullCheck {
+                found_type: sem_expr.sem_type,
+            });
+        }
+
+        Ok(SemanticExpression {
+            sem_type: SemanticType::new(SemanticTypeKind::Bool),
+            ownership: Ownership::Trivial,
+            kind: SemanticExpressionKind::IsNull {
+                expr: Box::new(sem_expr),
+                negated,
+            },
+        })
+    }
+
+    // Evaluates a branch in which the given optional variables are known not to be null.
+    // Each is shadowed by a variable of its inner type, which the branch may not assign to
+    pub(super) fn eval_narrowed_block(
+        &mut self,
+        statements: &[StatementNode],
+        non_null_variables: &[u32],
+    ) -> Result<SemanticBlock, SemanticError> {
+        if non_null_variables.is_empty() {
+            return self.eval_block(statements, SemanticScopeType::Block);
+        }
+
+        self.enter_scope(SemanticScopeType::Block);
+        let mut sem_stmts: Vec<SemanticStatement> = vec![];
+        for &var_id in non_null_variables {
+            let variable = &self.variables[&var_id];
+            let name = variable.name.clone();
+            let optional_type = variable.sem_type.clone();
+            let inner_type = optional_type.non_optional();
+
+            let narrowed_id = self.variable_id_gen.next_id();
+            self.scopes.last_mut().unwrap().variables.insert(name.clone(), narrowed_id);
+            self.variables.insert(narrowed_id, SemanticVariable {
+                name,
+                id: narrowed_id,
+                sem_type: inner_type.clone(),
+            });
+            self.narrowed_variables.insert(narrowed_id);
+
+            let ownership = if inner_type.can_be_owned() {
+                Ownership::Borrowed
+            } else {
+                Ownership::Trivial
+            };
+            sem_stmts.push(SemanticStatement::VariableDeclaration {
+                variable_id: narrowed_id,
+                init_expr: SemanticExpression {
+                    kind: SemanticExpressionKind::Unwrap(Box::new(SemanticExpression {
+                        kind: SemanticExpressionKind::Variable(var_id),
+                        sem_type: optional_type,
+                        ownership,
+                    })),
+                    sem_type: inner_type,
+                    ownership,
+                },
+            });
+        }
+
+        let mut sem_block = self.eval_block(statements, SemanticScopeType::Block)?;
+        sem_stmts.append(&mut sem_block.statements);
+        sem_stmts.extend(self.exit_scope(!sem_block.terminates));
+
+        Ok(SemanticBlock {
+            statements: sem_stmts,
+            terminates: sem_block.terminates,
+        })
+    }
+}
@@ -6,6 +6,16 @@ pub struct SemanticDatasource {
     pub name: String,
     pub id: u32,
     pub is_readonly: bool,
+    // Whether the datasource's tables are created at startup if missing
+    pub creates_tables: bool,
+}
+
+// A table, or the rows of a select, whose columns are visible to a query's clauses
+pub(super) struct ColumnSource {
+    name: String,
+    struct_id: u32,
+    // Set for tables of a left join, which may have no row matching the others
+    is_optional: bool,
 }
 
 pub struct SemanticTable {
@@ -14,51 +24,240 @@ pub struct SemanticTable {
     pub is_readonly: bool,
     pub struct_id: u32,
     pub datasource_id: u32,
+    // Constraints of each column, in declaration order
+    pub constraints: Vec<ColumnConstraints>,
+    // The version of the migration whose statements see the table as it then was, for tables
+    // standing in for a declared one, which are neither created nor verified
+    pub migration_version: Option<i32>,
+}
+
+impl SemanticTable {
+    // Index of the primary key column, which references to the table's rows store
+    pub fn key_column(&self) -> Option<u32> {
+        self.constraints.iter()
+            .position(|constraints| constraints.is_primary_key)
+            .map(|index| index as u32)
+    }
+}
+
+// Tables missing from a left join leave their columns null, so whatever is read from them is optional
+fn optional_if(is_optional: bool, sem_type: SemanticType) -> SemanticType {
+    if is_optional && !sem_type.is_optional() {
+        SemanticType::new(SemanticTypeKind::Optional(sem_type))
+    } else {
+        sem_type
+    }
+}
+
+fn column_ref_str(column: &ColumnRefNode) -> String {
+    match &column.table_name {
+        Some(table_name) => format!("{}.{}", table_name, column.column_name),
+        None => column.column_name.clone(),
+    }
+}
+
+// Name of the field holding a table's columns in the rows of a join
+pub(super) fn table_field_name(table_name: &str) -> String {
+    let start = table_name.find(|c: char| c.is_ascii_uppercase()).unwrap_or(0);
+    let mut field_name = table_name.to_string();
+    field_name[start..start + 1].make_ascii_lowercase();
+    field_name
+}
+
+impl WhereClause {
+    // Values bound to the clause's parameters, in placeholder order
+    pub fn bound_values(&self) -> Vec<&SemanticExpression> {
+        match self {
+            WhereClause::Comparison { value, .. } => vec![value],
+            WhereClause::Like { pattern, .. } => vec![pattern],
+            WhereClause::In { values, .. } => vec![values],
+            WhereClause::IsNull { .. } => vec![],
+            WhereClause::And(left, right) | WhereClause::Or(left, right) => {
+                let mut values = left.bound_values();
+                values.extend(right.bound_values());
+                values
+            }
+            WhereClause::Not(inner) => inner.bound_values(),
+        }
+    }
 }
 
 impl SemanticGen {
-    fn eval_where_clause(&self, table: &SemanticTable, column_name: &str, sem_expr: SemanticExpression) -> Result<WhereClause, SemanticError> {
-        let table_struct = &self.structs[table.struct_id];
-        let column_type = table_struct.fields.get(column_name);
-        match column_type {
-            Some(col_type) => {
-                let compatible = self.try_downcast(col_type, &sem_expr.sem_type);
-                if !compatible {
+    fn table_source(&self, table_id: u32) -> ColumnSource {
+        let table = &self.tables[table_id];
+        ColumnSource {
+            name: table.name.clone(),
+            struct_id: table.struct_id,
+            is_optional: false,
+        }
+    }
+
+    // Resolves a possibly table-qualified column against the tables of a query.
+    // Columns are numbered consecutively across tables, in the order they appear in the query
+    fn resolve_column(&self, sources: &[ColumnSource], column: &ColumnRefNode) -> Result<(u32, SemanticType), SemanticError> {
+        let mut offset = 0;
+        let mut found = None;
+        for source in sources {
+            let source_struct = &self.structs[source.struct_id];
+            let in_scope = column.table_name.as_ref().is_none_or(|name| *name == source.name);
+            let position = source_struct.field_order.iter().position(|name| *name == column.column_name);
+            if let (true, Some(position)) = (in_scope, position) {
+                if found.is_some() {
+                    return Err(SemanticError::AmbiguousColumn { column_name: column.column_name.clone() });
+                }
+                let column_type = source_struct.fields[&column.column_name].clone();
+                found = Some((offset + position as u32, column_type));
+            }
+            offset += source_struct.field_order.len() as u32;
+        }
+
+        match (found, &column.table_name) {
+            (Some(resolved), _) => Ok(resolved),
+            (None, Some(table_name)) if !sources.iter().any(|source| source.name == *table_name) => {
+                Err(SemanticError::TableNotInQuery { table_name: table_name.clone() })
+            }
+            (None, Some(table_name)) => Err(SemanticError::UndefinedColumn {
+                table_name: table_name.clone(),
+                column_name: column.column_name.clone(),
+            }),
+            (None, None) if sources.len() == 1 => Err(SemanticError::UndefinedColumn {
+                table_name: sources[0].name.clone(),
+                column_name: column.column_name.clone(),
+            }),
+            (None, None) => Err(SemanticError::UnresolvedColumn { column_name: column.column_name.clone() }),
+        }
+    }
+
+    fn source_of_column<'a>(&self, sources: &'a [ColumnSource], column_index: u32) -> &'a ColumnSource {
+        let mut offset = 0;
+        for source in sources {
+            offset += self.structs[source.struct_id].field_order.len() as u32;
+            if column_index < offset {
+                return source;
+            }
+        }
+        panic!("Column index out of range of query tables")
+    }
+
+    fn eval_where_clause(&mut self, sources: &[ColumnSource], where_node: &WhereNode) -> Result<WhereClause, SemanticError> {
+        match where_node {
+            WhereNode::Comparison { column, op, value } => {
+                let sem_value = self.eval_expr(value)?;
+                let (column_index, column_type) = self.resolve_column(sources, column)?;
+                if !self.try_downcast(&column_type, &sem_value.sem_type) {
                     return Err(SemanticError::IncompatibleColumnValue {
-                        table_name: table.name.clone(),
-                        column_name: column_name.to_string(),
-                        expected: col_type.clone(),
-                        found: sem_expr.sem_type.clone(),
+                        table_name: self.source_of_column(sources, column_index).name.clone(),
+                        column_name: column.column_name.clone(),
+                        expected: column_type,
+                        found: sem_value.sem_type,
+                    });
+                }
+
+                // SQL never finds NULL equal to anything, so comparing with null checks for it instead
+                let is_equality = *op == ComparisonType::Equal || *op == ComparisonType::NotEqual;
+                if let SemanticExpressionKind::Null = sem_value.kind && is_equality {
+                    return Ok(WhereClause::IsNull {
+                        column_index,
+                        negated: *op == ComparisonType::NotEqual,
                     });
                 }
+
+                let is_ordered = !matches!(
+                    column_type.non_optional().kind(),
+                    SemanticTypeKind::Bool | SemanticTypeKind::Enum(_, _)
+                );
+                if !is_ordered && *op != ComparisonType::Equal && *op != ComparisonType::NotEqual {
+                    return Err(SemanticError::IncompatibleOperands {
+                        operation: "comparison".to_string(),
+                        left_type: column_type,
+                        right_type: sem_value.sem_type,
+                    });
+                }
+
+                Ok(WhereClause::Comparison {
+                    column_index,
+                    op: *op,
+                    value: Box::new(sem_value),
+                })
             },
-            None => {
-                return Err(SemanticError::UndefinedColumn {
-                    table_name: table.name.clone(),
-                    column_name: column_name.to_string(),
-                });
-            }
-        }
+            WhereNode::Like { column, pattern } => {
+                let sem_pattern = self.eval_expr(pattern)?;
+                let (column_index, column_type) = self.resolve_column(sources, column)?;
+                if column_type.non_optional() != SemanticTypeKind::String || sem_pattern.sem_type != SemanticTypeKind::String {
+                    return Err(SemanticError::IncompatibleOperands {
+                        operation: "LIKE".to_string(),
+                        left_type: column_type,
+                        right_type: sem_pattern.sem_type,
+                    });
+                }
 
-        let column_index = table_struct.field_order.iter()
-            .position(|name| name == column_name)
-            .unwrap() as u32;
-        Ok(WhereClause {
-            column_index,
-            value: Box::new(sem_expr),
-        })
+                Ok(WhereClause::Like {
+                    column_index,
+                    pattern: Box::new(sem_pattern),
+                })
+            },
+            WhereNode::In { column, values } => {
+                let sem_values = self.eval_expr(values)?;
+                let (column_index, column_type) = self.resolve_column(sources, column)?;
+                let array_type = SemanticType::new(SemanticTypeKind::Array(column_type.non_optional()));
+                if !self.try_downcast(&array_type, &sem_values.sem_type) {
+                    return Err(SemanticError::IncompatibleOperands {
+                        operation: "IN".to_string(),
+                        left_type: column_type,
+                        right_type: sem_values.sem_type,
+                    });
+                }
+
+                Ok(WhereClause::In {
+                    column_index,
+                    values: Box::new(sem_values),
+                })
+            },
+            WhereNode::IsNull { column, negated } => {
+                let (column_index, _) = self.resolve_column(sources, column)?;
+                Ok(WhereClause::IsNull {
+                    column_index,
+                    negated: *negated,
+                })
+            },
+            WhereNode::And(left, right) => {
+                let sem_left = self.eval_where_clause(sources, left)?;
+                let sem_right = self.eval_where_clause(sources, right)?;
+                Ok(WhereClause::And(Box::new(sem_left), Box::new(sem_right)))
+            },
+            WhereNode::Or(left, right) => {
+                let sem_left = self.eval_where_clause(sources, left)?;
+                let sem_right = self.eval_where_clause(sources, right)?;
+                Ok(WhereClause::Or(Box::new(sem_left), Box::new(sem_right)))
+            },
+            WhereNode::Not(inner) => {
+                let sem_inner = self.eval_where_clause(sources, inner)?;
+                Ok(WhereClause::Not(Box::new(sem_inner)))
+            },
+        }
     }
 
-    pub(super) fn declare_datasource(&mut self, name: &str, is_readonly: bool) -> Result<(), SemanticError> {
+    pub(super) fn declare_datasource(
+        &mut self,
+        name: &str,
+        is_readonly: bool,
+        creates_tables: bool
+    ) -> Result<(), SemanticError> {
         if self.datasources.contains_name(name) {
             return Err(SemanticError::DuplicateDatasourceDeclaration {
                 name: name.to_string()
             });
         }
+        if is_readonly && creates_tables {
+            return Err(SemanticError::ReadonlyDatasourceCreatesTables {
+                name: name.to_string()
+            });
+        }
         let datasource_id = self.datasource_id_gen.next_id();
         self.datasources.insert(name.to_string(), datasource_id, SemanticDatasource {
             name: name.to_string(),
             is_readonly,
+            creates_tables,
             id: datasource_id,
         });
         Ok(())
@@ -67,13 +266,16 @@ impl SemanticGen {
     pub(super) fn define_table(
         &mut self,
         name: &str,
-        fields: &[TypedQNameNode],
+        column_nodes: &[ColumnNode],
         is_readonly: bool,
         datasource_name: &str
     ) -> Result<(), SemanticError> {
         if self.tables.contains_name(name) {
             return Err(SemanticError::DuplicateTableDefinition { name: name.to_string() });
         }
+        if self.type_exists(name) {
+            return Err(SemanticError::DuplicateTypeDefinition { name: name.to_string() });
+        }
 
         let datasource = self.datasources.get_by_name(datasource_name)
             .ok_or_else(|| SemanticError::UndefinedDatasource {
@@ -86,69 +288,407 @@ impl SemanticGen {
             });
         }
 
-        let mut struct_fields = HashMap::new();
-        for field in fields {
-            let is_primitive = match field.type_node {
-                TypeNode::Integer | TypeNode::Bool | TypeNode::String => true,
-                _ => false,
-            };
-            if !is_primitive {
-                return Err(SemanticError::NonPrimitiveColumnType { 
-                    table_name: name.to_string(),
-                    column_name: field.name.clone()
-                });
-            }
-            struct_fields.insert(
-                field.name.clone(),
-                self.try_get_semantic_type(&field.type_node)?,
-            );
-        }
-
+        let datasource_id = datasource.id;
         let struct_id = self.struct_id_gen.next_id();
-        self.structs.insert(name.to_string(), struct_id, SemanticStruct {
-            name: name.to_string(),
-            id: struct_id,
-            fields: struct_fields,
-            field_order: fields.iter().map(|f| f.name.clone()).collect(),
-        });
-
         let table_id = self.table_id_gen.next_id();
+        // The table is declared ahead of its columns, which may refer to it
         self.tables.insert(name.to_string(), table_id, SemanticTable {
             name: name.to_string(),
             id: table_id,
-            datasource_id: datasource.id,
+            datasource_id,
             struct_id,
             is_readonly,
+            constraints: vec![],
+            migration_version: None,
         });
 
-        Ok(())
+        let columns = self.eval_columns(name, column_nodes)?;
+        let struct_fields = columns.iter()
+            .map(|column| (column.name.clone(), column.sem_type.clone()))
+            .collect::<HashMap<String, SemanticType>>();
+        self.structs.insert(name.to_string(), struct_id, SemanticStruct {
+            name: name.to_string(),
+            id: struct_id,
+            fields: struct_fields,
+            field_order: columns.iter().map(|column| column.name.clone()).collect(),
+            is_synthesized: false,
+        });
+        self.tables[table_id].constraints = columns.iter()
+            .map(|column| column.constraints.clone())
+            .collect();
+
+        self.check_references(name, datasource_id, &columns)
+    }
+
+    // Finds the table a query names. Statements of a migration see the tables of its datasource as
+    // the migration has left them so far
+    pub(super) fn lookup_table(&mut self, name: &str) -> Result<u32, SemanticError> {
+        if let Some((datasource_id, version)) = self.migration {
+            if let Some(table_id) = self.migrated_table(datasource_id, version, name) {
+                return Ok(table_id);
+            }
+            if self.tables.get_by_name(name).is_some_and(|table| table.datasource_id == datasource_id) {
+                return Err(SemanticError::MigrationUndefinedTable {
+                    table_name: name.to_string(),
+                    version,
+                });
+            }
+        }
+        self.tables.get_by_name(name)
+            .map(|table| table.id)
+            .ok_or_else(|| SemanticError::UndefinedTable { name: name.to_string() })
+    }
+
+    fn eval_query_bound(&mut self, clause: &'static str, expr: &ExpressionNode) -> Result<Box<SemanticExpression>, SemanticError> {
+        let sem_expr = self.eval_expr(expr)?;
+        if sem_expr.sem_type != SemanticTypeKind::Integer {
+            return Err(SemanticError::NonIntegralQueryBound {
+                clause,
+                found_type: sem_expr.sem_type,
+            });
+        }
+        Ok(Box::new(sem_expr))
+    }
+
+    fn eval_aggregate(
+        &self,
+        sources: &[ColumnSource],
+        function_name: &str,
+        column: Option<&ColumnRefNode>
+    ) -> Result<(AggregateFunction, Option<u32>, SemanticType), SemanticError> {
+        let function = match function_name {
+            "count" => AggregateFunction::Count,
+            "sum" => AggregateFunction::Sum,
+            "avg" => AggregateFunction::Average,
+            "min" => AggregateFunction::Minimum,
+            "max" => AggregateFunction::Maximum,
+            _ => return Err(SemanticError::UndefinedAggregate { name: function_name.to_string() }),
+        };
+
+        let column = column
+            .map(|column| self.resolve_column(sources, column))
+            .transpose()?;
+        let Some((column_index, column_type)) = column else {
+            if let AggregateFunction::Count = function {
+                return Ok((function, None, SemanticType::new(SemanticTypeKind::Integer)));
+            }
+            return Err(SemanticError::MismatchingCallArity {
+                function_name: function_name.to_string(),
+                expected: 1,
+                found: 0,
+            });
+        };
+
+        let result_type = match function {
+            AggregateFunction::Count => Some(SemanticType::new(SemanticTypeKind::Integer)),
+            AggregateFunction::Sum => {
+                let is_numeric = matches!(
+                    column_type.non_optional().kind(),
+                    SemanticTypeKind::Integer | SemanticTypeKind::Long | SemanticTypeKind::Float
+                );
+                is_numeric.then(|| column_type.clone())
+            }
+            // Averages are fractional even over integer columns
+            AggregateFunction::Average => match column_type.non_optional().kind() {
+                SemanticTypeKind::Integer | SemanticTypeKind::Long | SemanticTypeKind::Float => {
+                    let float_type = SemanticType::new(SemanticTypeKind::Float);
+                    Some(if column_type.is_optional() {
+                        SemanticType::new(SemanticTypeKind::Optional(float_type))
+                    } else {
+                        float_type
+                    })
+                }
+                _ => None,
+            }
+            AggregateFunction::Minimum | AggregateFunction::Maximum => {
+                (column_type.non_optional() != SemanticTypeKind::Bool).then(|| column_type.clone())
+            }
+        };
+        match result_type {
+            Some(result_type) => Ok((function, Some(column_index), result_type)),
+            None => Err(SemanticError::IncompatibleAggregateColumn {
+                function_name: function_name.to_string(),
+                column_type,
+            }),
+        }
+    }
+
+    fn eval_join_condition(
+        &self,
+        sources: &[ColumnSource],
+        condition: &JoinConditionNode
+    ) -> Result<JoinCondition, SemanticError> {
+        let (left_column, left_type) = self.resolve_column(sources, &condition.left)?;
+        let (right_column, right_type) = self.resolve_column(sources, &condition.right)?;
+
+        let is_ordered = left_type != SemanticTypeKind::Bool;
+        let is_equality = condition.op == ComparisonType::Equal || condition.op == ComparisonType::NotEqual;
+        if left_type != right_type || (!is_ordered && !is_equality) {
+            return Err(SemanticError::IncompatibleOperands {
+                operation: "comparison".to_string(),
+                left_type,
+                right_type,
+            });
+        }
+
+        Ok(JoinCondition {
+            left_column,
+            op: condition.op,
+            right_column,
+        })
+    }
+
+    // Synthesizes the struct type of a select's rows. Identical projections share one struct
+    fn synthesize_row_struct(
+        &mut self,
+        struct_name: String,
+        fields: HashMap<String, SemanticType>,
+        field_order: Vec<String>,
+    ) -> SemanticType {
+        let struct_id = match self.structs.get_by_name(&struct_name) {
+            Some(existing) => existing.id,
+            None => {
+                let struct_id = self.struct_id_gen.next_id();
+                self.structs.insert(struct_name.clone(), struct_id, SemanticStruct {
+                    name: struct_name.clone(),
+                    id: struct_id,
+                    fields,
+                    field_order,
+                    is_synthesized: true,
+                });
+                struct_id
+            }
+        };
+        SemanticType::new(SemanticTypeKind::NamedStruct(struct_id, struct_name))
+    }
+
+    // Resolves the selected columns along with the struct type of each result row.
+    // Projections get a synthesized struct holding only the selected fields, while selecting
+    // every column of a join nests one struct per table, named after the table
+    fn eval_projection(
+        &mut self,
+        sources: &[ColumnSource],
+        columns: &[SelectColumnNode],
+        group_by: &[u32],
+    ) -> Result<(Vec<SelectColumn>, SemanticType), SemanticError> {
+        let sources_str = sources.iter()
+            .map(|source| source.name.as_str())
+            .collect::<Vec<_>>()
+            .join(" join ");
+        let is_aggregate = !group_by.is_empty() || columns.iter()
+            .any(|column| matches!(column, SelectColumnNode::Aggregate { .. }));
+
+        if columns.is_empty() {
+            if is_aggregate {
+                let ungrouped_column = sources.iter()
+                    .flat_map(|source| self.structs[source.struct_id].field_order.iter())
+                    .enumerate()
+                    .find(|(i, _)| !group_by.contains(&(*i as u32)));
+                if let Some((_, column_name)) = ungrouped_column {
+                    return Err(SemanticError::UngroupedSelectColumn { column_name: column_name.clone() });
+                }
+            }
+
+            if let [source] = sources {
+                let row_type = SemanticType::new(SemanticTypeKind::NamedStruct(
+                    source.struct_id,
+                    self.structs[source.struct_id].name.clone(),
+                ));
+                return Ok((vec![], row_type));
+            }
+
+            let mut fields = HashMap::new();
+            let mut field_order = vec![];
+            for source in sources {
+                let field_name = table_field_name(&source.name);
+                let field_type = optional_if(source.is_optional, SemanticType::new(SemanticTypeKind::NamedStruct(
+                    source.struct_id,
+                    self.structs[source.struct_id].name.clone(),
+                )));
+                fields.insert(field_name.clone(), field_type);
+                field_order.push(field_name);
+            }
+            let struct_name = format!("{}{{{}}}", sources_str, field_order.join(", "));
+            let row_type = self.synthesize_row_struct(struct_name, fields, field_order);
+            return Ok((vec![], row_type));
+        }
+
+        let mut sem_columns = vec![];
+        let mut fields = HashMap::new();
+        let mut field_order = vec![];
+        let mut column_strs = vec![];
+        for column in columns {
+            let alias = match column {
+                SelectColumnNode::Column { alias, .. } | SelectColumnNode::Aggregate { alias, .. } => alias,
+            };
+            let (sem_column, field_type, source_str, default_name) = match column {
+                SelectColumnNode::Column { column, .. } => {
+                    let (column_index, column_type) = self.resolve_column(sources, column)?;
+                    if is_aggregate && !group_by.contains(&column_index) {
+                        return Err(SemanticError::UngroupedSelectColumn { column_name: column.column_name.clone() });
+                    }
+                    let column_type = optional_if(self.source_of_column(sources, column_index).is_optional, column_type);
+                    let column_str = column_ref_str(column);
+                    (SelectColumn::Column(column_index), column_type, column_str, column.column_name.clone())
+                }
+                SelectColumnNode::Aggregate { function_name, column, .. } => {
+                    let (function, column_index, result_type) = self.eval_aggregate(
+                        sources,
+                        function_name,
+                        column.as_ref()
+                    )?;
+                    let argument_str = column.as_ref().map(column_ref_str).unwrap_or_default();
+                    let source_str = format!("{}({})", function_name, argument_str);
+                    let default_name = match column {
+                        Some(column) => format!("{}_{}", function_name, column.column_name),
+                        None => function_name.clone(),
+                    };
+                    (SelectColumn::Aggregate(function, column_index), result_type, source_str, default_name)
+                }
+            };
+
+            let field_name = alias.clone().unwrap_or(default_name);
+            if fields.insert(field_name.clone(), field_type).is_some() {
+                return Err(SemanticError::DuplicateSelectColumn { name: field_name });
+            }
+            sem_columns.push(sem_column);
+            field_order.push(field_name);
+            column_strs.push(match alias {
+                Some(alias) => format!("{} as {}", source_str, alias),
+                None => source_str,
+            });
+        }
+
+        let struct_name = format!("{}{{{}}}", sources_str, column_strs.join(", "));
+        let row_type = self.synthesize_row_struct(struct_name, fields, field_order);
+        Ok((sem_columns, row_type))
     }
 
     fn eval_select_query(&mut self, query: &SelectQueryNode) -> Result<SemanticQuery, SemanticError> {
-        let where_expr = query.where_clause.as_ref().map(|where_node| {
-            let sem_expr = self.eval_expr(&where_node.value)?;
-            Ok((where_node.column_name.clone(), sem_expr))
-         }).transpose()?;
+        let table_id = self.lookup_table(&query.table_name)?;
+        let datasource_id = self.tables[table_id].datasource_id;
+
+        let mut sources = vec![self.table_source(table_id)];
+        let mut joins = vec![];
+        for join_node in &query.joins {
+            let join_table_id = self.lookup_table(&join_node.table_name)?;
+            let join_table = &self.tables[join_table_id];
+            if join_table.datasource_id != datasource_id {
+                return Err(SemanticError::CrossDatasourceJoin {
+                    table_name: query.table_name.clone(),
+                    joined_table_name: join_node.table_name.clone(),
+                });
+            }
+            if sources.iter().any(|source| source.name == join_node.table_name) {
+                return Err(SemanticError::DuplicateJoinedTable { table_name: join_node.table_name.clone() });
+            }
 
-        let table = self.tables.get_by_name(&query.table_name)
-            .ok_or_else(|| SemanticError::UndefinedTable { name: query.table_name.clone() })?;
+            sources.push(ColumnSource {
+                is_optional: join_node.is_left,
+                ..self.table_source(join_table_id)
+            });
+            let conditions = join_node.conditions.iter()
+                .map(|condition| self.eval_join_condition(&sources, condition))
+                .collect::<Result<Vec<_>, SemanticError>>()?;
+            joins.push(Join {
+                table_id: join_table_id,
+                is_left: join_node.is_left,
+                conditions,
+            });
+        }
 
-        let where_clause = match where_expr {
-            Some((column_name, sem_expr)) => Some(self.eval_where_clause(table, &column_name, sem_expr)?),
+        let group_by = query.group_by.iter()
+            .map(|column| self.resolve_column(&sources, column).map(|(index, _)| index))
+            .collect::<Result<Vec<_>, SemanticError>>()?;
+        let (columns, result_type) = self.eval_projection(&sources, &query.columns, &group_by)?;
+
+        // A lone aggregate over the whole table yields a single value rather than rows
+        let is_scalar = group_by.is_empty()
+            && columns.len() == 1
+            && matches!(columns[0], SelectColumn::Aggregate(..));
+        let shape = if is_scalar { SelectShape::Scalar } else { SelectShape::Rows };
+
+        let where_clause = query.where_clause.as_ref()
+            .map(|where_node| self.eval_where_clause(&sources, where_node))
+            .transpose()?;
+
+        let having = match &query.having {
+            Some(_) if group_by.is_empty() => return Err(SemanticError::HavingWithoutGroupBy),
+            Some(having_node) => {
+                let SemanticTypeKind::NamedStruct(result_struct_id, result_name) = result_type.kind() else {
+                    unreachable!()
+                };
+                let result_source = ColumnSource {
+                    name: result_name,
+                    struct_id: result_struct_id,
+                    is_optional: false,
+                };
+                Some(self.eval_where_clause(&[result_source], having_node)?)
+            }
             None => None,
         };
 
+        let order_by = query.order_by.iter()
+            .map(|ordering| {
+                let (column_index, _) = self.resolve_column(&sources, &ordering.column)?;
+                Ok(Ordering {
+                    column_index,
+                    descending: ordering.descending,
+                })
+            })
+            .collect::<Result<Vec<_>, SemanticError>>()?;
+
+        let limit = query.limit.as_ref()
+            .map(|limit_expr| self.eval_query_bound("LIMIT", limit_expr))
+            .transpose()?;
+        let offset = query.offset.as_ref()
+            .map(|offset_expr| self.eval_query_bound("OFFSET", offset_expr))
+            .transpose()?;
+
         Ok(SemanticQuery::Select {
-            table_id: table.id,
+            table_id,
+            location: query.location,
+            joins,
+            is_distinct: query.is_distinct,
+            columns,
+            result_type,
+            shape,
             where_clause,
+            group_by,
+            having,
+            order_by,
+            limit,
+            offset,
         })
     }
 
+    // Resolves the columns returned by a mutating query, which are projected like a select's
+    fn eval_returning(
+        &mut self,
+        table_id: u32,
+        columns: &[SelectColumnNode]
+    ) -> Result<Option<Returning>, SemanticError> {
+        if columns.is_empty() {
+            return Ok(None);
+        }
+
+        let sources = [self.table_source(table_id)];
+        let (sem_columns, result_type) = self.eval_projection(&sources, columns, &[])?;
+        let columns = sem_columns.into_iter()
+            .map(|column| match column {
+                SelectColumn::Column(column_index) => column_index,
+                SelectColumn::Aggregate(..) => unreachable!("Aggregates cannot be returned"),
+            })
+            .collect();
+
+        Ok(Some(Returning { columns, result_type }))
+    }
+
     fn eval_insert_query(&mut self, query: &InsertQueryNode) -> Result<SemanticQuery, SemanticError> {
         let sem_value = self.eval_expr(&query.data_expr)?;
 
-        let table = self.tables.get_by_name(&query.table_name)
-            .ok_or_else(|| SemanticError::UndefinedTable { name: query.table_name.clone() })?;
+        let table_id = self.lookup_table(&query.table_name)?;
+        let table = &self.tables[table_id];
         if table.is_readonly {
             return Err(SemanticError::ReadonlyTableMutation {
                 table_name: table.name.clone(),
@@ -156,24 +696,81 @@ impl SemanticGen {
             });
         }
 
-        let expected_type = SemanticType::new(SemanticTypeKind::NamedStruct(
-            table.struct_id,
-            self.structs[table.struct_id].name.clone()
-        ));
+        // Arrays insert each of their elements as a row
+        let (value_row_type, is_array) = match sem_value.sem_type.kind() {
+            SemanticTypeKind::Array(elem_type) => (elem_type, true),
+            _ => (sem_value.sem_type.clone(), false),
+        };
+        let (columns, row_type) = self.eval_insert_columns(table_id, &value_row_type)?;
+        let expected_type = if is_array {
+            SemanticType::new(SemanticTypeKind::Array(row_type))
+        } else {
+            row_type
+        };
         let compatible = self.try_downcast(&expected_type, &sem_value.sem_type);
         if !compatible {
             return Err(SemanticError::IncompatibleInsertData {
-                table_name: table.name.clone(),
+                table_name: self.tables[table_id].name.clone(),
                 found_type: sem_value.sem_type.clone()
             });
         }
 
+        let returning = self.eval_returning(table_id, &query.returning)?;
+
         Ok(SemanticQuery::Insert {
-            table_id: table.id,
+            table_id,
+            location: query.location,
+            columns,
             value: Box::new(sem_value),
+            returning,
         })
     }
 
+    // Picks the columns given by inserted rows, along with the struct type of a row.
+    // Anonymous structs may leave out autoincremented, defaulted and optional columns, taking on a
+    // synthesized struct of the remaining ones
+    fn eval_insert_columns(
+        &mut self,
+        table_id: u32,
+        value_row_type: &SemanticType,
+    ) -> Result<(Vec<u32>, SemanticType), SemanticError> {
+        let table = &self.tables[table_id];
+        let table_struct = &self.structs[table.struct_id];
+        let all_columns = (0..table_struct.field_order.len() as u32).collect::<Vec<u32>>();
+        let table_row_type = SemanticType::new(SemanticTypeKind::NamedStruct(
+            table.struct_id,
+            table_struct.name.clone()
+        ));
+        let SemanticTypeKind::AnonymousStruct(value_fields) = value_row_type.kind() else {
+            return Ok((all_columns, table_row_type));
+        };
+
+        let mut columns = vec![];
+        for (i, column_name) in table_struct.field_order.iter().enumerate() {
+            if value_fields.contains_key(column_name) {
+                columns.push(i as u32);
+            } else if !table.constraints[i].is_omittable() && !table_struct.fields[column_name].is_optional() {
+                return Err(SemanticError::MissingInsertColumn {
+                    table_name: table.name.clone(),
+                    column_name: column_name.clone(),
+                });
+            }
+        }
+        if columns.len() == all_columns.len() {
+            return Ok((all_columns, table_row_type));
+        }
+
+        let field_order = columns.iter()
+            .map(|&i| table_struct.field_order[i as usize].clone())
+            .collect::<Vec<String>>();
+        let fields = field_order.iter()
+            .map(|name| (name.clone(), table_struct.fields[name].clone()))
+            .collect::<HashMap<String, SemanticType>>();
+        let struct_name = format!("{}{{{}}}", table.name, field_order.join(", "));
+        let row_type = self.synthesize_row_struct(struct_name, fields, field_order);
+        Ok((columns, row_type))
+    }
+
     fn eval_update_query(&mut self, query: &UpdateQueryNode) -> Result<SemanticQuery, SemanticError> {
         let assignments: Vec<(&str, SemanticExpression)> = query.assignments
             .iter()
@@ -183,14 +780,8 @@ impl SemanticGen {
             })
             .collect::<Result<_, SemanticError>>()?;
 
-        let where_expr = query.where_clause.as_ref().map(|where_node| {
-            let sem_expr = self.eval_expr(&where_node.value)?;
-            Ok((where_node.column_name.clone(), sem_expr))
-        }).transpose()?;
-
-        let Some(table) = self.tables.get_by_name(&query.table_name) else {
-            return Err(SemanticError::UndefinedTable { name: query.table_name.clone() });
-        };
+        let table_id = self.lookup_table(&query.table_name)?;
+        let table = &self.tables[table_id];
         if table.is_readonly {
             return Err(SemanticError::ReadonlyTableMutation {
                 table_name: table.name.clone(),
@@ -198,11 +789,12 @@ impl SemanticGen {
             });
         }
 
-        let where_clause = match where_expr {
-            Some((column_name, sem_expr)) => Some(self.eval_where_clause(table, &column_name, sem_expr)?),
-            None => None,
-        };
+        let sources = [self.table_source(table_id)];
+        let where_clause = query.where_clause.as_ref()
+            .map(|where_node| self.eval_where_clause(&sources, where_node))
+            .transpose()?;
 
+        let table = &self.tables[table_id];
         let table_struct = &self.structs[table.struct_id];
         let sem_assignments = assignments.into_iter().map(|(col_name, sem_expr)| {
             let column_type = table_struct.fields.get(col_name);
@@ -235,21 +827,20 @@ impl SemanticGen {
             })
         }).collect::<Result<Vec<_>, SemanticError>>()?;
 
+        let returning = self.eval_returning(table_id, &query.returning)?;
+
         Ok(SemanticQuery::Update {
-            table_id: table.id,
+            table_id,
+            location: query.location,
             assignments: sem_assignments,
             where_clause,
+            returning,
         })
     }
 
     fn eval_delete_query(&mut self, query: &DeleteQueryNode) -> Result<SemanticQuery, SemanticError> {
-        let where_expr = query.where_clause.as_ref().map(|where_node| {
-            let sem_expr = self.eval_expr(&where_node.value)?;
-            Ok((where_node.column_name.clone(), sem_expr))
-         }).transpose()?;
-
-        let table = self.tables.get_by_name(&query.table_name)
-            .ok_or_else(|| SemanticError::UndefinedTable { name: query.table_name.clone() })?;
+        let table_id = self.lookup_table(&query.table_name)?;
+        let table = &self.tables[table_id];
         if table.is_readonly {
             return Err(SemanticError::ReadonlyTableMutation {
                 table_name: table.name.clone(),
@@ -257,14 +848,18 @@ impl SemanticGen {
             });
         }
 
-        let where_clause = match where_expr {
-            Some((column_name, sem_expr)) => Some(self.eval_where_clause(table, &column_name, sem_expr)?),
-            None => None,
-        };
+        let sources = [self.table_source(table_id)];
+        let where_clause = query.where_clause.as_ref()
+            .map(|where_node| self.eval_where_clause(&sources, where_node))
+            .transpose()?;
+
+        let returning = self.eval_returning(table_id, &query.returning)?;
 
         Ok(SemanticQuery::Delete {
-            table_id: table.id,
+            table_id,
+            location: query.location,
             where_clause,
+            returning,
         })
     }
 
@@ -279,27 +874,56 @@ impl SemanticGen {
 
     fn return_type_of_query(&self, query: &SemanticQuery) -> SemanticType {
         match query {
-            SemanticQuery::Select { table_id, .. } => {
-                let table = &self.tables[*table_id];
-                let table_struct = &self.structs[table.struct_id];
-                let struct_type = SemanticType::new(SemanticTypeKind::NamedStruct(
-                    table.struct_id,
-                    table_struct.name.clone(),
-                ));
-                SemanticType::new(SemanticTypeKind::Array(struct_type))
+            SemanticQuery::Select { result_type, shape: SelectShape::Scalar, .. } => {
+                let SemanticTypeKind::NamedStruct(struct_id, _) = result_type.kind() else {
+                    unreachable!()
+                };
+                let result_struct = &self.structs[struct_id];
+                result_struct.fields[&result_struct.field_order[0]].clone()
+            },
+            SemanticQuery::Select { result_type, shape: SelectShape::Row, .. } => result_type.clone(),
+            SemanticQuery::Select { result_type, shape: SelectShape::Rows, .. } => {
+                SemanticType::new(SemanticTypeKind::Array(result_type.clone()))
+            },
+            // Inserts give the rowid, which SQLite keeps as a 64-bit integer, or the returned columns
+            // of each inserted row
+            SemanticQuery::Insert { value, returning, .. } => {
+                let row_type = match returning {
+                    Some(returning) => returning.result_type.clone(),
+                    None => SemanticType::new(SemanticTypeKind::Long),
+                };
+                match value.sem_type.kind() {
+                    SemanticTypeKind::Array(_) => SemanticType::new(SemanticTypeKind::Array(row_type)),
+                    _ => row_type,
+                }
+            },
+            // Updates and deletes give the number of affected rows, or the returned columns of each
+            SemanticQuery::Update { returning, .. } | SemanticQuery::Delete { returning, .. } => {
+                match returning {
+                    Some(returning) => SemanticType::new(SemanticTypeKind::Array(returning.result_type.clone())),
+                    None => SemanticType::new(SemanticTypeKind::Integer),
+                }
             },
-            _ => SemanticType::new(SemanticTypeKind::Void),
         }
     }
 
     pub(super) fn eval_immediate_query(&mut self, query: &QueryNode) -> Result<SemanticExpression, SemanticError> {
         let sem_query = self.eval_query(query)?;
+        Ok(self.immediate_query_expr(sem_query))
+    }
 
-        Ok(SemanticExpression {
-            sem_type: self.return_type_of_query(&sem_query),
-            kind: SemanticExpressionKind::ImmediateQuery(sem_query),
-            ownership: Ownership::Trivial,
-        })
+    // Results other than counts and rowids are created by the query, so the expression owns them
+    pub(super) fn immediate_query_expr(&self, query: SemanticQuery) -> SemanticExpression {
+        let sem_type = self.return_type_of_query(&query);
+        SemanticExpression {
+            ownership: if sem_type.can_be_owned() {
+                Ownership::Owned
+            } else {
+                Ownership::Trivial
+            },
+            sem_type,
+            kind: SemanticExpressionKind::ImmediateQuery(query),
+        }
     }
 
     pub(super) fn eval_parameterized_query(
@@ -332,6 +956,7 @@ impl SemanticGen {
             param_ids,
             captured_variables: vec![],
             return_type: SemanticType::new(SemanticTypeKind::Void),
+            generic_function_id: self.generic_function,
             body: SemanticClosureBody::dummy()
         });
 
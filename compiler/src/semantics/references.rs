@@ -0,0 +1,157 @@
+use super::*;
+
+impl SemanticGen {
+    // Index of the int or long primary key column that references to a table's rows store
+    fn referenced_key_column(&self, table_id: u32) -> Option<u32> {
+        let table = &self.tables[table_id];
+        let table_struct = &self.structs[table.struct_id];
+        table.key_column().filter(|&key_column| {
+            let key_name = &table_struct.field_order[key_column as usize];
+            matches!(table_struct.fields[key_name].kind(), SemanticTypeKind::Integer | SemanticTypeKind::Long)
+        })
+    }
+
+    fn table_of_row_struct(&self, struct_id: u32) -> Option<u32> {
+        self.tables.values()
+            .find(|table| table.struct_id == struct_id)
+            .map(|table| table.id)
+    }
+
+    // Reference columns must point at an int or long primary key of a table on the same datasource
+    pub(super) fn check_references(
+        &self,
+        table_name: &str,
+        datasource_id: u32,
+        columns: &[SemanticColumn],
+    ) -> Result<(), SemanticError> {
+        for column in columns {
+            let SemanticTypeKind::Ref(ref_table_id, ref_table_name) = column.sem_type.non_optional().kind() else {
+                continue;
+            };
+            if self.tables[ref_table_id].datasource_id != datasource_id {
+                return Err(SemanticError::CrossDatasourceReference {
+                    table_name: table_name.to_string(),
+                    column_name: column.name.clone(),
+                    referenced_table_name: ref_table_name,
+                });
+            }
+            if self.referenced_key_column(ref_table_id).is_none() {
+                return Err(SemanticError::UnreferenceableTable { table_name: ref_table_name });
+            }
+        }
+        Ok(())
+    }
+
+    // Selects the rows of a table whose column equals the given value
+    fn reference_query(
+        &self,
+        table_id: u32,
+        location: SourceLocation,
+        column_index: u32,
+        value: SemanticExpression,
+        shape: SelectShape,
+    ) -> SemanticExpression {
+        let table = &self.tables[table_id];
+        let result_type = SemanticType::new(SemanticTypeKind::NamedStruct(
+            table.struct_id,
+            self.structs[table.struct_id].name.clone()
+        ));
+        self.immediate_query_expr(SemanticQuery::Select {
+            table_id,
+            location,
+            joins: vec![],
+            is_distinct: false,
+            columns: vec![],
+            result_type,
+            shape,
+            where_clause: Some(WhereClause::Comparison {
+                column_index,
+                op: ComparisonType::Equal,
+                value: Box::new(value),
+            }),
+            group_by: vec![],
+            having: None,
+            order_by: vec![],
+            limit: None,
+            offset: None,
+        })
+    }
+
+    // `reference.fetch()` gives the row a reference points to
+    pub(super) fn eval_fetch(
+        &self,
+        table_id: u32,
+        location: SourceLocation,
+        sem_reference: SemanticExpression,
+        sem_args: &[SemanticExpression],
+    ) -> Result<SemanticExpression, SemanticError> {
+        self.check_args("Ref.fetch", sem_args, &[])?;
+        let key_column = self.referenced_key_column(table_id)
+            .expect("Referenced tables have an int or long primary key");
+        Ok(self.reference_query(table_id, location, key_column, sem_reference, SelectShape::Row))
+    }
+
+    // Methods on the rows of a table: `row.ref()` gives a reference to the row, and each
+    // table referring to it gives a method named after that table, such as `person.pets()`,
+    // selecting the rows that refer to this one
+    pub(super) fn call_row_method(
+        &self,
+        sem_row: SemanticExpression,
+        location: SourceLocation,
+        method_name: &str,
+        sem_args: &[SemanticExpression],
+    ) -> Result<SemanticExpression, SemanticError> {
+        let SemanticTypeKind::NamedStruct(struct_id, _) = sem_row.sem_type.kind() else {
+            unreachable!()
+        };
+        let undefined_method = |sem_row: SemanticExpression| SemanticError::UndefinedMethod {
+            receiver_type: sem_row.sem_type,
+            method_name: method_name.to_string(),
+        };
+        let Some(table_id) = self.table_of_row_struct(struct_id) else {
+            return Err(undefined_method(sem_row));
+        };
+        let table = &self.tables[table_id];
+
+        let referrers = self.tables.values()
+            .filter(|referrer| referrer.migration_version.is_none())
+            .filter(|referrer| format!("{}s", table_field_name(&referrer.name)) == method_name)
+            .flat_map(|referrer| {
+                let referrer_struct = &self.structs[referrer.struct_id];
+                referrer_struct.field_order.iter().enumerate()
+                    .filter(|(_, column_name)| {
+                        referrer_struct.fields[*column_name].non_optional() == SemanticTypeKind::Ref(table_id, table.name.clone())
+                    })
+                    .map(move |(i, _)| (referrer.id, i as u32))
+            })
+            .collect::<Vec<(u32, u32)>>();
+        if method_name != "ref" && referrers.is_empty() {
+            return Err(undefined_method(sem_row));
+        }
+
+        let key_column = self.referenced_key_column(table_id)
+            .ok_or_else(|| SemanticError::UnreferenceableTable { table_name: table.name.clone() })?;
+        let key_value = SemanticExpression {
+            sem_type: SemanticType::new(SemanticTypeKind::Ref(table_id, table.name.clone())),
+            kind: SemanticExpressionKind::StructField {
+                struct_expr: Box::new(sem_row),
+                index: key_column,
+            },
+            ownership: Ownership::Trivial,
+        };
+
+        if method_name == "ref" {
+            self.check_args(&format!("{}.ref", table.name), sem_args, &[])?;
+            return Ok(key_value);
+        }
+        let &[(referrer_id, column_index)] = referrers.as_slice() else {
+            return Err(SemanticError::AmbiguousBackReference {
+                table_name: self.tables[referrers[0].0].name.clone(),
+                referenced_table_name: table.name.clone(),
+                method_name: method_name.to_string(),
+            });
+        };
+        self.check_args(&format!("{}.{}", table.name, method_name), sem_args, &[])?;
+        Ok(self.reference_query(referrer_id, location, column_index, key_value, SelectShape::Rows))
+    }
+}
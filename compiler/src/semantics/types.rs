@@ -6,12 +6,21 @@ use super::*;
 pub enum SemanticTypeKind {
     Any,
     Integer,
+    Long,
+    Float,
     Bool,
     String,
     Array(SemanticType),
     NamedStruct(u32, String),
+    Enum(u32, String),
+    // Key of a row in the table with the given id and name
+    Ref(u32, String),
+    // Either null or a value of the inner type
+    Optional(SemanticType),
     AnonymousStruct(HashMap<String, SemanticType>),
     Callable(Vec<SemanticType>, SemanticType),
+    // The type parameter at the given position of the generic function or struct it appears in
+    TypeParameter(u32, String),
     Void
 }
 
@@ -20,13 +29,20 @@ impl PartialEq for SemanticTypeKind {
         match (self, other) {
             (SemanticTypeKind::Any, SemanticTypeKind::Any) => true,
             (SemanticTypeKind::Integer, SemanticTypeKind::Integer) => true,
+            (SemanticTypeKind::Long, SemanticTypeKind::Long) => true,
+            (SemanticTypeKind::Float, SemanticTypeKind::Float) => true,
             (SemanticTypeKind::Bool, SemanticTypeKind::Bool) => true,
             (SemanticTypeKind::String, SemanticTypeKind::String) => true,
             (SemanticTypeKind::Array(elem_a), SemanticTypeKind::Array(elem_b)) => elem_a == elem_b,
             (SemanticTypeKind::NamedStruct(id_a, _), SemanticTypeKind::NamedStruct(id_b, _)) => id_a == id_b,
+            (SemanticTypeKind::Enum(id_a, _), SemanticTypeKind::Enum(id_b, _)) => id_a == id_b,
+            (SemanticTypeKind::Ref(table_a, _), SemanticTypeKind::Ref(table_b, _)) => table_a == table_b,
+            (SemanticTypeKind::Optional(inner_a), SemanticTypeKind::Optional(inner_b)) => inner_a == inner_b,
             (SemanticTypeKind::AnonymousStruct(fields_a), SemanticTypeKind::AnonymousStruct(fields_b)) => fields_a == fields_b,
             (SemanticTypeKind::Callable(params_a, ret_a), SemanticTypeKind::Callable(params_b, ret_b)) =>
                 params_a == params_b && ret_a == ret_b,
+            (SemanticTypeKind::TypeParameter(index_a, name_a), SemanticTypeKind::TypeParameter(index_b, name_b)) =>
+                index_a == index_b && name_a == name_b,
             (SemanticTypeKind::Void, SemanticTypeKind::Void) => true,
             _ => false
         }
@@ -38,6 +54,7 @@ impl SemanticTypeKind {
         match self {
             SemanticTypeKind::Any => false,
             SemanticTypeKind::Array(elem_type) => elem_type.is_concrete(),
+            SemanticTypeKind::Optional(inner_type) => inner_type.is_concrete(),
             SemanticTypeKind::AnonymousStruct(_) => false,
             _ => true
         }
@@ -48,7 +65,11 @@ impl SemanticTypeKind {
             SemanticTypeKind::String => true,
             SemanticTypeKind::Array(_) => true,
             SemanticTypeKind::NamedStruct(_, _) => true,
+            SemanticTypeKind::Enum(_, _) => true,
             SemanticTypeKind::Callable(_,_) => true,
+            // May stand for an owned type in some instances of its function
+            SemanticTypeKind::TypeParameter(_, _) => true,
+            SemanticTypeKind::Optional(inner_type) => inner_type.can_be_owned(),
             _ => false
         }
     }
@@ -59,10 +80,15 @@ impl Display for SemanticTypeKind {
         match self {
             SemanticTypeKind::Any => write!(f, "any"),
             SemanticTypeKind::Integer => write!(f, "int"),
+            SemanticTypeKind::Long => write!(f, "long"),
+            SemanticTypeKind::Float => write!(f, "float"),
             SemanticTypeKind::Bool => write!(f, "bool"),
             SemanticTypeKind::String => write!(f, "str"),
             SemanticTypeKind::Array(elem_type) => write!(f, "{}[]", elem_type),
             SemanticTypeKind::NamedStruct(_, name) => write!(f, "{}", name),
+            SemanticTypeKind::Enum(_, name) => write!(f, "{}", name),
+            SemanticTypeKind::Ref(_, table_name) => write!(f, "ref {}", table_name),
+            SemanticTypeKind::Optional(inner_type) => write!(f, "{}?", inner_type),
             SemanticTypeKind::AnonymousStruct(fields) => {
                 write!(f, "{{")?;
                 for (i, (field_name, field_type)) in fields.iter().enumerate() {
@@ -83,6 +109,7 @@ impl Display for SemanticTypeKind {
                 }
                 write!(f, ") -> {}", ret_type)
             }
+            SemanticTypeKind::TypeParameter(_, name) => write!(f, "{}", name),
             SemanticTypeKind::Void => write!(f, "void"),
         }
     }
@@ -115,6 +142,43 @@ impl SemanticType {
     pub fn kind(&self) -> SemanticTypeKind {
         self.0.borrow().clone()
     }
+
+    pub fn is_optional(&self) -> bool {
+        matches!(*self.borrow(), SemanticTypeKind::Optional(_))
+    }
+
+    // Turns this type into an optional of itself, along with every value sharing it
+    pub(super) fn make_optional(&self) {
+        let inner_type = SemanticType::new(self.kind());
+        *(self.borrow_mut()) = SemanticTypeKind::Optional(inner_type);
+    }
+
+    // A copy of this type sharing none of its parts, which unifying it with other types may change
+    pub(super) fn deep_clone(&self) -> SemanticType {
+        let kind = match self.kind() {
+            SemanticTypeKind::Array(elem_type) => SemanticTypeKind::Array(elem_type.deep_clone()),
+            SemanticTypeKind::Optional(inner_type) => SemanticTypeKind::Optional(inner_type.deep_clone()),
+            SemanticTypeKind::AnonymousStruct(fields) => SemanticTypeKind::AnonymousStruct(
+                fields.iter()
+                    .map(|(field_name, field_type)| (field_name.clone(), field_type.deep_clone()))
+                    .collect()
+            ),
+            SemanticTypeKind::Callable(param_types, ret_type) => SemanticTypeKind::Callable(
+                param_types.iter().map(|param_type| param_type.deep_clone()).collect(),
+                ret_type.deep_clone()
+            ),
+            kind => kind,
+        };
+        SemanticType::new(kind)
+    }
+
+    // The type of the values held by an optional, or the type itself otherwise
+    pub fn non_optional(&self) -> SemanticType {
+        match &*self.borrow() {
+            SemanticTypeKind::Optional(inner_type) => inner_type.clone(),
+            _ => self.clone(),
+        }
+    }
 }
 
 impl Display for SemanticType {
@@ -140,22 +204,55 @@ impl PartialEq<SemanticTypeKind> for SemanticType {
 }
 
 impl SemanticGen {
-    pub fn try_get_semantic_type(&self, type_node: &TypeNode) -> Result<SemanticType, SemanticError> {
+    pub(super) fn type_exists(&self, name: &str) -> bool {
+        self.structs.contains_name(name) || self.enums.contains_name(name) || self.generic_structs.contains_key(name)
+    }
+
+    pub fn try_get_semantic_type(&mut self, type_node: &TypeNode) -> Result<SemanticType, SemanticError> {
         match type_node {
             TypeNode::Integer => Ok(SemanticType::new(SemanticTypeKind::Integer)),
+            TypeNode::Long => Ok(SemanticType::new(SemanticTypeKind::Long)),
+            TypeNode::Float => Ok(SemanticType::new(SemanticTypeKind::Float)),
             TypeNode::Bool => Ok(SemanticType::new(SemanticTypeKind::Bool)),
             TypeNode::String => Ok(SemanticType::new(SemanticTypeKind::String)),
             TypeNode::Array(elem_type_node) => {
                 let elem_type = self.try_get_semantic_type(elem_type_node)?;
                 Ok(SemanticType::new(SemanticTypeKind::Array(elem_type)))
             },
-            TypeNode::Struct(struct_name) => {
-                if let Some(named_struct) = self.structs.get_by_name(struct_name) {
-                    Ok(SemanticType::new(SemanticTypeKind::NamedStruct(named_struct.id, struct_name.clone())))
+            TypeNode::Named(struct_name, type_arg_nodes) if !type_arg_nodes.is_empty() => {
+                let struct_name = self.resolve_name(struct_name)?;
+                self.eval_generic_struct_type(&struct_name, type_arg_nodes)
+            },
+            TypeNode::Named(struct_name, _) => {
+                if let Some(index) = self.type_params.iter().position(|param| param == struct_name) {
+                    return Ok(SemanticType::new(SemanticTypeKind::TypeParameter(index as u32, struct_name.clone())));
+                }
+                let struct_name = self.resolve_name(struct_name)?;
+                if self.generic_structs.contains_key(&struct_name) {
+                    Err(SemanticError::MissingTypeArguments { name: struct_name })
+                } else if let Some(named_struct) = self.structs.get_by_name(&struct_name) {
+                    Ok(SemanticType::new(SemanticTypeKind::NamedStruct(named_struct.id, struct_name)))
+                } else if let Some(sem_enum) = self.enums.get_by_name(&struct_name) {
+                    Ok(SemanticType::new(SemanticTypeKind::Enum(sem_enum.id, struct_name)))
+                } else {
+                    Err(SemanticError::UndefinedStruct { name: struct_name })
+                }
+            },
+            TypeNode::Ref(table_name) => {
+                if let Some(table) = self.tables.get_by_name(table_name) {
+                    Ok(SemanticType::new(SemanticTypeKind::Ref(table.id, table_name.clone())))
                 } else {
-                    Err(SemanticError::UndefinedStruct { name: struct_name.to_string() })
+                    Err(SemanticError::UndefinedTable { name: table_name.to_string() })
                 }
             },
+            TypeNode::Optional(inner_type_node) => {
+                let inner_type = self.try_get_semantic_type(inner_type_node)?;
+                if inner_type.is_optional() || inner_type == SemanticTypeKind::Void {
+                    return Err(SemanticError::InvalidOptionalType { inner_type });
+                }
+                self.note_optional_type_param(&inner_type);
+                Ok(SemanticType::new(SemanticTypeKind::Optional(inner_type)))
+            },
             TypeNode::Callable(param_type_nodes, ret_type_node) => {
                 let param_types = param_type_nodes.iter()
                     .map(|param_node| self.try_get_semantic_type(param_node))
@@ -167,11 +264,18 @@ impl SemanticGen {
         }
     }
 
+    // Only b may be wrapped to take on the type of an optional a, never the other way around
     pub(super) fn try_unify(&self, a: &SemanticType, b: &SemanticType) -> bool {
-        return self.try_downcast(b, a) || self.try_downcast(a, b);
+        return self.downcast(b, a, false) || self.try_downcast(a, b);
     }
 
     pub(super) fn try_downcast(&self, target: &SemanticType, sem_type: &SemanticType) -> bool {
+        self.downcast(target, sem_type, true)
+    }
+
+    // Values are wrapped into optionals where they are stored, so a value may only take on an
+    // optional type at the top level, and not as the element of an array or a projected row
+    fn downcast(&self, target: &SemanticType, sem_type: &SemanticType, can_wrap: bool) -> bool {
         let target_kind = target.kind();
         let self_kind = sem_type.kind();
         match (target_kind, self_kind) {
@@ -179,9 +283,31 @@ impl SemanticGen {
                 *(sem_type.borrow_mut()) = other;
                 true
             },
-            (SemanticTypeKind::Array(elem_a), SemanticTypeKind::Array(elem_b)) => self.try_downcast(&elem_a, &elem_b),
+            (SemanticTypeKind::Array(elem_a), SemanticTypeKind::Array(elem_b)) => self.downcast(&elem_a, &elem_b, false),
+            (SemanticTypeKind::Optional(inner_a), SemanticTypeKind::Optional(inner_b)) => {
+                self.downcast(&inner_a, &inner_b, false)
+            },
+            (SemanticTypeKind::Optional(inner_type), _) if can_wrap => {
+                // The inner type may itself be undetermined, as for an optional inferred from null
+                self.downcast(&inner_type, sem_type, false) || self.downcast(sem_type, &inner_type, false)
+            },
             (SemanticTypeKind::NamedStruct(struct_a, _), SemanticTypeKind::NamedStruct(struct_b, _))
-                => struct_a == struct_b,
+                if struct_a == struct_b => true,
+            (SemanticTypeKind::NamedStruct(target_id, target_name), SemanticTypeKind::NamedStruct(struct_id, _)) => {
+                // Rows of a projection take on the type of any struct with the same layout
+                let target_struct = &self.structs[target_id];
+                let sem_struct = &self.structs[struct_id];
+                let mut struct_fields = sem_struct.fields.clone();
+                if sem_struct.is_synthesized
+                    && sem_struct.field_order == target_struct.field_order
+                    && self.downcast_struct(&target_struct.fields, &mut struct_fields, false)
+                {
+                    *(sem_type.borrow_mut()) = SemanticTypeKind::NamedStruct(target_id, target_name);
+                    true
+                } else {
+                    false
+                }
+            }
             (SemanticTypeKind::NamedStruct(struct_id, struct_name), SemanticTypeKind::AnonymousStruct(ref mut fields)) => {
                 let target_fields = &self.structs[struct_id].fields;
                 if self.try_downcast_struct(target_fields, fields) {
@@ -204,6 +330,15 @@ impl SemanticGen {
         &self,
         target_fields: &HashMap<String, SemanticType>,
         struct_fields: &mut HashMap<String, SemanticType>
+    ) -> bool {
+        self.downcast_struct(target_fields, struct_fields, true)
+    }
+
+    fn downcast_struct(
+        &self,
+        target_fields: &HashMap<String, SemanticType>,
+        struct_fields: &mut HashMap<String, SemanticType>,
+        can_wrap: bool,
     ) -> bool {
         if target_fields.len() != struct_fields.len() {
             return false;
@@ -211,7 +346,7 @@ impl SemanticGen {
         for (field_name, field_type) in struct_fields {
             match target_fields.get(field_name) {
                 Some(target_type) => {
-                    if !self.try_downcast(target_type, field_type) {
+                    if !self.downcast(target_type, field_type, can_wrap) {
                         return false;
                     }
                 },
@@ -60,6 +60,10 @@ impl<V> DualLookup<V> {
         self.name_to_id.contains_key(name)
     }
 
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.id_to_value.values()
+    }
+
     pub fn collect_id_value_map(self) -> HashMap<u32, V> {
         self.id_to_value
     }
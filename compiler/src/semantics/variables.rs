@@ -12,6 +12,7 @@ pub enum SemanticScopeType {
     Function,
     Closure(u32),
     Loop(u32),
+    Transaction(u32),
     Block,
 }
 
@@ -77,11 +78,112 @@ impl SemanticGen {
         Ok(declaration_node)
     }
 
-    pub(super) fn assign_variable(&mut self, name: &str, expr: &ExpressionNode) -> Result<SemanticStatement, SemanticError> {
-        let sem_expr = self.eval_expr(expr)?;
+    // Indices within the target are evaluated once, ahead of the value, into hidden variables, so
+    // compound assignments read the target through them too
+    pub(super) fn eval_assignment(
+        &mut self,
+        target: &ExpressionNode,
+        op: Option<(SourceLocation, CompoundAssignmentType)>,
+        expr: &ExpressionNode,
+    ) -> Result<Vec<SemanticStatement>, SemanticError> {
+        if let ExpressionNode::QName(name) = target {
+            let sem_expr = self.eval_assigned_value(target, op, expr)?;
+            return self.assign_variable(name, sem_expr).map(|s| vec![s]);
+        }
+
+        self.enter_scope(SemanticScopeType::Block);
+        let mut stmts = vec![];
+        let target = self.hoist_indices(target, &mut stmts)?;
+        let place = self.eval_expr(&target)?;
+        let sem_expr = self.eval_assigned_value(&target, op, expr)?;
+        self.check_place_assignable(&place)?;
+        if !self.try_downcast(&place.sem_type, &sem_expr.sem_type) {
+            return Err(SemanticError::IncompatiblePlaceAssignment {
+                place_type: place.sem_type,
+                expr_type: sem_expr.sem_type,
+            });
+        }
+
+        stmts.push(SemanticStatement::PlaceAssignment {
+            place,
+            expr: sem_expr,
+        });
+        // The hoisted indices are integers, which have nothing to drop
+        self.exit_scope(false);
+        Ok(stmts)
+    }
+
+    fn eval_assigned_value(
+        &mut self,
+        target: &ExpressionNode,
+        op: Option<(SourceLocation, CompoundAssignmentType)>,
+        expr: &ExpressionNode,
+    ) -> Result<SemanticExpression, SemanticError> {
+        match op {
+            None => self.eval_expr(expr),
+            Some((location, CompoundAssignmentType::Add)) => self.eval_add(target, location, expr),
+            Some((location, CompoundAssignmentType::Subtract)) => self.eval_subtract(target, location, expr),
+        }
+    }
+
+    // Rebuilds the target of an assignment with each of its indices replaced by a hidden variable
+    fn hoist_indices(
+        &mut self,
+        target: &ExpressionNode,
+        stmts: &mut Vec<SemanticStatement>,
+    ) -> Result<ExpressionNode, SemanticError> {
+        match target {
+            ExpressionNode::QName(name) => Ok(ExpressionNode::QName(name.clone())),
+            ExpressionNode::StructField(struct_expr, field_name) => {
+                let struct_expr = self.hoist_indices(struct_expr, stmts)?;
+                Ok(ExpressionNode::StructField(Box::new(struct_expr), field_name.clone()))
+            }
+            ExpressionNode::ArrayIndex(array_expr, index_expr) => {
+                let array_expr = self.hoist_indices(array_expr, stmts)?;
+                let sem_index = self.eval_expr(index_expr)?;
+                let index_name = format!("#index{}", stmts.len());
+                let variable_id = self.declare_bound_variable(&index_name, sem_index.sem_type.clone());
+                stmts.push(SemanticStatement::VariableDeclaration {
+                    variable_id,
+                    init_expr: sem_index,
+                });
+                Ok(ExpressionNode::ArrayIndex(Box::new(array_expr), Box::new(ExpressionNode::QName(index_name))))
+            }
+            _ => Err(SemanticError::InvalidAssignmentTarget),
+        }
+    }
+
+    // Bound and narrowed variables hold copies of the values they came from, so their fields
+    // cannot be assigned. Elements of the arrays they hold are shared, and can be
+    fn check_place_assignable(&self, place: &SemanticExpression) -> Result<(), SemanticError> {
+        match &place.kind {
+            SemanticExpressionKind::Variable(variable_id) => self.check_variable_assignable(*variable_id),
+            SemanticExpressionKind::StructField { struct_expr, .. } => self.check_place_assignable(struct_expr),
+            _ => Ok(()),
+        }
+    }
+
+    fn check_variable_assignable(&self, variable_id: u32) -> Result<(), SemanticError> {
+        let variable = &self.variables[&variable_id];
+        if self.bound_variables.contains(&variable_id) {
+            return Err(SemanticError::BoundVariableAssignment {
+                var_name: variable.name.clone(),
+            });
+        }
+        if self.narrowed_variables.contains(&variable_id) {
+            return Err(SemanticError::NarrowedVariableAssignment {
+                var_name: variable.name.clone(),
+                narrowed_type: variable.sem_type.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    fn assign_variable(&mut self, name: &str, sem_expr: SemanticExpression) -> Result<SemanticStatement, SemanticError> {
         let variable = self.get_variable(name)?;
         let var_id = variable.id;
         let var_type = variable.sem_type.clone();
+        self.check_variable_assignable(var_id)?;
 
         let compatible = self.try_downcast(&var_type, &sem_expr.sem_type);
         if !compatible {
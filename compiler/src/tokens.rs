@@ -1,23 +1,118 @@
 pub struct ProgramNode {
+    pub imports: Vec<ImportNode>,
     pub datasources: Vec<DatasourceNode>,
-    pub tables: Vec<TableNode>,
+    // Tables, structs and enums, in the order they were declared
+    pub declarations: Vec<DeclarationNode>,
+    pub migrations: Vec<MigrationNode>,
     pub functions: Vec<FunctionNode>,
 }
 
+pub enum ImportNode {
+    // A file by its path from the importing file's directory
+    File(String),
+    // A module by its path, such as models::people for models/people.ql
+    Module(Vec<String>),
+}
+
 pub struct DatasourceNode {
     pub name: String,
     pub is_readonly: bool,
+    pub creates_tables: bool,
+}
+
+pub enum DeclarationNode {
+    Table(TableNode),
+    Struct(StructNode),
+    Enum(EnumNode),
 }
 
 pub struct TableNode {
     pub name: String,
     pub datasource_name: String,
-    pub columns: Vec<TypedQNameNode>,
+    pub columns: Vec<ColumnNode>,
     pub is_readonly: bool,
 }
 
+pub struct StructNode {
+    pub is_public: bool,
+    pub name: String,
+    pub type_params: Vec<String>,
+    pub fields: Vec<TypedQNameNode>,
+}
+
+pub struct EnumNode {
+    pub is_public: bool,
+    pub name: String,
+    pub variants: Vec<VariantNode>,
+}
+
+pub struct VariantNode {
+    pub name: String,
+    pub fields: Vec<TypedQNameNode>,
+}
+
+pub struct ColumnNode {
+    pub name: String,
+    pub type_node: TypeNode,
+    pub constraints: Vec<ColumnConstraintNode>,
+}
+
+pub enum ColumnConstraintNode {
+    PrimaryKey,
+    Autoincrement,
+    Unique,
+    Check(WhereNode),
+    Default(Box<ExpressionNode>),
+}
+
+pub struct MigrationNode {
+    pub version: i32,
+    pub datasource_name: String,
+    pub steps: Vec<MigrationStepNode>,
+}
+
+pub enum MigrationStepNode {
+    SchemaChange(SchemaChangeNode),
+    Statement(StatementNode),
+}
+
+pub enum SchemaChangeNode {
+    CreateTable {
+        location: SourceLocation,
+        table_name: String,
+        columns: Vec<ColumnNode>,
+    },
+    DropTable {
+        location: SourceLocation,
+        table_name: String,
+    },
+    RenameTable {
+        location: SourceLocation,
+        table_name: String,
+        new_name: String,
+    },
+    AddColumn {
+        location: SourceLocation,
+        table_name: String,
+        column: ColumnNode,
+    },
+    DropColumn {
+        location: SourceLocation,
+        table_name: String,
+        column_name: String,
+    },
+    RenameColumn {
+        location: SourceLocation,
+        table_name: String,
+        column_name: String,
+        new_name: String,
+    },
+}
+
 pub struct FunctionNode {
+    pub is_public: bool,
     pub name: String,
+    pub type_params: Vec<String>,
     pub return_type: TypeNode,
     pub params: Vec<TypedQNameNode>,
     pub body: Vec<StatementNode>,
@@ -29,8 +124,11 @@ pub enum StatementNode {
         var_type: Option<TypeNode>, 
         init_expr: Box<ExpressionNode> 
     },
+    // The target is a variable, or a struct field or array element reached from one. Compound
+    // assignments combine the target's value with the expression
     Assignment {
-        name: String,
+        target: Box<ExpressionNode>,
+        op: Option<(SourceLocation, CompoundAssignmentType)>,
         expr: Box<ExpressionNode>,
     },
     Conditional {
@@ -42,10 +140,29 @@ pub enum StatementNode {
         body: Vec<StatementNode>,
         label: Option<String>,
     },
+    // `for element in array`, or `for index, element in array`
+    ArrayLoop {
+        index: Option<String>,
+        element: String,
+        array: Box<ExpressionNode>,
+        body: Vec<StatementNode>,
+        label: Option<String>,
+    },
+    // `for variable in start..end`, counting up through the range
+    RangeLoop {
+        variable: String,
+        range: RangeNode,
+        body: Vec<StatementNode>,
+        label: Option<String>,
+    },
     LoneExpression(Box<ExpressionNode>),
     Return(Option<Box<ExpressionNode>>),
     Break(Option<String>),
-    Continue(Option<String>)
+    Continue(Option<String>),
+    Transaction {
+        datasource_name: String,
+        body: Vec<StatementNode>,
+    },
 }
 
 pub struct TypedQNameNode {
@@ -55,10 +172,16 @@ pub struct TypedQNameNode {
 
 pub enum TypeNode {
     Integer,
+    Long,
+    Float,
     Bool,
     String,
     Array(Box<TypeNode>),
-    Struct(String),
+    // A struct or an enum, along with the type arguments of a generic struct
+    Named(String, Vec<TypeNode>),
+    // Key of a row in the named table
+    Ref(String),
+    Optional(Box<TypeNode>),
     Callable(Vec<TypeNode>, Box<TypeNode>),
     Void
 }
@@ -68,26 +191,52 @@ pub struct ConditionalBranchNode {
     pub body: Vec<StatementNode>
 }
 
+// `start..end`, or `start..=end` to include the end, with an optional step
+pub struct RangeNode {
+    pub start: Box<ExpressionNode>,
+    pub end: Box<ExpressionNode>,
+    pub inclusive: bool,
+    pub step: Option<(SourceLocation, Box<ExpressionNode>)>,
+}
+
 pub enum ExpressionNode {
     QName(String),
     StructField(Box<ExpressionNode>, String),
-    IntegerLiteral(i32),
+    // Kept wider than an int, as the minimum int is written as a negated literal out of its range
+    IntegerLiteral(i64),
+    LongLiteral(i64),
+    FloatLiteral(f64),
     BoolLiteral(bool),
     StringLiteral(String),
+    NullLiteral,
     Closure(Vec<TypedQNameNode>, Option<TypeNode>, ClosureBodyNode),
-    Add(Box<ExpressionNode>, Box<ExpressionNode>),
-    Subtract(Box<ExpressionNode>, Box<ExpressionNode>),
+    Add(Box<ExpressionNode>, SourceLocation, Box<ExpressionNode>),
+    Subtract(Box<ExpressionNode>, SourceLocation, Box<ExpressionNode>),
+    Arithmetic(Box<ExpressionNode>, SourceLocation, ArithmeticType, Box<ExpressionNode>),
+    Negate(SourceLocation, Box<ExpressionNode>),
     Comparison(Box<ExpressionNode>, Box<ExpressionNode>, ComparisonType),
+    // `expr is null`, or `expr is not null` when negated
+    IsNull(Box<ExpressionNode>, bool),
+    And(Box<ExpressionNode>, Box<ExpressionNode>),
+    Or(Box<ExpressionNode>, Box<ExpressionNode>),
+    Not(Box<ExpressionNode>),
     FunctionCall(String, Vec<Box<ExpressionNode>>),
     Struct(Option<String>, Vec<ColumnValueNode>),
     Array(Vec<Box<ExpressionNode>>),
     ArrayIndex(Box<ExpressionNode>, Box<ExpressionNode>),
-    MethodCall(Box<ExpressionNode>, String, Vec<Box<ExpressionNode>>),
+    MethodCall(Box<ExpressionNode>, SourceLocation, String, Vec<Box<ExpressionNode>>),
     ImmediateQuery(QueryNode),
     ParameterizedQuery {
         parameters: Vec<TypedQNameNode>,
         query: QueryNode
-    }
+    },
+    // A value of an enum, with its payload given in the order of the variant's fields
+    Variant {
+        enum_name: String,
+        variant_name: String,
+        args: Vec<Box<ExpressionNode>>,
+    },
+    Match(Box<ExpressionNode>, Vec<MatchArmNode>),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -100,6 +249,25 @@ pub enum ComparisonType {
     LessThanOrEqual
 }
 
+// Binary operators on numbers other than addition and subtraction, which also apply to strings
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArithmeticType {
+    Multiply,
+    Divide,
+    Modulo,
+    BitwiseAnd,
+    BitwiseOr,
+    BitwiseXor,
+    ShiftLeft,
+    ShiftRight
+}
+
+#[derive(Clone, Copy)]
+pub enum CompoundAssignmentType {
+    Add,
+    Subtract,
+}
+
 pub struct ColumnValueNode {
     pub name: String,
     pub value: Box<ExpressionNode>
@@ -110,6 +278,22 @@ pub enum ClosureBodyNode {
     Expression(Box<ExpressionNode>),
 }
 
+pub struct MatchArmNode {
+    pub pattern: PatternNode,
+    pub body: ClosureBodyNode,
+}
+
+pub enum PatternNode {
+    // Bindings are given in the order of the variant's fields, with None for those left unbound.
+    // Without them the payload is ignored altogether
+    Variant {
+        enum_name: String,
+        variant_name: String,
+        bindings: Option<Vec<Option<String>>>,
+    },
+    Wildcard,
+}
+
 // --- QUERIES ---
 
 pub enum QueryNode {
@@ -120,32 +304,123 @@ pub enum QueryNode {
 }
 
 pub struct SelectQueryNode {
+    pub location: SourceLocation,
+    pub is_distinct: bool,
+    pub columns: Vec<SelectColumnNode>,
     pub table_name: String,
+    pub joins: Vec<JoinNode>,
     pub where_clause: Option<WhereNode>,
+    pub group_by: Vec<ColumnRefNode>,
+    pub having: Option<WhereNode>,
+    pub order_by: Vec<OrderingNode>,
+    pub limit: Option<Box<ExpressionNode>>,
+    pub offset: Option<Box<ExpressionNode>>,
 }
 
-pub struct WhereNode {
+pub struct ColumnRefNode {
+    pub table_name: Option<String>,
     pub column_name: String,
-    pub value: Box<ExpressionNode>,
+}
+
+pub enum SelectColumnNode {
+    Column {
+        column: ColumnRefNode,
+        alias: Option<String>,
+    },
+    Aggregate {
+        function_name: String,
+        column: Option<ColumnRefNode>,
+        alias: Option<String>,
+    },
+}
+
+pub struct JoinNode {
+    pub is_left: bool,
+    pub table_name: String,
+    pub conditions: Vec<JoinConditionNode>,
+}
+
+pub struct JoinConditionNode {
+    pub left: ColumnRefNode,
+    pub op: ComparisonType,
+    pub right: ColumnRefNode,
+}
+
+pub struct OrderingNode {
+    pub column: ColumnRefNode,
+    pub descending: bool,
+}
+
+pub enum WhereNode {
+    Comparison {
+        column: ColumnRefNode,
+        op: ComparisonType,
+        value: Box<ExpressionNode>,
+    },
+    Like {
+        column: ColumnRefNode,
+        pattern: Box<ExpressionNode>,
+    },
+    In {
+        column: ColumnRefNode,
+        values: Box<ExpressionNode>,
+    },
+    IsNull {
+        column: ColumnRefNode,
+        negated: bool,
+    },
+    And(Box<WhereNode>, Box<WhereNode>),
+    Or(Box<WhereNode>, Box<WhereNode>),
+    Not(Box<WhereNode>),
 }
 
 pub struct InsertQueryNode {
+    pub location: SourceLocation,
     pub table_name: String,
     pub data_expr: Box<ExpressionNode>,
+    pub returning: Vec<SelectColumnNode>,
 }
 
 pub struct DeleteQueryNode {
+    pub location: SourceLocation,
     pub table_name: String,
     pub where_clause: Option<WhereNode>,
+    pub returning: Vec<SelectColumnNode>,
 }
 
 pub struct UpdateQueryNode {
+    pub location: SourceLocation,
     pub table_name: String,
     pub assignments: Vec<UpdateAssignmentNode>,
     pub where_clause: Option<WhereNode>,
+    pub returning: Vec<SelectColumnNode>,
 }
 
 pub struct UpdateAssignmentNode {
     pub column_name: String,
     pub value_expr: Box<ExpressionNode>,
 }
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SourceLocation {
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SourceLocation {
+    pub fn from_offset(source: &str, offset: usize) -> Self {
+        let preceding = &source[..offset];
+        let line = preceding.matches('\n').count() + 1;
+        let line_start = preceding.rfind('\n').map_or(0, |newline| newline + 1);
+        SourceLocation {
+            line: line as u32,
+            column: (preceding[line_start..].chars().count() + 1) as u32,
+        }
+    }
+}
+
+impl std::fmt::Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
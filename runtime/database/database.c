@@ -7,6 +7,8 @@
 #include "../qlstring.h"
 #include "../array.h"
 #include "database.h"
+#include "definitions.h"
+#include "sql_buffer.h"
 
 void __ql__init_dbs_from_args(int argc, char** argv, int num_dbs, sqlite3*** db_globals) {
     argc--; argv++;
@@ -24,10 +26,188 @@ void __ql__init_dbs_from_args(int argc, char** argv, int num_dbs, sqlite3*** db_
             }
             exit(1);
         }
+        // Enforce the foreign keys of reference columns, which SQLite leaves off by default
+        sqlite3_exec(db, "PRAGMA foreign_keys = ON;", NULL, NULL, NULL);
         *(db_globals[i]) = db;
     }
 }
 
+// Optional columns are nullable columns of their inner type
+static QLType __ql__column_type(QLTypeInfo* type_info) {
+    if (type_info->type == TYPE_OPTIONAL) {
+        return type_info->fields[1].type_info->type;
+    }
+    return type_info->type;
+}
+
+static const char* __ql__column_sql_type(QLType type) {
+    switch (type) {
+        case TYPE_INT:
+        case TYPE_LONG:
+        case TYPE_BOOL:
+            return "INTEGER";
+        case TYPE_FLOAT:
+            return "REAL";
+        case TYPE_STRING:
+        case TYPE_ENUM:
+            return "TEXT";
+        default:
+            fprintf(stderr, "Unsupported column type %d\n", type);
+            exit(1);
+    }
+}
+
+void __ql__create_table(
+    sqlite3* db,
+    char* table_name,
+    char** column_names,
+    QLTypeInfo* struct_type_info,
+    char** column_constraints
+) {
+    SqlBuffer sql = __ql__SqlBuffer_new();
+    __ql__SqlBuffer_write(&sql, "CREATE TABLE IF NOT EXISTS %s (", table_name);
+    for (unsigned int i = 0; i < struct_type_info->num_fields; i++) {
+        QLTypeInfo* type_info = struct_type_info->fields[i].type_info;
+        // Only optional columns may hold NULL, which rows decode as absent
+        __ql__SqlBuffer_write(
+            &sql,
+            i == 0 ? "%s %s%s%s" : ", %s %s%s%s",
+            column_names[i],
+            __ql__column_sql_type(__ql__column_type(type_info)),
+            type_info->type == TYPE_OPTIONAL ? "" : " NOT NULL",
+            column_constraints[i]
+        );
+    }
+    __ql__SqlBuffer_write(&sql, ");");
+
+    char* error_message;
+    if (sqlite3_exec(db, sql.data, NULL, NULL, &error_message) != SQLITE_OK) {
+        fprintf(stderr, "Cannot create table %s: %s\n    %s\n", table_name, error_message, sql.data);
+        sqlite3_free(error_message);
+        exit(1);
+    }
+    __ql__SqlBuffer_free(&sql);
+}
+
+typedef enum {
+    AFFINITY_INTEGER,
+    AFFINITY_TEXT,
+    AFFINITY_BLOB,
+    AFFINITY_REAL,
+    AFFINITY_NUMERIC
+} SQLAffinity;
+
+// Determines a declared column type's affinity, following SQLite's own rules
+static SQLAffinity __ql__sql_type_affinity(const char* sql_type) {
+    if (sqlite3_strlike("%INT%", sql_type, 0) == 0) {
+        return AFFINITY_INTEGER;
+    }
+    if (sqlite3_strlike("%CHAR%", sql_type, 0) == 0
+        || sqlite3_strlike("%CLOB%", sql_type, 0) == 0
+        || sqlite3_strlike("%TEXT%", sql_type, 0) == 0) {
+        return AFFINITY_TEXT;
+    }
+    if (sql_type[0] == '\0' || sqlite3_strlike("%BLOB%", sql_type, 0) == 0) {
+        return AFFINITY_BLOB;
+    }
+    if (sqlite3_strlike("%REAL%", sql_type, 0) == 0
+        || sqlite3_strlike("%FLOA%", sql_type, 0) == 0
+        || sqlite3_strlike("%DOUB%", sql_type, 0) == 0) {
+        return AFFINITY_REAL;
+    }
+    return AFFINITY_NUMERIC;
+}
+
+// Whether a live column's declared SQL type stores values of a ql column type unchanged
+static bool __ql__is_compatible_sql_type(const char* sql_type, QLType type) {
+    SQLAffinity affinity = __ql__sql_type_affinity(sql_type);
+    // Untyped and BLOB columns store any value as given
+    if (affinity == AFFINITY_BLOB) {
+        return true;
+    }
+    switch (type) {
+        case TYPE_INT:
+        case TYPE_LONG:
+        case TYPE_BOOL:
+            return affinity == AFFINITY_INTEGER || affinity == AFFINITY_NUMERIC;
+        case TYPE_FLOAT:
+            return affinity == AFFINITY_REAL || affinity == AFFINITY_NUMERIC;
+        case TYPE_STRING:
+        case TYPE_ENUM:
+            return affinity == AFFINITY_TEXT;
+        default:
+            return false;
+    }
+}
+
+// Prints the heading of a table's schema diff before its first mismatched column
+static void __ql__report_mismatched_table(const char* table_name, bool* matches) {
+    if (*matches) {
+        fprintf(stderr, "Table %s does not match its declaration:\n", table_name);
+        *matches = false;
+    }
+}
+
+// Compares a table's live columns to its declaration position by position, as selects decode by position
+bool __ql__verify_table(sqlite3* db, char* table_name, char** column_names, QLTypeInfo* struct_type_info) {
+    SqlBuffer sql = __ql__SqlBuffer_new();
+    __ql__SqlBuffer_write(&sql, "PRAGMA table_info(%s);", table_name);
+    sqlite3_stmt* stmt;
+    int prepare_result = sqlite3_prepare_v2(db, sql.data, -1, &stmt, NULL);
+    __ql__SqlBuffer_free(&sql);
+    if (prepare_result != SQLITE_OK) {
+        fprintf(stderr, "Cannot read schema of table %s: %s\n", table_name, sqlite3_errmsg(db));
+        return false;
+    }
+
+    bool matches = true;
+    unsigned int i = 0;
+    int result;
+    while ((result = sqlite3_step(stmt)) == SQLITE_ROW) {
+        const char* live_name = (const char*)sqlite3_column_text(stmt, 1);
+        const char* live_type = (const char*)sqlite3_column_text(stmt, 2);
+        if (i >= struct_type_info->num_fields) {
+            __ql__report_mismatched_table(table_name, &matches);
+            fprintf(stderr, "    column %u: undeclared column %s %s\n", i + 1, live_name, live_type);
+            i++;
+            continue;
+        }
+        QLType type = __ql__column_type(struct_type_info->fields[i].type_info);
+        bool same_name = sqlite3_stricmp(live_name, column_names[i]) == 0;
+        if (!same_name || !__ql__is_compatible_sql_type(live_type, type)) {
+            __ql__report_mismatched_table(table_name, &matches);
+            fprintf(stderr, "    column %u: declared %s %s, found %s %s\n",
+                i + 1, column_names[i], __ql__column_sql_type(type), live_name, live_type);
+        }
+        i++;
+    }
+    if (result != SQLITE_DONE) {
+        fprintf(stderr, "Cannot read schema of table %s: %s\n", table_name, sqlite3_errmsg(db));
+        sqlite3_finalize(stmt);
+        return false;
+    }
+    sqlite3_finalize(stmt);
+
+    if (i == 0) {
+        fprintf(stderr, "Table %s does not exist in its database\n", table_name);
+        return false;
+    }
+    for (; i < struct_type_info->num_fields; i++) {
+        __ql__report_mismatched_table(table_name, &matches);
+        QLType type = __ql__column_type(struct_type_info->fields[i].type_info);
+        fprintf(stderr, "    column %u: declared %s %s, found no column\n",
+            i + 1, column_names[i], __ql__column_sql_type(type));
+    }
+    return matches;
+}
+
+void __ql__check_schema(bool schema_matches, int num_dbs, sqlite3*** db_globals) {
+    if (!schema_matches) {
+        __ql__close_dbs(num_dbs, db_globals);
+        exit(SCHEMA_MISMATCH_STATUS);
+    }
+}
+
 void __ql__close_dbs(int num_dbs, sqlite3*** db_globals) {
     for (int i = 0; i < num_dbs; i++) {
         sqlite3_close(*(db_globals[i]));
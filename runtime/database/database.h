@@ -2,11 +2,21 @@
 #define RUNTIME_DATABASE_H
 
 #include <stdbool.h>
+#include "../metadata.h"
 
 typedef struct sqlite3 sqlite3;
 typedef struct sqlite3_stmt sqlite3_stmt;
 
 void __ql__init_dbs_from_args(int argc, char** argv, int num_dbs, sqlite3*** db_globals);
 void __ql__close_dbs(int num_dbs, sqlite3*** db_globals);
+void __ql__create_table(
+    sqlite3* db,
+    char* table_name,
+    char** column_names,
+    QLTypeInfo* struct_type_info,
+    char** column_constraints
+);
+bool __ql__verify_table(sqlite3* db, char* table_name, char** column_names, QLTypeInfo* struct_type_info);
+void __ql__check_schema(bool schema_matches, int num_dbs, sqlite3*** db_globals);
 
 #endif
@@ -1,11 +1,114 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
 #include <stdbool.h>
+#include <math.h>
 #include <sqlite3.h>
 #include "../metadata.h"
 #include "../qlstring.h"
+#include "../array.h"
 #include "definitions.h"
 
-void __ql__bind_value(sqlite3_stmt* stmt, unsigned int index, QLType value_type, void* value) {
-    switch (value_type) {
+typedef struct {
+    char* data;
+    size_t length;
+    size_t capacity;
+} JsonBuffer;
+
+static void __ql__JsonBuffer_write(JsonBuffer* buffer, const char* text, size_t length) {
+    while (buffer->length + length + 1 > buffer->capacity) {
+        buffer->capacity *= 2;
+        buffer->data = realloc(buffer->data, buffer->capacity);
+    }
+    memcpy(buffer->data + buffer->length, text, length);
+    buffer->length += length;
+    buffer->data[buffer->length] = '\0';
+}
+
+static void __ql__JsonBuffer_write_string(JsonBuffer* buffer, QLString* str) {
+    __ql__JsonBuffer_write(buffer, "\"", 1);
+    for (unsigned int i = 0; i < str->length; i++) {
+        char c = str->raw_string[i];
+        if (c == '"' || c == '\\') {
+            char escaped[2] = { '\\', c };
+            __ql__JsonBuffer_write(buffer, escaped, 2);
+        } else if ((unsigned char)c < 0x20) {
+            char escaped[8];
+            int length = sprintf(escaped, "\\u%04x", c);
+            __ql__JsonBuffer_write(buffer, escaped, length);
+        } else {
+            __ql__JsonBuffer_write(buffer, &c, 1);
+        }
+    }
+    __ql__JsonBuffer_write(buffer, "\"", 1);
+}
+
+static void __ql__JsonBuffer_write_value(JsonBuffer* buffer, QLTypeInfo* type_info, void* value) {
+    char number[32];
+    switch (type_info->type) {
+        case TYPE_STRING:
+            __ql__JsonBuffer_write_string(buffer, *(QLString**)value);
+            break;
+        case TYPE_INT: {
+            int length = sprintf(number, "%d", *(int*)value);
+            __ql__JsonBuffer_write(buffer, number, length);
+            break;
+        }
+        case TYPE_LONG: {
+            int length = sprintf(number, "%lld", *(long long*)value);
+            __ql__JsonBuffer_write(buffer, number, length);
+            break;
+        }
+        case TYPE_FLOAT: {
+            // JSON has no infinities or NaN. SQLite reads a number too large for a double as
+            // infinity, and NaN is stored as NULL
+            double float_value = *(double*)value;
+            int length = isnan(float_value) ? sprintf(number, "null")
+                : isinf(float_value) ? sprintf(number, float_value > 0 ? "9e999" : "-9e999")
+                : sprintf(number, "%.17g", float_value);
+            __ql__JsonBuffer_write(buffer, number, length);
+            break;
+        }
+        case TYPE_BOOL:
+            __ql__JsonBuffer_write(buffer, *(bool*)value ? "1" : "0", 1);
+            break;
+        case TYPE_OPTIONAL:
+            // An absent optional holds false in its first field
+            if (*(bool*)value) {
+                StructField inner = type_info->fields[1];
+                __ql__JsonBuffer_write_value(buffer, inner.type_info, (char*)value + inner.offset);
+            } else {
+                __ql__JsonBuffer_write(buffer, "null", 4);
+            }
+            break;
+        case TYPE_ENUM: {
+            const char* name = type_info->variant_names[*(unsigned int*)value];
+            __ql__JsonBuffer_write(buffer, "\"", 1);
+            __ql__JsonBuffer_write(buffer, name, strlen(name));
+            __ql__JsonBuffer_write(buffer, "\"", 1);
+            break;
+        }
+        default:
+            break;
+    }
+}
+
+// Encodes an array of primitives as JSON text, to be expanded by json_each
+static char* __ql__array_to_json(QLArray* array) {
+    JsonBuffer buffer = { malloc(64), 0, 64 };
+    __ql__JsonBuffer_write(&buffer, "[", 1);
+    for (unsigned int i = 0; i < array->num_elems; i++) {
+        if (i > 0) {
+            __ql__JsonBuffer_write(&buffer, ",", 1);
+        }
+        __ql__JsonBuffer_write_value(&buffer, array->type_info, __ql__QLArray_index(array, i));
+    }
+    __ql__JsonBuffer_write(&buffer, "]", 1);
+    return buffer.data;
+}
+
+void __ql__bind_value(sqlite3_stmt* stmt, unsigned int index, QLTypeInfo* type_info, void* value) {
+    switch (type_info->type) {
         case TYPE_STRING: {
             QLString* str = *(QLString**)value;
             sqlite3_bind_text(stmt, index, str->raw_string, str->length, SQLITE_TRANSIENT);
@@ -15,12 +118,200 @@ void __ql__bind_value(sqlite3_stmt* stmt, unsigned int index, QLType value_type,
             sqlite3_bind_int(stmt, index, *(int*)value);
             break;
         }
+        case TYPE_LONG: {
+            sqlite3_bind_int64(stmt, index, *(long long*)value);
+            break;
+        }
+        case TYPE_FLOAT: {
+            sqlite3_bind_double(stmt, index, *(double*)value);
+            break;
+        }
         case TYPE_BOOL: {
             int as_int = *(bool*)value ? 1 : 0;
             sqlite3_bind_int(stmt, index, as_int);
             break;
         }
+        case TYPE_ARRAY: {
+            char* json = __ql__array_to_json(*(QLArray**)value);
+            sqlite3_bind_text(stmt, index, json, -1, free);
+            break;
+        }
+        case TYPE_OPTIONAL: {
+            if (*(bool*)value) {
+                StructField inner = type_info->fields[1];
+                __ql__bind_value(stmt, index, inner.type_info, (char*)value + inner.offset);
+            } else {
+                sqlite3_bind_null(stmt, index);
+            }
+            break;
+        }
+        case TYPE_ENUM: {
+            // Enums stored in columns have no payloads, so the variant's name is all there is to store
+            const char* name = type_info->variant_names[*(unsigned int*)value];
+            sqlite3_bind_text(stmt, index, name, -1, SQLITE_STATIC);
+            break;
+        }
+        default:
+            break;
+    }
+}
+
+static void __ql__abort_query(sqlite3* db) {
+    if (!sqlite3_get_autocommit(db)) {
+        sqlite3_exec(db, "ROLLBACK;", NULL, NULL, NULL);
+    }
+    exit(QUERY_FAILURE_STATUS);
+}
+
+// Number of result columns a value is decoded from, which is one per field for the row of a table
+static int __ql__num_columns(QLTypeInfo* type_info) {
+    switch (type_info->type) {
+        case TYPE_STRUCT: {
+            int num_columns = 0;
+            for (unsigned int i = 0; i < type_info->num_fields; i++) {
+                num_columns += __ql__num_columns(type_info->fields[i].type_info);
+            }
+            return num_columns;
+        }
+        case TYPE_OPTIONAL:
+            return __ql__num_columns(type_info->fields[1].type_info);
+        default:
+            return 1;
+    }
+}
+
+static bool __ql__columns_are_null(sqlite3_stmt* stmt, int index, int num_columns) {
+    for (int i = index; i < index + num_columns; i++) {
+        if (sqlite3_column_type(stmt, i) != SQLITE_NULL) {
+            return false;
+        }
+    }
+    return true;
+}
+
+int __ql__decode_column(sqlite3_stmt* stmt, int index, StructField field, void* struct_ptr) {
+    void* field_ptr = (char*)struct_ptr + field.offset;
+    switch (field.type_info->type) {
+        case TYPE_STRUCT: {
+            // Rows of a join hold one struct per table, each filled from its own columns
+            return __ql__decode_struct(stmt, index, field.type_info, field_ptr);
+        }
+        case TYPE_OPTIONAL: {
+            // A table missing from a left join leaves every one of its columns null
+            int num_columns = __ql__num_columns(field.type_info);
+            if (__ql__columns_are_null(stmt, index, num_columns)) {
+                memset(field_ptr, 0, field.type_info->size);
+                return index + num_columns;
+            }
+            *(bool*)field_ptr = true;
+            return __ql__decode_column(stmt, index, field.type_info->fields[1], field_ptr);
+        }
+        case TYPE_STRING: {
+            const unsigned char* text = sqlite3_column_text(stmt, index);
+            unsigned int length = sqlite3_column_bytes(stmt, index);
+            QLString* val = __ql__QLString_new(malloc(length), length, false);
+            memcpy(val->raw_string, text, length);
+            *(QLString**)field_ptr = val;
+            break;
+        }
+        case TYPE_INT: {
+            *(int*)field_ptr = sqlite3_column_int(stmt, index);
+            break;
+        }
+        case TYPE_LONG: {
+            *(long long*)field_ptr = sqlite3_column_int64(stmt, index);
+            break;
+        }
+        case TYPE_FLOAT: {
+            *(double*)field_ptr = sqlite3_column_double(stmt, index);
+            break;
+        }
+        case TYPE_BOOL: {
+            *(bool*)field_ptr = sqlite3_column_int(stmt, index) != 0;
+            break;
+        }
+        case TYPE_ENUM: {
+            // The text must name one of the enum's variants, as it may have been written outside the program
+            const char* text = (const char*)sqlite3_column_text(stmt, index);
+            unsigned int num_variants = field.type_info->num_fields;
+            unsigned int tag = 0;
+            while (tag < num_variants && (text == NULL || strcmp(text, field.type_info->variant_names[tag]) != 0)) {
+                tag++;
+            }
+            if (tag == num_variants) {
+                fprintf(
+                    stderr,
+                    "Column %s holds %s, which is not a variant of its enum\n    %s\n",
+                    sqlite3_column_name(stmt, index),
+                    text == NULL ? "null" : text,
+                    sqlite3_sql(stmt)
+                );
+                __ql__abort_query(sqlite3_db_handle(stmt));
+            }
+            memset(field_ptr, 0, field.type_info->size);
+            *(unsigned int*)field_ptr = tag;
+            break;
+        }
         default:
             break;
     }
-}
\ No newline at end of file
+    return index + 1;
+}
+
+int __ql__decode_struct(sqlite3_stmt* stmt, int index, QLTypeInfo* type_info, void* struct_ptr) {
+    for (unsigned int i = 0; i < type_info->num_fields; i++) {
+        index = __ql__decode_column(stmt, index, type_info->fields[i], struct_ptr);
+    }
+    return index;
+}
+
+static void __ql__fail_query(sqlite3* db, const char* sql, QueryOrigin origin) {
+    fprintf(
+        stderr,
+        "Query on table %s at %s failed: %s\n    %s\n",
+        origin.table_name,
+        origin.location,
+        sqlite3_errmsg(db),
+        sql
+    );
+    __ql__abort_query(db);
+}
+
+void __ql__check_prepare(sqlite3* db, int result, const char* sql, QueryOrigin origin) {
+    if (result != SQLITE_OK) {
+        __ql__fail_query(db, sql, origin);
+    }
+}
+
+void __ql__check_step(sqlite3_stmt* stmt, int result, QueryOrigin origin) {
+    if (result != SQLITE_ROW && result != SQLITE_DONE) {
+        __ql__fail_query(sqlite3_db_handle(stmt), sqlite3_sql(stmt), origin);
+    }
+}
+
+void __ql__check_row_found(sqlite3_stmt* stmt, int result, QueryOrigin origin) {
+    __ql__check_step(stmt, result, origin);
+    if (result == SQLITE_DONE) {
+        fprintf(
+            stderr,
+            "Query on table %s at %s found no row for the reference\n    %s\n",
+            origin.table_name,
+            origin.location,
+            sqlite3_sql(stmt)
+        );
+        __ql__abort_query(sqlite3_db_handle(stmt));
+    }
+}
+
+QLArray* __ql__collect_rows(sqlite3_stmt* stmt, QLTypeInfo* row_type_info, QueryOrigin origin) {
+    QLArray* results = __ql__QLArray_new(NULL, 0, row_type_info);
+    void* row_ptr = malloc(row_type_info->size);
+    int result;
+    while ((result = sqlite3_step(stmt)) == SQLITE_ROW) {
+        __ql__decode_struct(stmt, 0, row_type_info, row_ptr);
+        __ql__QLArray_append(results, row_ptr);
+    }
+    __ql__check_step(stmt, result, origin);
+    free(row_ptr);
+    return results;
+}
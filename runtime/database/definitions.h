@@ -2,9 +2,33 @@
 #define RUNTIME_DB_DEFINITIONS
 
 #include "../metadata.h"
+#include "../array.h"
+#include "database.h"
 
-#define MAX_SQL_LENGTH 1024
+#define QUERY_FAILURE_STATUS 3
+#define SCHEMA_MISMATCH_STATUS 4
+#define MIGRATION_FAILURE_STATUS 5
 
-void __ql__bind_value(sqlite3_stmt* stmt, unsigned int index, QLType value_type, void* value);
+// Where a query was written, reported along with any failure
+typedef struct {
+    char* table_name;
+    char* location;
+} QueryOrigin;
+
+void __ql__bind_value(sqlite3_stmt* stmt, unsigned int index, QLTypeInfo* type_info, void* value);
+
+// Decodes the result columns starting at index into a field or a whole struct,
+// returning the index of the first column left undecoded
+int __ql__decode_column(sqlite3_stmt* stmt, int index, StructField field, void* struct_ptr);
+int __ql__decode_struct(sqlite3_stmt* stmt, int index, QLTypeInfo* type_info, void* struct_ptr);
+
+// Exit the program when a statement failed to prepare or run, rolling back any open transaction
+void __ql__check_prepare(sqlite3* db, int result, const char* sql, QueryOrigin origin);
+void __ql__check_step(sqlite3_stmt* stmt, int result, QueryOrigin origin);
+// Also exits when a statement expected to find a referenced row found none
+void __ql__check_row_found(sqlite3_stmt* stmt, int result, QueryOrigin origin);
+
+// Steps through every result row of a statement, decoding each into an array element
+QLArray* __ql__collect_rows(sqlite3_stmt* stmt, QLTypeInfo* row_type_info, QueryOrigin origin);
 
 #endif
\ No newline at end of file
@@ -4,44 +4,74 @@
 #include <sqlite3.h>
 #include "../metadata.h"
 #include "../qlstring.h"
+#include "../array.h"
 #include "definitions.h"
 #include "delete_query.h"
 
 DeletePlan* __ql__DeletePlan_new(char* table_name) {
     DeletePlan* plan = malloc(sizeof(DeletePlan));
     plan->table_name = table_name;
-    plan->has_where_clause = false;
+    plan->where_clause = NULL;
+    plan->returning = NULL;
     return plan;
 }
 
-void __ql__DeletePlan_set_where(DeletePlan* plan, char* column_name) {
-    plan->has_where_clause = true;
-    plan->where_column = column_name;
+void __ql__DeletePlan_set_where(DeletePlan* plan, WhereNode* where_clause) {
+    plan->where_clause = where_clause;
 }
 
-PreparedDelete* __ql__DeletePlan_prepare(sqlite3* db, DeletePlan* plan) {
+void __ql__DeletePlan_set_returning(DeletePlan* plan, ReturningClause* returning) {
+    plan->returning = returning;
+}
+
+PreparedDelete* __ql__DeletePlan_prepare(sqlite3* db, DeletePlan* plan, char* location) {
     PreparedDelete* prepared_delete = malloc(sizeof(PreparedDelete));
-    
-    char sql[MAX_SQL_LENGTH];
-    if (plan->has_where_clause) {
-        sprintf(sql, "DELETE FROM %s WHERE %s = ?;", plan->table_name, plan->where_column);
-    } else {
-        sprintf(sql, "DELETE FROM %s;", plan->table_name);
+    prepared_delete->origin = (QueryOrigin){ plan->table_name, location };
+    prepared_delete->returning_type_info = NULL;
+
+    SqlBuffer sql = __ql__SqlBuffer_new();
+    __ql__SqlBuffer_write(&sql, "DELETE FROM %s", plan->table_name);
+    if (plan->where_clause != NULL) {
+        unsigned int param_index = 1;
+        __ql__SqlBuffer_write(&sql, " WHERE ");
+        __ql__WhereNode_write_sql(&sql, plan->where_clause, &param_index);
+        __ql__WhereNode_free(plan->where_clause);
+    }
+    if (plan->returning != NULL) {
+        prepared_delete->returning_type_info = plan->returning->struct_type_info;
+        __ql__ReturningClause_write_sql(&sql, plan->returning);
+        __ql__ReturningClause_free(plan->returning);
     }
+    __ql__SqlBuffer_write(&sql, ";");
 
-    sqlite3_prepare_v2(db, sql, -1, &prepared_delete->stmt, NULL);
+    int result = sqlite3_prepare_v2(db, sql.data, -1, &prepared_delete->stmt, NULL);
+    __ql__check_prepare(db, result, sql.data, prepared_delete->origin);
+    __ql__SqlBuffer_free(&sql);
     free(plan);
     return prepared_delete;
 }
 
 
-void __ql__PreparedDelete_bind_where(PreparedDelete* prepared_delete, QLType value_type, void* value) {
-    __ql__bind_value(prepared_delete->stmt, 1, value_type, value);
+void __ql__PreparedDelete_bind_where(
+    PreparedDelete* prepared_delete,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+) {
+    __ql__bind_value(prepared_delete->stmt, index + 1, value_type_info, value);
+}
+
+// Returns the number of deleted rows
+int __ql__PreparedDelete_exec(PreparedDelete* prepared_delete) {
+    __ql__check_step(prepared_delete->stmt, sqlite3_step(prepared_delete->stmt), prepared_delete->origin);
+    sqlite3_reset(prepared_delete->stmt);
+    return sqlite3_changes(sqlite3_db_handle(prepared_delete->stmt));
 }
 
-void __ql__PreparedDelete_exec(PreparedDelete* prepared_delete) {
-    sqlite3_step(prepared_delete->stmt);
+QLArray* __ql__PreparedDelete_exec_returning(PreparedDelete* prepared_delete) {
+    QLArray* results = __ql__collect_rows(prepared_delete->stmt, prepared_delete->returning_type_info, prepared_delete->origin);
     sqlite3_reset(prepared_delete->stmt);
+    return results;
 }
 
 void __ql__PreparedDelete_finalize(PreparedDelete* prepared_delete) {
@@ -2,23 +2,35 @@
 #define RUNTIME_DELETE_QUERY_H
 
 #include <stdbool.h>
+#include "definitions.h"
+#include "where_clause.h"
+#include "returning.h"
 
 typedef struct {
     char* table_name;
-    bool has_where_clause;
-    char* where_column;
+    WhereNode* where_clause;
+    ReturningClause* returning;
 } DeletePlan;
 
 typedef struct {
     sqlite3_stmt* stmt;
+    QueryOrigin origin;
+    QLTypeInfo* returning_type_info;
 } PreparedDelete;
 
 DeletePlan* __ql__DeletePlan_new(char* table_name);
-void __ql__DeletePlan_set_where(DeletePlan* plan, char* column_name);
-PreparedDelete* __ql__DeletePlan_prepare(sqlite3* db, DeletePlan* plan);
+void __ql__DeletePlan_set_where(DeletePlan* plan, WhereNode* where_clause);
+void __ql__DeletePlan_set_returning(DeletePlan* plan, ReturningClause* returning);
+PreparedDelete* __ql__DeletePlan_prepare(sqlite3* db, DeletePlan* plan, char* location);
 
-void __ql__PreparedDelete_bind_where(PreparedDelete* prepared_delete, QLType value_type, void* value);
-void __ql__PreparedDelete_exec(PreparedDelete* prepared_delete);
+void __ql__PreparedDelete_bind_where(
+    PreparedDelete* prepared_delete,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+);
+int __ql__PreparedDelete_exec(PreparedDelete* prepared_delete);
+QLArray* __ql__PreparedDelete_exec_returning(PreparedDelete* prepared_delete);
 void __ql__PreparedDelete_finalize(PreparedDelete* prepared_delete);
 
 #endif
@@ -5,51 +5,89 @@
 #include "../metadata.h"
 #include "../qlstring.h"
 #include "../array.h"
+#include "../primitives.h"
 #include "definitions.h"
 #include "insert_query.h"
 
-InsertPlan* __ql__InsertPlan_new(char* table_name, QLTypeInfo* struct_type_info) {
+InsertPlan* __ql__InsertPlan_new(char* table_name, QLTypeInfo* struct_type_info, char** column_names) {
     InsertPlan* plan = malloc(sizeof(InsertPlan));
     plan->table_name = table_name;
     plan->struct_type_info = struct_type_info;
+    plan->column_names = column_names;
+    plan->returning = NULL;
     return plan;
 }
 
-PreparedInsert* __ql__InsertPlan_prepare(sqlite3* db, InsertPlan* plan) {
+void __ql__InsertPlan_set_returning(InsertPlan* plan, ReturningClause* returning) {
+    plan->returning = returning;
+}
+
+PreparedInsert* __ql__InsertPlan_prepare(sqlite3* db, InsertPlan* plan, char* location) {
     PreparedInsert* prepared_insert = malloc(sizeof(PreparedInsert));
+    prepared_insert->origin = (QueryOrigin){ plan->table_name, location };
     prepared_insert->struct_type_info = plan->struct_type_info;
+    prepared_insert->has_returning = plan->returning != NULL;
+    prepared_insert->result_type_info = &__ql__long_type_info;
 
-    char sql[MAX_SQL_LENGTH];
-    char* writer = sql;
-    writer += sprintf(writer, "INSERT INTO %s VALUES (?1", plan->table_name);
-
+    SqlBuffer sql = __ql__SqlBuffer_new();
     unsigned int n_fields = plan->struct_type_info->num_fields;
-    for (unsigned int i = 1; i < n_fields; i++) {
-        writer += sprintf(writer, ", ?%d", i + 1);
+    if (n_fields == 0) {
+        // Rows of tables whose columns all have defaults may be inserted without any values
+        __ql__SqlBuffer_write(&sql, "INSERT INTO %s DEFAULT VALUES", plan->table_name);
+    } else {
+        __ql__SqlBuffer_write(&sql, "INSERT INTO %s (%s", plan->table_name, plan->column_names[0]);
+        for (unsigned int i = 1; i < n_fields; i++) {
+            __ql__SqlBuffer_write(&sql, ", %s", plan->column_names[i]);
+        }
+        __ql__SqlBuffer_write(&sql, ") VALUES (?1");
+        for (unsigned int i = 1; i < n_fields; i++) {
+            __ql__SqlBuffer_write(&sql, ", ?%d", i + 1);
+        }
+        __ql__SqlBuffer_write(&sql, ")");
     }
-    writer += sprintf(writer, ");");
+    if (plan->returning != NULL) {
+        prepared_insert->result_type_info = plan->returning->struct_type_info;
+        __ql__ReturningClause_write_sql(&sql, plan->returning);
+        __ql__ReturningClause_free(plan->returning);
+    }
+    __ql__SqlBuffer_write(&sql, ";");
 
-    sqlite3_prepare_v2(db, sql, -1, &prepared_insert->stmt, NULL);
+    int result = sqlite3_prepare_v2(db, sql.data, -1, &prepared_insert->stmt, NULL);
+    __ql__check_prepare(db, result, sql.data, prepared_insert->origin);
+    __ql__SqlBuffer_free(&sql);
     free(plan);
     return prepared_insert;
 }
 
-void __ql__PreparedInsert_exec_row(PreparedInsert* prepared_insert, void* row) {
+// Inserts a single row, writing either its returned columns or its rowid into result
+void __ql__PreparedInsert_exec_row(PreparedInsert* prepared_insert, void* row, void* result) {
     unsigned int n_fields = prepared_insert->struct_type_info->num_fields;
     for (unsigned int i = 0; i < n_fields; i++) {
         StructField field = prepared_insert->struct_type_info->fields[i];
         void* field_ptr = (char*)row + field.offset;
-        __ql__bind_value(prepared_insert->stmt, i + 1, field.type_info->type, field_ptr);
+        __ql__bind_value(prepared_insert->stmt, i + 1, field.type_info, field_ptr);
+    }
+    int step_result = sqlite3_step(prepared_insert->stmt);
+    __ql__check_step(prepared_insert->stmt, step_result, prepared_insert->origin);
+    if (prepared_insert->has_returning) {
+        __ql__decode_struct(prepared_insert->stmt, 0, prepared_insert->result_type_info, result);
+    } else {
+        sqlite3* db = sqlite3_db_handle(prepared_insert->stmt);
+        *(long long*)result = sqlite3_last_insert_rowid(db);
     }
-    sqlite3_step(prepared_insert->stmt);
     sqlite3_reset(prepared_insert->stmt);
 }
 
-void __ql__PreparedInsert_exec_array(PreparedInsert* prepared_insert, QLArray* array) {
+QLArray* __ql__PreparedInsert_exec_array(PreparedInsert* prepared_insert, QLArray* array) {
+    QLArray* results = __ql__QLArray_new(NULL, 0, prepared_insert->result_type_info);
+    void* result_ptr = malloc(prepared_insert->result_type_info->size);
     for (unsigned int i = 0; i < array->num_elems; i++) {
         void* elem_ptr = __ql__QLArray_index(array, i);
-        __ql__PreparedInsert_exec_row(prepared_insert, elem_ptr);
+        __ql__PreparedInsert_exec_row(prepared_insert, elem_ptr, result_ptr);
+        __ql__QLArray_append(results, result_ptr);
     }
+    free(result_ptr);
+    return results;
 }
 
 void __ql__PreparedInsert_finalize(PreparedInsert* prepared_insert) {
@@ -3,22 +3,32 @@
 
 #include <stdbool.h>
 #include "database.h"
+#include "definitions.h"
+#include "returning.h"
 
 typedef struct {
     QLTypeInfo* struct_type_info;
     char* table_name;
+    // Names of the columns given by each row, in the order of the row's fields
+    char** column_names;
+    ReturningClause* returning;
 } InsertPlan;
 
 typedef struct {
     sqlite3_stmt* stmt;
+    QueryOrigin origin;
     QLTypeInfo* struct_type_info;
+    // Type of a row's result, either the returned columns or an int rowid
+    QLTypeInfo* result_type_info;
+    bool has_returning;
 } PreparedInsert;
 
-InsertPlan* __ql__InsertPlan_new(char* table_name, QLTypeInfo* struct_type_info);
-PreparedInsert* __ql__InsertPlan_prepare(sqlite3* db, InsertPlan* plan);
+InsertPlan* __ql__InsertPlan_new(char* table_name, QLTypeInfo* struct_type_info, char** column_names);
+void __ql__InsertPlan_set_returning(InsertPlan* plan, ReturningClause* returning);
+PreparedInsert* __ql__InsertPlan_prepare(sqlite3* db, InsertPlan* plan, char* location);
 
-void __ql__PreparedInsert_exec_row(PreparedInsert* prepared_insert, void* row);
-void __ql__PreparedInsert_exec_array(PreparedInsert* prepared_insert, QLArray* array);
+void __ql__PreparedInsert_exec_row(PreparedInsert* prepared_insert, void* row, void* result);
+QLArray* __ql__PreparedInsert_exec_array(PreparedInsert* prepared_insert, QLArray* array);
 void __ql__PreparedInsert_finalize(PreparedInsert* prepared_insert);
 
 #endif
@@ -0,0 +1,64 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdbool.h>
+#include <sqlite3.h>
+#include "definitions.h"
+#include "transaction.h"
+#include "migration.h"
+
+static void __ql__Migration_fail(sqlite3* db, int version) {
+    fprintf(stderr, "Migration %d failed: %s\n", version, sqlite3_errmsg(db));
+    if (!sqlite3_get_autocommit(db)) {
+        sqlite3_exec(db, "ROLLBACK;", NULL, NULL, NULL);
+    }
+    exit(MIGRATION_FAILURE_STATUS);
+}
+
+static bool __ql__Migration_is_applied(sqlite3* db, int version) {
+    const char* sql = "CREATE TABLE IF NOT EXISTS __ql__migrations (version INTEGER PRIMARY KEY);";
+    if (sqlite3_exec(db, sql, NULL, NULL, NULL) != SQLITE_OK) {
+        __ql__Migration_fail(db, version);
+    }
+
+    sqlite3_stmt* stmt;
+    if (sqlite3_prepare_v2(db, "SELECT 1 FROM __ql__migrations WHERE version = ?1;", -1, &stmt, NULL) != SQLITE_OK) {
+        __ql__Migration_fail(db, version);
+    }
+    sqlite3_bind_int(stmt, 1, version);
+    int result = sqlite3_step(stmt);
+    sqlite3_finalize(stmt);
+    if (result != SQLITE_ROW && result != SQLITE_DONE) {
+        __ql__Migration_fail(db, version);
+    }
+    return result == SQLITE_ROW;
+}
+
+void __ql__Migration_run(sqlite3* db, int version, void (*migration)(void)) {
+    if (__ql__Migration_is_applied(db, version)) {
+        return;
+    }
+
+    bool is_savepoint = __ql__Transaction_begin(db);
+    migration();
+
+    // The version is recorded in the migration's transaction, so it is only marked applied on success
+    sqlite3_stmt* stmt;
+    if (sqlite3_prepare_v2(db, "INSERT INTO __ql__migrations VALUES (?1);", -1, &stmt, NULL) != SQLITE_OK) {
+        __ql__Migration_fail(db, version);
+    }
+    sqlite3_bind_int(stmt, 1, version);
+    int result = sqlite3_step(stmt);
+    sqlite3_finalize(stmt);
+    if (result != SQLITE_DONE) {
+        __ql__Migration_fail(db, version);
+    }
+    __ql__Transaction_commit(db, is_savepoint);
+}
+
+void __ql__Migration_change_schema(sqlite3* db, char* sql, char* table_name, char* location) {
+    QueryOrigin origin = { table_name, location };
+    sqlite3_stmt* stmt;
+    __ql__check_prepare(db, sqlite3_prepare_v2(db, sql, -1, &stmt, NULL), sql, origin);
+    __ql__check_step(stmt, sqlite3_step(stmt), origin);
+    sqlite3_finalize(stmt);
+}
@@ -0,0 +1,10 @@
+#ifndef RUNTIME_MIGRATION_H
+#define RUNTIME_MIGRATION_H
+
+#include "database.h"
+
+// Applies a migration in its own transaction, unless the database records it as already applied
+void __ql__Migration_run(sqlite3* db, int version, void (*migration)(void));
+void __ql__Migration_change_schema(sqlite3* db, char* sql, char* table_name, char* location);
+
+#endif
@@ -0,0 +1,31 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include "returning.h"
+
+ReturningClause* __ql__ReturningClause_new(QLTypeInfo* struct_type_info) {
+    ReturningClause* returning = malloc(sizeof(ReturningClause));
+    returning->struct_type_info = struct_type_info;
+    returning->column_names = NULL;
+    returning->num_columns = 0;
+    return returning;
+}
+
+void __ql__ReturningClause_add_column(ReturningClause* returning, char* column_name) {
+    returning->column_names = realloc(
+        returning->column_names,
+        (returning->num_columns + 1) * sizeof(char*)
+    );
+    returning->column_names[returning->num_columns] = column_name;
+    returning->num_columns++;
+}
+
+void __ql__ReturningClause_write_sql(SqlBuffer* buffer, ReturningClause* returning) {
+    for (unsigned int i = 0; i < returning->num_columns; i++) {
+        __ql__SqlBuffer_write(buffer, i == 0 ? " RETURNING %s" : ", %s", returning->column_names[i]);
+    }
+}
+
+void __ql__ReturningClause_free(ReturningClause* returning) {
+    free(returning->column_names);
+    free(returning);
+}
@@ -0,0 +1,20 @@
+#ifndef RUNTIME_RETURNING_H
+#define RUNTIME_RETURNING_H
+
+#include "../metadata.h"
+#include "sql_buffer.h"
+
+typedef struct {
+    QLTypeInfo* struct_type_info;
+    char** column_names;
+    unsigned int num_columns;
+} ReturningClause;
+
+ReturningClause* __ql__ReturningClause_new(QLTypeInfo* struct_type_info);
+void __ql__ReturningClause_add_column(ReturningClause* returning, char* column_name);
+
+// Columns are decoded by position, so the returned row struct needs no aliases
+void __ql__ReturningClause_write_sql(SqlBuffer* buffer, ReturningClause* returning);
+void __ql__ReturningClause_free(ReturningClause* returning);
+
+#endif
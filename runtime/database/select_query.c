@@ -12,69 +12,214 @@ SelectPlan* __ql__SelectPlan_new(char* table_name, QLTypeInfo* struct_type_info)
     SelectPlan* plan = malloc(sizeof(SelectPlan));
     plan->table_name = table_name;
     plan->struct_type_info = struct_type_info;
-    plan->has_where_clause = false;
+    plan->joins = NULL;
+    plan->num_joins = 0;
+    plan->is_distinct = false;
+    plan->columns = NULL;
+    plan->num_columns = 0;
+    plan->where_clause = NULL;
+    plan->group_by = NULL;
+    plan->num_group_by = 0;
+    plan->having = NULL;
+    plan->orderings = NULL;
+    plan->num_orderings = 0;
+    plan->has_limit = false;
+    plan->has_offset = false;
     return plan;
 }
 
-void __ql__SelectPlan_set_where(SelectPlan* plan, char* column_name){
-    plan->has_where_clause = true;
-    plan->where_column = column_name;
+void __ql__SelectPlan_add_join(SelectPlan* plan, bool is_left, char* table_name, WhereNode* condition) {
+    plan->joins = realloc(plan->joins, (plan->num_joins + 1) * sizeof(SelectJoin));
+    plan->joins[plan->num_joins].is_left = is_left;
+    plan->joins[plan->num_joins].table_name = table_name;
+    plan->joins[plan->num_joins].condition = condition;
+    plan->num_joins++;
 }
 
-PreparedSelect* __ql__SelectPlan_prepare(sqlite3* db, SelectPlan* plan) {
+void __ql__SelectPlan_set_distinct(SelectPlan* plan) {
+    plan->is_distinct = true;
+}
+
+static void __ql__SelectPlan_push_column(SelectPlan* plan, SelectColumn column) {
+    plan->columns = realloc(plan->columns, (plan->num_columns + 1) * sizeof(SelectColumn));
+    plan->columns[plan->num_columns] = column;
+    plan->num_columns++;
+}
+
+void __ql__SelectPlan_add_column(SelectPlan* plan, char* column_name, char* alias) {
+    SelectColumn column = { false, AGGREGATE_COUNT, column_name, alias };
+    __ql__SelectPlan_push_column(plan, column);
+}
+
+void __ql__SelectPlan_add_aggregate(SelectPlan* plan, AggregateType aggregate, char* column_name, char* alias) {
+    SelectColumn column = { true, aggregate, column_name, alias };
+    __ql__SelectPlan_push_column(plan, column);
+}
+
+void __ql__SelectPlan_set_where(SelectPlan* plan, WhereNode* where_clause) {
+    plan->where_clause = where_clause;
+}
+
+void __ql__SelectPlan_add_group_by(SelectPlan* plan, char* column_name) {
+    plan->group_by = realloc(plan->group_by, (plan->num_group_by + 1) * sizeof(char*));
+    plan->group_by[plan->num_group_by] = column_name;
+    plan->num_group_by++;
+}
+
+void __ql__SelectPlan_set_having(SelectPlan* plan, WhereNode* having) {
+    plan->having = having;
+}
+
+void __ql__SelectPlan_add_ordering(SelectPlan* plan, char* column_name, bool descending) {
+    plan->orderings = realloc(plan->orderings, (plan->num_orderings + 1) * sizeof(SelectOrdering));
+    plan->orderings[plan->num_orderings].column_name = column_name;
+    plan->orderings[plan->num_orderings].descending = descending;
+    plan->num_orderings++;
+}
+
+void __ql__SelectPlan_set_limit(SelectPlan* plan) {
+    plan->has_limit = true;
+}
+
+void __ql__SelectPlan_set_offset(SelectPlan* plan) {
+    plan->has_offset = true;
+}
+
+static const char* AGGREGATE_SQL[] = { "COUNT", "SUM", "AVG", "MIN", "MAX" };
+
+static void __ql__SelectColumn_write_sql(SqlBuffer* buffer, SelectColumn column, bool is_first) {
+    if (!is_first) {
+        __ql__SqlBuffer_write(buffer, ", ");
+    }
+    if (column.is_aggregate) {
+        char* argument = column.column_name != NULL ? column.column_name : "*";
+        __ql__SqlBuffer_write(buffer, "%s(%s)", AGGREGATE_SQL[column.aggregate], argument);
+    } else {
+        __ql__SqlBuffer_write(buffer, "%s", column.column_name);
+    }
+    // Aliases are always written for aggregates so HAVING can refer to them
+    if (column.is_aggregate || strcmp(column.column_name, column.alias) != 0) {
+        __ql__SqlBuffer_write(buffer, " AS %s", column.alias);
+    }
+}
+
+PreparedSelect* __ql__SelectPlan_prepare(sqlite3* db, SelectPlan* plan, char* location) {
     PreparedSelect* prepared_select = malloc(sizeof(PreparedSelect));
+    prepared_select->origin = (QueryOrigin){ plan->table_name, location };
     prepared_select->struct_type_info = plan->struct_type_info;
+    prepared_select->limit_param = 0;
+    prepared_select->offset_param = 0;
 
-    char sql[MAX_SQL_LENGTH];
-    if (plan->has_where_clause) {
-        sprintf(sql, "SELECT * FROM %s WHERE %s = ?1;", plan->table_name, plan->where_column);
-        sqlite3_prepare_v2(db, sql, -1, &prepared_select->stmt, NULL);
-    } else {
-        sprintf(sql, "SELECT * FROM %s;", plan->table_name);
-        sqlite3_prepare_v2(db, sql, -1, &prepared_select->stmt, NULL);
+    SqlBuffer sql = __ql__SqlBuffer_new();
+    unsigned int param_index = 1;
+    __ql__SqlBuffer_write(&sql, plan->is_distinct ? "SELECT DISTINCT " : "SELECT ");
+    if (plan->num_columns == 0) {
+        __ql__SqlBuffer_write(&sql, "*");
+    }
+    for (unsigned int i = 0; i < plan->num_columns; i++) {
+        __ql__SelectColumn_write_sql(&sql, plan->columns[i], i == 0);
+    }
+    __ql__SqlBuffer_write(&sql, " FROM %s", plan->table_name);
+    for (unsigned int i = 0; i < plan->num_joins; i++) {
+        SelectJoin join = plan->joins[i];
+        __ql__SqlBuffer_write(&sql, join.is_left ? " LEFT JOIN %s ON " : " JOIN %s ON ", join.table_name);
+        __ql__WhereNode_write_sql(&sql, join.condition, &param_index);
+        __ql__WhereNode_free(join.condition);
+    }
+    if (plan->where_clause != NULL) {
+        __ql__SqlBuffer_write(&sql, " WHERE ");
+        __ql__WhereNode_write_sql(&sql, plan->where_clause, &param_index);
+        __ql__WhereNode_free(plan->where_clause);
     }
+    for (unsigned int i = 0; i < plan->num_group_by; i++) {
+        __ql__SqlBuffer_write(&sql, i == 0 ? " GROUP BY %s" : ", %s", plan->group_by[i]);
+    }
+    prepared_select->having_param_offset = param_index - 1;
+    if (plan->having != NULL) {
+        __ql__SqlBuffer_write(&sql, " HAVING ");
+        __ql__WhereNode_write_sql(&sql, plan->having, &param_index);
+        __ql__WhereNode_free(plan->having);
+    }
+    for (unsigned int i = 0; i < plan->num_orderings; i++) {
+        SelectOrdering ordering = plan->orderings[i];
+        __ql__SqlBuffer_write(&sql, i == 0 ? " ORDER BY " : ", ");
+        __ql__SqlBuffer_write(&sql, "%s %s", ordering.column_name, ordering.descending ? "DESC" : "ASC");
+    }
+    if (plan->has_limit) {
+        prepared_select->limit_param = param_index++;
+        __ql__SqlBuffer_write(&sql, " LIMIT ?%u", prepared_select->limit_param);
+    } else if (plan->has_offset) {
+        // SQLite only accepts OFFSET after a LIMIT, where -1 means no limit
+        __ql__SqlBuffer_write(&sql, " LIMIT -1");
+    }
+    if (plan->has_offset) {
+        prepared_select->offset_param = param_index++;
+        __ql__SqlBuffer_write(&sql, " OFFSET ?%u", prepared_select->offset_param);
+    }
+    __ql__SqlBuffer_write(&sql, ";");
 
+    int result = sqlite3_prepare_v2(db, sql.data, -1, &prepared_select->stmt, NULL);
+    __ql__check_prepare(db, result, sql.data, prepared_select->origin);
+    __ql__SqlBuffer_free(&sql);
+    free(plan->joins);
+    free(plan->columns);
+    free(plan->group_by);
+    free(plan->orderings);
     free(plan);
     return prepared_select;
 }
 
-void __ql__PreparedSelect_bind_where(PreparedSelect* prepared_select, QLType value_type, void* value) {
-    __ql__bind_value(prepared_select->stmt, 1, value_type, value);
+void __ql__PreparedSelect_bind_where(
+    PreparedSelect* prepared_select,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+) {
+    __ql__bind_value(prepared_select->stmt, index + 1, value_type_info, value);
+}
+
+void __ql__PreparedSelect_bind_having(
+    PreparedSelect* prepared_select,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+) {
+    unsigned int param_index = prepared_select->having_param_offset + index + 1;
+    __ql__bind_value(prepared_select->stmt, param_index, value_type_info, value);
+}
+
+void __ql__PreparedSelect_bind_limit(PreparedSelect* prepared_select, int limit) {
+    sqlite3_bind_int(prepared_select->stmt, prepared_select->limit_param, limit);
+}
+
+void __ql__PreparedSelect_bind_offset(PreparedSelect* prepared_select, int offset) {
+    sqlite3_bind_int(prepared_select->stmt, prepared_select->offset_param, offset);
 }
 
 QLArray* __ql__PreparedSelect_execute(PreparedSelect* prepared_select) {
-    QLArray* results = __ql__QLArray_new(NULL, 0, prepared_select->struct_type_info);
-
-    int n_cols = prepared_select->struct_type_info->num_fields;
-    void* struct_ptr = malloc(prepared_select->struct_type_info->size);
-    while (sqlite3_step(prepared_select->stmt) == SQLITE_ROW) {
-        for (int i = 0; i < n_cols; i++) {
-            StructField field = prepared_select->struct_type_info->fields[i];
-            void* field_ptr = (char*)struct_ptr + field.offset;
-            int column_type = sqlite3_column_type(prepared_select->stmt, i);
-            switch (column_type) {
-                case SQLITE_TEXT: {
-                    const unsigned char* text = sqlite3_column_text(prepared_select->stmt, i);
-                    unsigned int length = sqlite3_column_bytes(prepared_select->stmt, i);
-                    QLString* val = __ql__QLString_new(malloc(length), length, false);
-                    memcpy(val->raw_string, text, length);
-                    *(QLString**)field_ptr = val;
-                    break;
-                }
-                case SQLITE_INTEGER: {
-                    int val = sqlite3_column_int(prepared_select->stmt, i);
-                    *(int*)field_ptr = val;
-                    break;
-                }
-            }
-            
-        }
-        __ql__QLArray_append(results, struct_ptr);
+    QLArray* results = __ql__collect_rows(prepared_select->stmt, prepared_select->struct_type_info, prepared_select->origin);
+    sqlite3_reset(prepared_select->stmt);
+    return results;
+}
+
+// Runs an aggregate query producing a single value, decoded into result
+void __ql__PreparedSelect_execute_scalar(PreparedSelect* prepared_select, void* result) {
+    StructField field = prepared_select->struct_type_info->fields[0];
+    field.offset = 0;
+    int step_result = sqlite3_step(prepared_select->stmt);
+    __ql__check_step(prepared_select->stmt, step_result, prepared_select->origin);
+    if (step_result == SQLITE_ROW) {
+        __ql__decode_column(prepared_select->stmt, 0, field, result);
     }
+    sqlite3_reset(prepared_select->stmt);
+}
 
-    free(struct_ptr);
+// Runs a query for the single row a reference points to, decoded into result
+void __ql__PreparedSelect_execute_row(PreparedSelect* prepared_select, void* result) {
+    int step_result = sqlite3_step(prepared_select->stmt);
+    __ql__check_row_found(prepared_select->stmt, step_result, prepared_select->origin);
+    __ql__decode_struct(prepared_select->stmt, 0, prepared_select->struct_type_info, result);
     sqlite3_reset(prepared_select->stmt);
-    return results;
 }
 
 void __ql__PreparedSelect_finalize(PreparedSelect* prepared_select) {
@@ -3,28 +3,95 @@
 
 #include <stdbool.h>
 #include "../metadata.h"
+#include "definitions.h"
+#include "where_clause.h"
 
 struct sqlite3;
 struct sqlite3_stmt;
 
+typedef enum {
+    AGGREGATE_COUNT,
+    AGGREGATE_SUM,
+    AGGREGATE_AVG,
+    AGGREGATE_MIN,
+    AGGREGATE_MAX,
+} AggregateType;
+
+typedef struct {
+    bool is_aggregate;
+    AggregateType aggregate;
+    char* column_name;
+    char* alias;
+} SelectColumn;
+
+typedef struct {
+    char* column_name;
+    bool descending;
+} SelectOrdering;
+
+typedef struct {
+    bool is_left;
+    char* table_name;
+    WhereNode* condition;
+} SelectJoin;
+
 typedef struct {
     QLTypeInfo* struct_type_info;
     char* table_name;
-    bool has_where_clause;
-    char* where_column;
+    SelectJoin* joins;
+    unsigned int num_joins;
+    bool is_distinct;
+    SelectColumn* columns;
+    unsigned int num_columns;
+    WhereNode* where_clause;
+    char** group_by;
+    unsigned int num_group_by;
+    WhereNode* having;
+    SelectOrdering* orderings;
+    unsigned int num_orderings;
+    bool has_limit;
+    bool has_offset;
 } SelectPlan;
 
 typedef struct {
     struct sqlite3_stmt* stmt;
+    QueryOrigin origin;
     QLTypeInfo* struct_type_info;
+    unsigned int having_param_offset;
+    unsigned int limit_param;
+    unsigned int offset_param;
 } PreparedSelect;
 
 SelectPlan* __ql__SelectPlan_new(char* table_name, QLTypeInfo* struct_type_info);
-void __ql__SelectPlan_set_where(SelectPlan* plan, char* column_name);
-PreparedSelect* __ql__SelectPlan_prepare(struct sqlite3* db, SelectPlan* plan);
+void __ql__SelectPlan_add_join(SelectPlan* plan, bool is_left, char* table_name, WhereNode* condition);
+void __ql__SelectPlan_set_distinct(SelectPlan* plan);
+void __ql__SelectPlan_add_column(SelectPlan* plan, char* column_name, char* alias);
+void __ql__SelectPlan_add_aggregate(SelectPlan* plan, AggregateType aggregate, char* column_name, char* alias);
+void __ql__SelectPlan_set_where(SelectPlan* plan, WhereNode* where_clause);
+void __ql__SelectPlan_add_group_by(SelectPlan* plan, char* column_name);
+void __ql__SelectPlan_set_having(SelectPlan* plan, WhereNode* having);
+void __ql__SelectPlan_add_ordering(SelectPlan* plan, char* column_name, bool descending);
+void __ql__SelectPlan_set_limit(SelectPlan* plan);
+void __ql__SelectPlan_set_offset(SelectPlan* plan);
+PreparedSelect* __ql__SelectPlan_prepare(struct sqlite3* db, SelectPlan* plan, char* location);
 
-void __ql__PreparedSelect_bind_where(PreparedSelect* prepared_select, QLType value_type, void* value);
+void __ql__PreparedSelect_bind_where(
+    PreparedSelect* prepared_select,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+);
+void __ql__PreparedSelect_bind_having(
+    PreparedSelect* prepared_select,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+);
+void __ql__PreparedSelect_bind_limit(PreparedSelect* prepared_select, int limit);
+void __ql__PreparedSelect_bind_offset(PreparedSelect* prepared_select, int offset);
 QLArray* __ql__PreparedSelect_execute(PreparedSelect* prepared_select);
+void __ql__PreparedSelect_execute_scalar(PreparedSelect* prepared_select, void* result);
+void __ql__PreparedSelect_execute_row(PreparedSelect* prepared_select, void* result);
 void __ql__PreparedSelect_finalize(PreparedSelect* prepared_select);
 
 #endif
\ No newline at end of file
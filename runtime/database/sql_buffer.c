@@ -0,0 +1,30 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <stdarg.h>
+#include "sql_buffer.h"
+
+SqlBuffer __ql__SqlBuffer_new(void) {
+    SqlBuffer buffer = { malloc(256), 0, 256 };
+    buffer.data[0] = '\0';
+    return buffer;
+}
+
+void __ql__SqlBuffer_write(SqlBuffer* buffer, const char* format, ...) {
+    va_list args;
+    va_start(args, format);
+    int length = vsnprintf(NULL, 0, format, args);
+    va_end(args);
+
+    while (buffer->length + length + 1 > buffer->capacity) {
+        buffer->capacity *= 2;
+        buffer->data = realloc(buffer->data, buffer->capacity);
+    }
+    va_start(args, format);
+    vsnprintf(buffer->data + buffer->length, buffer->capacity - buffer->length, format, args);
+    va_end(args);
+    buffer->length += length;
+}
+
+void __ql__SqlBuffer_free(SqlBuffer* buffer) {
+    free(buffer->data);
+}
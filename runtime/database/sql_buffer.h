@@ -0,0 +1,19 @@
+#ifndef RUNTIME_SQL_BUFFER_H
+#define RUNTIME_SQL_BUFFER_H
+
+#include <stddef.h>
+
+// SQL text of a statement, which grows as it is written, as conditions and columns
+// can make statements of any length
+typedef struct {
+    char* data;
+    size_t length;
+    size_t capacity;
+} SqlBuffer;
+
+SqlBuffer __ql__SqlBuffer_new(void);
+// Appends text formatted as by printf
+void __ql__SqlBuffer_write(SqlBuffer* buffer, const char* format, ...);
+void __ql__SqlBuffer_free(SqlBuffer* buffer);
+
+#endif
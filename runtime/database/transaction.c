@@ -0,0 +1,37 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include <sqlite3.h>
+#include "transaction.h"
+
+static void __ql__Transaction_exec(sqlite3* db, const char* sql) {
+    if (sqlite3_exec(db, sql, NULL, NULL, NULL) != SQLITE_OK) {
+        fprintf(stderr, "Transaction failed: %s\n", sqlite3_errmsg(db));
+        if (!sqlite3_get_autocommit(db)) {
+            sqlite3_exec(db, "ROLLBACK;", NULL, NULL, NULL);
+        }
+        exit(1);
+    }
+}
+
+bool __ql__Transaction_begin(sqlite3* db) {
+    // Outside of a transaction SQLite is in autocommit mode
+    if (sqlite3_get_autocommit(db)) {
+        __ql__Transaction_exec(db, "BEGIN;");
+        return false;
+    }
+    // Savepoints share a name, since RELEASE and ROLLBACK TO pick the most recent one
+    __ql__Transaction_exec(db, "SAVEPOINT __ql__savepoint;");
+    return true;
+}
+
+void __ql__Transaction_commit(sqlite3* db, bool is_savepoint) {
+    __ql__Transaction_exec(db, is_savepoint ? "RELEASE __ql__savepoint;" : "COMMIT;");
+}
+
+void __ql__Transaction_rollback(sqlite3* db, bool is_savepoint) {
+    // Rolling back to a savepoint keeps it on the stack, so it is released afterwards
+    __ql__Transaction_exec(
+        db,
+        is_savepoint ? "ROLLBACK TO __ql__savepoint; RELEASE __ql__savepoint;" : "ROLLBACK;"
+    );
+}
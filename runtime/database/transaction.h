@@ -0,0 +1,14 @@
+#ifndef RUNTIME_TRANSACTION_H
+#define RUNTIME_TRANSACTION_H
+
+#include <stdbool.h>
+
+struct sqlite3;
+
+// Starts a transaction, or a savepoint when one is already open on the database.
+// Returns whether a savepoint was created
+bool __ql__Transaction_begin(struct sqlite3* db);
+void __ql__Transaction_commit(struct sqlite3* db, bool is_savepoint);
+void __ql__Transaction_rollback(struct sqlite3* db, bool is_savepoint);
+
+#endif
@@ -3,6 +3,7 @@
 #include <stdbool.h>
 #include <sqlite3.h>
 #include "../metadata.h"
+#include "../array.h"
 #include "definitions.h"
 #include "update_query.h"
 
@@ -15,56 +16,84 @@ UpdatePlan* __ql__UpdatePlan_new(
     plan->table_name = table_name;
     plan->num_assignments = num_assignments;
     plan->assign_columns = assign_columns;
-    plan->has_where_clause = false;
+    plan->where_clause = NULL;
+    plan->returning = NULL;
     return plan;
 }
 
-void __ql__UpdatePlan_set_where(UpdatePlan* plan, char* column_name) {
-    plan->has_where_clause = true;
-    plan->where_column = column_name;
+void __ql__UpdatePlan_set_where(UpdatePlan* plan, WhereNode* where_clause) {
+    plan->where_clause = where_clause;
 }
 
-PreparedUpdate* __ql__UpdatePlan_prepare(sqlite3* db, UpdatePlan* plan) {
+void __ql__UpdatePlan_set_returning(UpdatePlan* plan, ReturningClause* returning) {
+    plan->returning = returning;
+}
+
+PreparedUpdate* __ql__UpdatePlan_prepare(sqlite3* db, UpdatePlan* plan, char* location) {
     PreparedUpdate* prepared_update = malloc(sizeof(PreparedUpdate));
+    prepared_update->origin = (QueryOrigin){ plan->table_name, location };
+    prepared_update->num_assignments = plan->num_assignments;
+    prepared_update->returning_type_info = NULL;
 
-    char sql[MAX_SQL_LENGTH];
-    char* writer = sql;
+    SqlBuffer sql = __ql__SqlBuffer_new();
 
     // Build SET clause
-    writer += sprintf(writer, "UPDATE %s SET ", plan->table_name);
+    __ql__SqlBuffer_write(&sql, "UPDATE %s SET ", plan->table_name);
     for (unsigned int i = 0; i < plan->num_assignments; i++) {
-        if (i > 0) writer += sprintf(writer, ", ");
-        writer += sprintf(writer, "%s = ?%d", plan->assign_columns[i], i + 2);
+        if (i > 0) __ql__SqlBuffer_write(&sql, ", ");
+        __ql__SqlBuffer_write(&sql, "%s = ?%d", plan->assign_columns[i], i + 1);
     }
     
-    // Add WHERE clause if present
-    if (plan->has_where_clause) {
-        writer += sprintf(writer, " WHERE %s = ?1;", plan->where_column);
-    } else {
-        writer += sprintf(writer, ";");
+    // Add WHERE clause if present, numbering its parameters after the assignments
+    if (plan->where_clause != NULL) {
+        unsigned int param_index = plan->num_assignments + 1;
+        __ql__SqlBuffer_write(&sql, " WHERE ");
+        __ql__WhereNode_write_sql(&sql, plan->where_clause, &param_index);
+        __ql__WhereNode_free(plan->where_clause);
+    }
+    if (plan->returning != NULL) {
+        prepared_update->returning_type_info = plan->returning->struct_type_info;
+        __ql__ReturningClause_write_sql(&sql, plan->returning);
+        __ql__ReturningClause_free(plan->returning);
     }
+    __ql__SqlBuffer_write(&sql, ";");
     
-    sqlite3_prepare_v2(db, sql, -1, &prepared_update->stmt, NULL);
+    int result = sqlite3_prepare_v2(db, sql.data, -1, &prepared_update->stmt, NULL);
+    __ql__check_prepare(db, result, sql.data, prepared_update->origin);
+    __ql__SqlBuffer_free(&sql);
     free(plan);
     return prepared_update;
 }
 
-void __ql__PreparedUpdate_bind_where(PreparedUpdate* prepared_update, QLType value_type, void* value) {
-    __ql__bind_value(prepared_update->stmt, 1, value_type, value);
+void __ql__PreparedUpdate_bind_where(
+    PreparedUpdate* prepared_update,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+) {
+    __ql__bind_value(prepared_update->stmt, prepared_update->num_assignments + index + 1, value_type_info, value);
 }
 
 void __ql__PreparedUpdate_bind_assignment(
     PreparedUpdate* prepared_update,
     unsigned int index,
-    QLType value_type,
+    QLTypeInfo* value_type_info,
     void* value
 ) {
-    __ql__bind_value(prepared_update->stmt, index + 2, value_type, value);
+    __ql__bind_value(prepared_update->stmt, index + 1, value_type_info, value);
+}
+
+// Returns the number of updated rows
+int __ql__PreparedUpdate_exec(PreparedUpdate* prepared_update) {
+    __ql__check_step(prepared_update->stmt, sqlite3_step(prepared_update->stmt), prepared_update->origin);
+    sqlite3_reset(prepared_update->stmt);
+    return sqlite3_changes(sqlite3_db_handle(prepared_update->stmt));
 }
 
-void __ql__PreparedUpdate_exec(PreparedUpdate* prepared_update) {
-    sqlite3_step(prepared_update->stmt);
+QLArray* __ql__PreparedUpdate_exec_returning(PreparedUpdate* prepared_update) {
+    QLArray* results = __ql__collect_rows(prepared_update->stmt, prepared_update->returning_type_info, prepared_update->origin);
     sqlite3_reset(prepared_update->stmt);
+    return results;
 }
 
 void __ql__PreparedUpdate_finalize(PreparedUpdate* prepared_update) {
@@ -3,13 +3,16 @@
 
 #include <stdbool.h>
 #include "../metadata.h"
+#include "definitions.h"
+#include "where_clause.h"
+#include "returning.h"
 
 typedef struct {
     char* table_name;
     unsigned int num_assignments;
     char** assign_columns;
-    bool has_where_clause;
-    char* where_column;
+    WhereNode* where_clause;
+    ReturningClause* returning;
 } UpdatePlan;
 
 UpdatePlan* __ql__UpdatePlan_new(
@@ -20,19 +23,29 @@ UpdatePlan* __ql__UpdatePlan_new(
 
 typedef struct {
     sqlite3_stmt* stmt;
+    QueryOrigin origin;
+    unsigned int num_assignments;
+    QLTypeInfo* returning_type_info;
 } PreparedUpdate;
 
-void __ql__UpdatePlan_set_where(UpdatePlan* plan, char* column_name);
-PreparedUpdate* __ql__UpdatePlan_prepare(sqlite3* db, UpdatePlan* plan);
+void __ql__UpdatePlan_set_where(UpdatePlan* plan, WhereNode* where_clause);
+void __ql__UpdatePlan_set_returning(UpdatePlan* plan, ReturningClause* returning);
+PreparedUpdate* __ql__UpdatePlan_prepare(sqlite3* db, UpdatePlan* plan, char* location);
 
-void __ql__PreparedUpdate_bind_where(PreparedUpdate* prepared_update, QLType value_type, void* value);
+void __ql__PreparedUpdate_bind_where(
+    PreparedUpdate* prepared_update,
+    unsigned int index,
+    QLTypeInfo* value_type_info,
+    void* value
+);
 void __ql__PreparedUpdate_bind_assignment(
     PreparedUpdate* prepared_update,
     unsigned int index,
-    QLType value_type,
+    QLTypeInfo* value_type_info,
     void* value
 );
-void __ql__PreparedUpdate_exec(PreparedUpdate* prepared_update);
+int __ql__PreparedUpdate_exec(PreparedUpdate* prepared_update);
+QLArray* __ql__PreparedUpdate_exec_returning(PreparedUpdate* prepared_update);
 void __ql__PreparedUpdate_finalize(PreparedUpdate* prepared_update);
 
 #endif
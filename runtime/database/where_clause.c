@@ -0,0 +1,114 @@
+#include <stdio.h>
+#include <stdlib.h>
+#include "where_clause.h"
+
+WhereNode* __ql__WhereNode_new_condition(WhereNodeType type, char* column_name) {
+    WhereNode* node = malloc(sizeof(WhereNode));
+    node->type = type;
+    node->column_name = column_name;
+    node->other_column_name = NULL;
+    node->left = NULL;
+    node->right = NULL;
+    return node;
+}
+
+WhereNode* __ql__WhereNode_new_column_comparison(WhereNodeType type, char* column_name, char* other_column_name) {
+    WhereNode* node = __ql__WhereNode_new_condition(type, column_name);
+    node->other_column_name = other_column_name;
+    return node;
+}
+
+WhereNode* __ql__WhereNode_new_logical(WhereNodeType type, WhereNode* left, WhereNode* right) {
+    WhereNode* node = malloc(sizeof(WhereNode));
+    node->type = type;
+    node->column_name = NULL;
+    node->other_column_name = NULL;
+    node->left = left;
+    node->right = right;
+    return node;
+}
+
+static const char* __ql__comparison_operator(WhereNodeType type) {
+    switch (type) {
+        case WHERE_EQUAL: return "=";
+        case WHERE_NOT_EQUAL: return "<>";
+        case WHERE_GREATER_THAN: return ">";
+        case WHERE_LESS_THAN: return "<";
+        case WHERE_GREATER_THAN_OR_EQUAL: return ">=";
+        case WHERE_LESS_THAN_OR_EQUAL: return "<=";
+        case WHERE_LIKE: return "LIKE";
+        default: return NULL;
+    }
+}
+
+// Writes the operands of a chain of one logical operator, such as a OR b OR c, within a single
+// pair of parentheses, as SQLite limits how deeply they nest
+static void __ql__WhereNode_write_chain(
+    SqlBuffer* buffer,
+    WhereNode* node,
+    WhereNodeType type,
+    unsigned int* param_index
+) {
+    if (node->type != type) {
+        __ql__WhereNode_write_sql(buffer, node, param_index);
+        return;
+    }
+    __ql__WhereNode_write_chain(buffer, node->left, type, param_index);
+    __ql__SqlBuffer_write(buffer, type == WHERE_AND ? " AND " : " OR ");
+    __ql__WhereNode_write_chain(buffer, node->right, type, param_index);
+}
+
+void __ql__WhereNode_write_sql(SqlBuffer* buffer, WhereNode* node, unsigned int* param_index) {
+    switch (node->type) {
+        case WHERE_IN:
+            // Arrays are bound as JSON text, so one parameter covers every element
+            __ql__SqlBuffer_write(buffer, "%s IN (SELECT value FROM json_each(?%u))", node->column_name, (*param_index)++);
+            break;
+        case WHERE_IS_NULL:
+            __ql__SqlBuffer_write(buffer, "%s IS NULL", node->column_name);
+            break;
+        case WHERE_IS_NOT_NULL:
+            __ql__SqlBuffer_write(buffer, "%s IS NOT NULL", node->column_name);
+            break;
+        case WHERE_AND:
+        case WHERE_OR:
+            __ql__SqlBuffer_write(buffer, "(");
+            __ql__WhereNode_write_chain(buffer, node, node->type, param_index);
+            __ql__SqlBuffer_write(buffer, ")");
+            break;
+        case WHERE_NOT:
+            __ql__SqlBuffer_write(buffer, "NOT (");
+            __ql__WhereNode_write_sql(buffer, node->left, param_index);
+            __ql__SqlBuffer_write(buffer, ")");
+            break;
+        default:
+            if (node->other_column_name != NULL) {
+                __ql__SqlBuffer_write(
+                    buffer,
+                    "%s %s %s",
+                    node->column_name,
+                    __ql__comparison_operator(node->type),
+                    node->other_column_name
+                );
+                break;
+            }
+            __ql__SqlBuffer_write(
+                buffer,
+                "%s %s ?%u",
+                node->column_name,
+                __ql__comparison_operator(node->type),
+                (*param_index)++
+            );
+            break;
+    }
+}
+
+void __ql__WhereNode_free(WhereNode* node) {
+    if (node->left != NULL) {
+        __ql__WhereNode_free(node->left);
+    }
+    if (node->right != NULL) {
+        __ql__WhereNode_free(node->right);
+    }
+    free(node);
+}
@@ -0,0 +1,40 @@
+#ifndef RUNTIME_WHERE_CLAUSE_H
+#define RUNTIME_WHERE_CLAUSE_H
+
+#include <stdbool.h>
+#include "sql_buffer.h"
+
+typedef enum {
+    WHERE_EQUAL,
+    WHERE_NOT_EQUAL,
+    WHERE_GREATER_THAN,
+    WHERE_LESS_THAN,
+    WHERE_GREATER_THAN_OR_EQUAL,
+    WHERE_LESS_THAN_OR_EQUAL,
+    WHERE_LIKE,
+    WHERE_IN,
+    WHERE_IS_NULL,
+    WHERE_IS_NOT_NULL,
+    WHERE_AND,
+    WHERE_OR,
+    WHERE_NOT
+} WhereNodeType;
+
+typedef struct WhereNode {
+    WhereNodeType type;
+    char* column_name;
+    // Set when the condition compares two columns rather than a column and a parameter
+    char* other_column_name;
+    struct WhereNode* left;
+    struct WhereNode* right;
+} WhereNode;
+
+WhereNode* __ql__WhereNode_new_condition(WhereNodeType type, char* column_name);
+WhereNode* __ql__WhereNode_new_column_comparison(WhereNodeType type, char* column_name, char* other_column_name);
+WhereNode* __ql__WhereNode_new_logical(WhereNodeType type, WhereNode* left, WhereNode* right);
+
+// Writes the node as SQL, numbering its parameters from *param_index onward
+void __ql__WhereNode_write_sql(SqlBuffer* buffer, WhereNode* node, unsigned int* param_index);
+void __ql__WhereNode_free(WhereNode* node);
+
+#endif
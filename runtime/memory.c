@@ -1,3 +1,4 @@
+#include <stddef.h>
 #include "qlstring.h"
 #include "array.h"
 #include "callable.h"
@@ -25,6 +26,22 @@ void __ql__drop_value(void* value_ptr, QLTypeInfo* type_info) {
             __ql__QLCallable_remove_ref(callable);
             break;
         }
+        case TYPE_OPTIONAL: {
+            // Only a present optional holds a value to drop
+            if (*(bool*)value_ptr) {
+                StructField inner = type_info->fields[1];
+                __ql__drop_value((char*)value_ptr + inner.offset, inner.type_info);
+            }
+            break;
+        }
+        case TYPE_ENUM: {
+            // Enums start with the tag of their variant, whose payload is the only one to drop
+            StructField payload = type_info->fields[*(unsigned int*)value_ptr];
+            if (payload.type_info != NULL) {
+                __ql__drop_value((char*)value_ptr + payload.offset, payload.type_info);
+            }
+            break;
+        }
         default:
             // Primitive types don't require special handling
             break;
@@ -3,11 +3,15 @@
 
 typedef enum {
     TYPE_INT,
+    TYPE_LONG,
+    TYPE_FLOAT,
     TYPE_BOOL,
     TYPE_STRING,
     TYPE_ARRAY,
     TYPE_STRUCT,
-    TYPE_CALLABLE
+    TYPE_CALLABLE,
+    TYPE_OPTIONAL,
+    TYPE_ENUM
 } QLType;
 
 typedef struct QLTypeInfo QLTypeInfo;
@@ -22,6 +26,8 @@ typedef struct QLTypeInfo {
     unsigned long size;
     unsigned int num_fields;
     StructField* fields;
+    // Names of an enum's variants, indexed by tag. Enums hold one field per variant for its payload
+    char** variant_names;
 } QLTypeInfo;
 
 #endif
\ No newline at end of file
@@ -1,4 +1,5 @@
 #include <stdio.h>
+#include <stdlib.h>
 #include <stdbool.h>
 #include "metadata.h"
 #include "primitives.h"
@@ -8,6 +9,16 @@ QLTypeInfo __ql__int_type_info = {
     .size = sizeof(int)
 };
 
+QLTypeInfo __ql__long_type_info = {
+    .type = TYPE_LONG,
+    .size = sizeof(long long)
+};
+
+QLTypeInfo __ql__float_type_info = {
+    .type = TYPE_FLOAT,
+    .size = sizeof(double)
+};
+
 QLTypeInfo __ql__bool_type_info = {
     .type = TYPE_BOOL,
     .size = sizeof(bool)
@@ -17,6 +28,15 @@ void printi(int x) {
     printf("%d\n", x);
 }
 
+void printl(long long x) {
+    printf("%lld\n", x);
+}
+
+// Named apart from the C library's printf, which ql's printf would otherwise clash with
+void __ql__printf(double x) {
+    printf("%.15g\n", x);
+}
+
 void printb(bool x) {
     printf("%s\n", x ? "true" : "false");
 }
@@ -27,3 +47,25 @@ int inputi() {
     while(getchar() != '\n');
     return x;
 }
+
+// Reached from programs built with checked arithmetic when an integer operation overflows
+void __ql__arithmetic_overflow(const char* location) {
+    fprintf(stderr, "Integer overflow at %s\n", location);
+    exit(ARITHMETIC_FAILURE_STATUS);
+}
+
+void __ql__division_by_zero(const char* location) {
+    fprintf(stderr, "Division by zero at %s\n", location);
+    exit(ARITHMETIC_FAILURE_STATUS);
+}
+
+// Reached when a float converted to an int or long is NaN, infinite or out of its range
+void __ql__invalid_float_conversion(const char* location) {
+    fprintf(stderr, "Float out of range for conversion at %s\n", location);
+    exit(ARITHMETIC_FAILURE_STATUS);
+}
+
+void __ql__invalid_range_step(const char* location) {
+    fprintf(stderr, "Range step must be positive at %s\n", location);
+    exit(RANGE_FAILURE_STATUS);
+}
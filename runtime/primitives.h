@@ -3,11 +3,22 @@
 
 #include "metadata.h"
 
+#define ARITHMETIC_FAILURE_STATUS 6
+#define RANGE_FAILURE_STATUS 7
+
 extern QLTypeInfo __ql__int_type_info;
+extern QLTypeInfo __ql__long_type_info;
+extern QLTypeInfo __ql__float_type_info;
 extern QLTypeInfo __ql__bool_type_info;
 
 void printi(int);
+void printl(long long);
+void __ql__printf(double);
 void printb(bool);
 int inputi();
+void __ql__arithmetic_overflow(const char* location);
+void __ql__division_by_zero(const char* location);
+void __ql__invalid_float_conversion(const char* location);
+void __ql__invalid_range_step(const char* location);
 
 #endif
\ No newline at end of file
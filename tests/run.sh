@@ -0,0 +1,56 @@
+#!/bin/sh
+# Builds and runs every program in tests/, comparing what it prints with the .out file beside it.
+# Compiler flags for a program go in a .flags file beside it, and each datasource it or the modules
+# it imports declare is given a fresh database. Programs expected to fail have an .err file holding
+# what they write to stderr, either when compiled, for programs without an .out file, or when run.
+# Modules that tests import go in subdirectories, so they are not run themselves
+compiler=$1
+status=0
+mkdir -p out/tests
+
+# Prints the path of a module and of every module it imports, directly or not
+module_paths() {
+    realpath "$1"
+    sed -n -e 's/^import "\(.*\)";/\1/p' -e 's/^import \(.*\);/\1.ql/p' "$1" | sed 's#::#/#g' | while read -r path; do
+        module_paths "$(dirname "$1")/$path"
+    done
+}
+
+for src in tests/*.ql; do
+    name=$(basename "$src" .ql)
+    flags=$(cat "tests/$name.flags" 2>/dev/null)
+    # Modules imported more than once are loaded once
+    datasource_count=$(module_paths "$src" | sort -u | while read -r path; do
+        cat "$path"
+    done | grep -cE '^(readonly )?datasource')
+    dbs=""
+    for i in $(seq "$datasource_count"); do
+        rm -f "out/tests/$name$i.db"
+        dbs="$dbs out/tests/$name$i.db"
+    done
+
+    failed=0
+    if "$compiler" "$src" "out/tests/$name" $flags 2> "out/tests/$name.err"; then
+        "out/tests/$name" $dbs > "out/tests/$name.out" 2> "out/tests/$name.err"
+        run_status=$?
+        diff -u "tests/$name.out" "out/tests/$name.out" || failed=1
+        if [ -f "tests/$name.err" ]; then
+            [ $run_status -ne 0 ] && diff -u "tests/$name.err" "out/tests/$name.err" || failed=1
+        elif [ $run_status -ne 0 ]; then
+            cat "out/tests/$name.err"
+            failed=1
+        fi
+    elif [ -f "tests/$name.err" ] && [ ! -f "tests/$name.out" ]; then
+        # The compiler ends with the error its main function returns, which says nothing of the cause
+        grep -v '^Error: ' "out/tests/$name.err" | diff -u "tests/$name.err" - || failed=1
+    else
+        cat "out/tests/$name.err"
+        failed=1
+    fi
+
+    if [ $failed -ne 0 ]; then
+        echo "FAILED: $name"
+        status=1
+    fi
+done
+exit $status